@@ -0,0 +1,239 @@
+//! 输出音频格式
+//!
+//! `/synthesize` 的 `format` 参数: wav (默认, 16-bit PCM) / mp3 (lame, 可选码率) /
+//! ogg (Ogg Opus, libopus, 可选码率) / flac (无损)。
+//! wav 可用 `sample_format` 选择 s16 (默认) / s24 / f32。
+//! `sample_rate` 参数先把模型输出 (24 kHz) 转换到请求的采样率再编码。
+//! 格式、码率、样本格式和采样率进入缓存键, 缓存文件使用各自的扩展名。
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::flac;
+use crate::mp3_encoder;
+use crate::opus_encoder::{self, OpusError};
+use crate::resampler;
+use crate::subprocess::SubprocessError;
+use crate::tts_engine;
+use crate::wav_encoder::{self, PcmFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    #[default]
+    Wav,
+    Mp3,
+    Ogg,
+    Flac,
+}
+
+impl AudioFormat {
+    pub const ALL: [AudioFormat; 4] = [AudioFormat::Wav, AudioFormat::Mp3, AudioFormat::Ogg, AudioFormat::Flac];
+
+    /// 缓存文件扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::Flac => "flac",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Ogg => "audio/ogg",
+            AudioFormat::Flac => "audio/flac",
+        }
+    }
+
+    /// 无损格式可以互相转换而不损失质量
+    pub fn is_lossless(self) -> bool {
+        matches!(self, AudioFormat::Wav | AudioFormat::Flac)
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.extension() == extension)
+    }
+}
+
+impl fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// 编码参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    pub format: AudioFormat,
+    /// 有损格式的码率 (kbps), 缺省使用格式的默认值
+    pub bitrate: Option<u32>,
+    /// 输出采样率 (Hz), 缺省使用模型的采样率
+    pub sample_rate: Option<u32>,
+    /// WAV 样本格式
+    pub sample_format: PcmFormat,
+}
+
+impl EncodeOptions {
+    /// 实际输出的采样率
+    pub fn output_rate(&self) -> u32 {
+        self.sample_rate.unwrap_or(tts_engine::SAMPLE_RATE)
+    }
+
+    /// 校验参数组合
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(sample_rate) = self.sample_rate {
+            resampler::validate_rate(sample_rate)?;
+        }
+        if self.sample_format != PcmFormat::S16 && self.format != AudioFormat::Wav {
+            return Err("sample_format 只适用于 wav".to_string());
+        }
+        match (self.format, self.bitrate) {
+            (AudioFormat::Wav | AudioFormat::Flac, Some(_)) => Err("bitrate 只适用于有损格式 (mp3/ogg)".to_string()),
+            (AudioFormat::Mp3, Some(bitrate)) => mp3_encoder::validate_bitrate(bitrate, self.output_rate()),
+            (AudioFormat::Ogg, Some(bitrate)) => opus_encoder::validate_bitrate(bitrate),
+            (_, None) => Ok(()),
+        }
+    }
+
+    /// 缓存键后缀 (默认 WAV 为空, 已有缓存文件继续有效)
+    pub fn cache_suffix(&self) -> String {
+        let format = match (self.format, self.sample_format) {
+            (AudioFormat::Wav, PcmFormat::S16) => String::new(),
+            (AudioFormat::Wav, pcm) => format!("|pcm:{}", pcm.name()),
+            (AudioFormat::Mp3, _) => format!("|format:mp3:{}", self.bitrate.unwrap_or(mp3_encoder::DEFAULT_BITRATE)),
+            (AudioFormat::Ogg, _) => format!("|format:ogg:{}", self.bitrate.unwrap_or(opus_encoder::DEFAULT_BITRATE)),
+            (AudioFormat::Flac, _) => "|format:flac".to_string(),
+        };
+        match self.sample_rate {
+            Some(sample_rate) => format!("{}|rate:{}", format, sample_rate),
+            None => format,
+        }
+    }
+}
+
+/// 按请求的采样率和格式编码
+pub fn encode(audio: &[f32], sample_rate: u32, options: &EncodeOptions) -> Result<Vec<u8>> {
+    let resampled;
+    let (audio, sample_rate) = match options.sample_rate {
+        Some(rate) if rate != sample_rate => {
+            resampled = resampler::resample(audio, sample_rate, rate);
+            (resampled.as_slice(), rate)
+        }
+        _ => (audio, sample_rate),
+    };
+    match options.format {
+        AudioFormat::Wav => wav_encoder::encode_wav_as(audio, sample_rate, options.sample_format),
+        AudioFormat::Mp3 => {
+            mp3_encoder::encode_mp3(audio, sample_rate, options.bitrate.unwrap_or(mp3_encoder::DEFAULT_BITRATE))
+        }
+        AudioFormat::Ogg => {
+            opus_encoder::encode_opus(audio, sample_rate, options.bitrate.unwrap_or(opus_encoder::DEFAULT_BITRATE))
+        }
+        AudioFormat::Flac => flac::encode_flac(&wav_encoder::to_pcm16(audio), sample_rate),
+    }
+}
+
+/// 无损格式之间转换 (wav ↔ flac), 样本完全一致
+pub fn transcode_lossless(data: &[u8], from: AudioFormat, to: AudioFormat) -> Result<Vec<u8>> {
+    let (samples, sample_rate) = match from {
+        AudioFormat::Wav => wav_encoder::decode_wav_pcm16(data)?,
+        AudioFormat::Flac => flac::decode_flac(data)?,
+        _ => bail!("{} 不是无损格式", from),
+    };
+    match to {
+        AudioFormat::Wav => wav_encoder::encode_wav_pcm16(&samples, sample_rate),
+        AudioFormat::Flac => flac::encode_flac(&samples, sample_rate),
+        _ => bail!("{} 不是无损格式", to),
+    }
+}
+
+/// 编码器没有安装 (如缺少 lame 或 libopus)
+pub fn is_unavailable(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref(), Some(SubprocessError::NotInstalled { .. }))
+        || matches!(e.downcast_ref(), Some(OpusError::NotInstalled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcode_lossless() {
+        let audio: Vec<f32> = (0..6000).map(|i| (i as f32 * 0.02).sin() * 0.8).collect();
+        let wav = encode(&audio, 24000, &EncodeOptions::default()).unwrap();
+        let flac = transcode_lossless(&wav, AudioFormat::Wav, AudioFormat::Flac).unwrap();
+        assert!(flac.starts_with(b"fLaC") && flac.len() < wav.len());
+        assert_eq!(transcode_lossless(&flac, AudioFormat::Flac, AudioFormat::Wav).unwrap(), wav);
+        assert!(transcode_lossless(&wav, AudioFormat::Wav, AudioFormat::Mp3).is_err());
+    }
+
+    #[test]
+    fn test_encode_sample_rate() {
+        let audio = vec![0.25f32; 24000];
+        let options = EncodeOptions { sample_rate: Some(16000), ..Default::default() };
+        let (samples, rate) = wav_encoder::decode_wav_pcm16(&encode(&audio, 24000, &options).unwrap()).unwrap();
+        assert_eq!((samples.len(), rate), (16000, 16000));
+    }
+
+    #[test]
+    fn test_format_metadata() {
+        assert_eq!(AudioFormat::from_extension("mp3"), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::from_extension("exe"), None);
+        assert_eq!(AudioFormat::Mp3.content_type(), "audio/mpeg");
+        assert_eq!(AudioFormat::from_extension("ogg"), Some(AudioFormat::Ogg));
+        assert_eq!(AudioFormat::Ogg.content_type(), "audio/ogg");
+        let parsed: AudioFormat = serde_json::from_str(r#""mp3""#).unwrap();
+        assert_eq!(parsed, AudioFormat::Mp3);
+        assert!(serde_json::from_str::<AudioFormat>(r#""aiff""#).is_err());
+    }
+
+    #[test]
+    fn test_options() {
+        let wav = EncodeOptions::default();
+        let mp3 = EncodeOptions { format: AudioFormat::Mp3, ..Default::default() };
+        let mp3_128 = EncodeOptions { format: AudioFormat::Mp3, bitrate: Some(128), ..Default::default() };
+
+        assert_eq!(wav.cache_suffix(), "");
+        assert_ne!(mp3.cache_suffix(), mp3_128.cache_suffix());
+        assert_eq!(mp3.cache_suffix(), EncodeOptions { bitrate: Some(64), ..mp3 }.cache_suffix());
+
+        assert!(mp3_128.validate().is_ok());
+        assert!(EncodeOptions { bitrate: Some(100), ..mp3 }.validate().is_err());
+        assert!(EncodeOptions { bitrate: Some(64), ..wav }.validate().is_err());
+
+        let ogg = EncodeOptions { format: AudioFormat::Ogg, ..Default::default() };
+        assert_ne!(ogg.cache_suffix(), mp3.cache_suffix());
+        assert!(EncodeOptions { bitrate: Some(100), ..ogg }.validate().is_ok());
+        assert!(EncodeOptions { bitrate: Some(1), ..ogg }.validate().is_err());
+
+        let flac = EncodeOptions { format: AudioFormat::Flac, ..Default::default() };
+        assert!(flac.validate().is_ok());
+        assert!(EncodeOptions { bitrate: Some(64), ..flac }.validate().is_err());
+        assert_ne!(flac.cache_suffix(), wav.cache_suffix());
+
+        let wav_16k = EncodeOptions { sample_rate: Some(16000), ..wav };
+        assert!(wav_16k.validate().is_ok());
+        assert_eq!(wav_16k.cache_suffix(), "|rate:16000");
+        assert_ne!(EncodeOptions { sample_rate: Some(16000), ..mp3 }.cache_suffix(), mp3.cache_suffix());
+        assert!(EncodeOptions { sample_rate: Some(11025), ..wav }.validate().is_err());
+
+        // MP3 码率受输出采样率限制: 24 kHz 是 MPEG-2, 最高 160 kbps
+        assert!(EncodeOptions { bitrate: Some(192), ..mp3 }.validate().is_err());
+        assert!(EncodeOptions { bitrate: Some(160), ..mp3 }.validate().is_ok());
+        assert!(EncodeOptions { bitrate: Some(320), sample_rate: Some(48000), ..mp3 }.validate().is_ok());
+        assert!(EncodeOptions { bitrate: Some(320), sample_rate: Some(16000), ..mp3 }.validate().is_err());
+        assert!(EncodeOptions { bitrate: Some(8), sample_rate: Some(44100), ..mp3 }.validate().is_err());
+
+        let wav_24 = EncodeOptions { sample_format: PcmFormat::S24, ..wav };
+        assert!(wav_24.validate().is_ok());
+        assert_eq!(wav_24.cache_suffix(), "|pcm:s24");
+        assert!(EncodeOptions { sample_format: PcmFormat::F32, ..flac }.validate().is_err());
+        let parsed: PcmFormat = serde_json::from_str(r#""f32""#).unwrap();
+        assert_eq!(parsed, PcmFormat::F32);
+    }
+}
@@ -1,23 +1,47 @@
-/// 音频缓存模块
-///
-/// 功能:
-/// - SHA256 文本哈希 → 文件 ID
-/// - 缓存 WAV 文件到磁盘
-/// - 自动过期清理 (1 小时 TTL)
-/// - 线程安全访问
+//! 音频缓存模块
+//!
+//! 功能:
+//! - SHA256 文本哈希 → 文件 ID
+//! - 缓存音频文件到磁盘 (按格式使用 .wav / .mp3 / .ogg / .flac 扩展名)
+//! - 可选以 FLAC 存储无损格式, 读取时转回请求的格式 (约节省一半磁盘)
+//! - 自动过期清理 (1 小时 TTL), previews/ 子目录中的声音试听不参与清理
+//! - 线程安全访问
 
 use anyhow::{Context, Result};
 use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use tracing::{debug, info, warn};
 
+use crate::audio_format::{self, AudioFormat};
+use crate::wav_encoder;
+
+/// 文本输入的缓存命名空间
+pub const TEXT_NAMESPACE: &str = "text";
+
+/// 声音试听片段子目录 (不过期)
+pub const PREVIEW_DIR: &str = "previews";
+
+/// 缓存文件的存储方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheStorage {
+    /// 按请求的格式原样保存
+    #[default]
+    AsRequested,
+    /// 无损格式 (wav/flac) 统一以 FLAC 保存, 读取时转回请求的格式;
+    /// 有损格式 (mp3/ogg) 本身已经很小, 以及 24-bit / 浮点 WAV (FLAC 编码器只支持 16-bit),
+    /// 仍然原样保存
+    Flac,
+}
+
 /// 音频缓存管理器
 pub struct AudioCache {
     cache_dir: PathBuf,
     ttl_seconds: u64,
+    storage: CacheStorage,
 }
 
 impl AudioCache {
@@ -39,9 +63,33 @@ impl AudioCache {
         Ok(Self {
             cache_dir,
             ttl_seconds,
+            storage: CacheStorage::default(),
         })
     }
 
+    /// 设置存储方式
+    pub fn with_storage(mut self, storage: CacheStorage) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// 请求的格式在磁盘上实际使用的格式
+    fn stored_format(&self, format: AudioFormat) -> AudioFormat {
+        match self.storage {
+            CacheStorage::Flac if format.is_lossless() => AudioFormat::Flac,
+            _ => format,
+        }
+    }
+
+    /// 查找缓存文件: 先找存储格式, 再找请求格式 (原样保存的文件)
+    fn find_file(&self, file_id: &str, format: AudioFormat) -> Option<(AudioFormat, PathBuf)> {
+        let stored = self.stored_format(format);
+        [stored, format]
+            .into_iter()
+            .map(|format| (format, self.get_file_path(file_id, format)))
+            .find(|(_, path)| path.exists())
+    }
+
     /// 根据文本和声音生成唯一的文件 ID (SHA256 哈希)
     ///
     /// # Arguments
@@ -50,9 +98,30 @@ impl AudioCache {
     ///
     /// # Returns
     /// 16 字符的十六进制哈希 (SHA256 前 64 位)
+    #[allow(dead_code)]
     pub fn get_file_id(&self, text: &str, voice: Option<&str>) -> String {
+        self.get_file_id_in(TEXT_NAMESPACE, text, voice)
+    }
+
+    /// 在指定命名空间下生成文件 ID
+    ///
+    /// 不同输入模式 (文本 / 音素 / token) 使用不同命名空间,
+    /// 相同的字符串不会互相命中缓存。
+    ///
+    /// # Arguments
+    /// * `namespace` - 输入模式, 如 "text" / "phonemes" / "tokens"
+    /// * `input` - 输入内容
+    /// * `voice` - 可选的声音名称
+    pub fn get_file_id_in(&self, namespace: &str, input: &str, voice: Option<&str>) -> String {
         let mut hasher = Sha256::new();
-        hasher.update(text.as_bytes());
+
+        // 文本命名空间保持原有哈希, 已有缓存文件继续有效
+        if namespace != TEXT_NAMESPACE {
+            hasher.update(b"ns:");
+            hasher.update(namespace.as_bytes());
+            hasher.update(b"|");
+        }
+        hasher.update(input.as_bytes());
 
         // 如果有声音参数,也包含在哈希中
         if let Some(v) = voice {
@@ -70,8 +139,9 @@ impl AudioCache {
     ///
     /// # Arguments
     /// * `file_id` - 文件 ID
-    fn get_file_path(&self, file_id: &str) -> PathBuf {
-        self.cache_dir.join(format!("{}.wav", file_id))
+    /// * `format` - 音频格式 (决定扩展名)
+    fn get_file_path(&self, file_id: &str, format: AudioFormat) -> PathBuf {
+        self.cache_dir.join(format!("{}.{}", file_id, format.extension()))
     }
 
     /// 检查缓存是否存在且未过期
@@ -82,14 +152,18 @@ impl AudioCache {
     ///
     /// # Returns
     /// `Some(file_id)` 如果缓存命中, `None` 如果未命中或已过期
+    #[allow(dead_code)]
     pub fn exists(&self, text: &str, voice: Option<&str>) -> Option<String> {
-        let file_id = self.get_file_id(text, voice);
-        let file_path = self.get_file_path(&file_id);
+        self.exists_in(TEXT_NAMESPACE, text, voice, AudioFormat::Wav)
+    }
 
-        if !file_path.exists() {
+    /// 检查指定命名空间下的缓存是否存在且未过期
+    pub fn exists_in(&self, namespace: &str, input: &str, voice: Option<&str>, format: AudioFormat) -> Option<String> {
+        let file_id = self.get_file_id_in(namespace, input, voice);
+        let Some((_, file_path)) = self.find_file(&file_id, format) else {
             debug!("❌ 缓存未命中: {} (文件不存在)", file_id);
             return None;
-        }
+        };
 
         // 检查文件修改时间
         match fs::metadata(&file_path) {
@@ -124,22 +198,62 @@ impl AudioCache {
     ///
     /// # Returns
     /// 文件 ID
+    #[allow(dead_code)]
     pub fn save(&self, text: &str, voice: Option<&str>, audio_data: &[u8]) -> Result<String> {
-        let file_id = self.get_file_id(text, voice);
-        let file_path = self.get_file_path(&file_id);
+        self.save_in(TEXT_NAMESPACE, text, voice, AudioFormat::Wav, audio_data)
+    }
+
+    /// 保存音频数据到指定命名空间
+    ///
+    /// `audio_data` 是请求格式的数据, 以 FLAC 存储时在这里转换
+    pub fn save_in(
+        &self,
+        namespace: &str,
+        input: &str,
+        voice: Option<&str>,
+        format: AudioFormat,
+        audio_data: &[u8],
+    ) -> Result<String> {
+        let file_id = self.get_file_id_in(namespace, input, voice);
+        let mut stored = self.stored_format(format);
+        if stored != format && !wav_encoder::is_pcm16(audio_data) {
+            stored = format;
+        }
+        let file_path = self.get_file_path(&file_id, stored);
+        let audio_data = match stored == format {
+            true => Cow::Borrowed(audio_data),
+            false => Cow::Owned(audio_format::transcode_lossless(audio_data, format, stored)?),
+        };
 
         let mut file = File::create(&file_path)
             .with_context(|| format!("无法创建缓存文件: {:?}", file_path))?;
 
-        file.write_all(audio_data)
+        file.write_all(&audio_data)
             .with_context(|| "写入缓存文件失败")?;
 
-        info!("💾 保存到缓存: {} ({} 字节)", file_id, audio_data.len());
+        info!("💾 保存到缓存: {} ({} 字节, {})", file_id, audio_data.len(), stored);
         Ok(file_id)
     }
 
+    /// 读取缓存的音频, 转换为请求的格式
+    ///
+    /// # Returns
+    /// `None` 如果文件不存在
+    pub fn read(&self, file_id: &str, format: AudioFormat) -> Result<Option<Vec<u8>>> {
+        let Some((stored, file_path)) = self.find_file(file_id, format) else {
+            return Ok(None);
+        };
+        let data = fs::read(&file_path).with_context(|| format!("读取缓存文件失败: {:?}", file_path))?;
+        match stored == format {
+            true => Ok(Some(data)),
+            false => audio_format::transcode_lossless(&data, stored, format).map(Some),
+        }
+    }
+
     /// 清理所有过期的缓存文件
     ///
+    /// 只处理缓存目录顶层的音频文件, 子目录 (如 previews/) 不受影响
+    ///
     /// # Returns
     /// 删除的文件数量
     #[allow(dead_code)]
@@ -150,27 +264,25 @@ impl AudioCache {
         let entries = fs::read_dir(&self.cache_dir)
             .with_context(|| format!("无法读取缓存目录: {:?}", self.cache_dir))?;
 
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
+        for entry in entries.flatten() {
+            let path = entry.path();
 
-                // 只处理 .wav 文件
-                if path.extension().and_then(|s| s.to_str()) != Some("wav") {
-                    continue;
-                }
+            // 只处理音频文件
+            if path.extension().and_then(|s| s.to_str()).and_then(AudioFormat::from_extension).is_none() {
+                continue;
+            }
 
-                if let Ok(metadata) = entry.metadata() {
-                    if let Ok(modified) = metadata.modified() {
-                        if let Ok(duration) = now.duration_since(modified) {
-                            if duration.as_secs() >= self.ttl_seconds {
-                                match fs::remove_file(&path) {
-                                    Ok(_) => {
-                                        debug!("🗑️  删除过期缓存: {:?}", path.file_name());
-                                        deleted += 1;
-                                    }
-                                    Err(e) => {
-                                        warn!("⚠️  删除缓存文件失败 {:?}: {}", path, e);
-                                    }
+            if let Ok(metadata) = entry.metadata() {
+                if let Ok(modified) = metadata.modified() {
+                    if let Ok(duration) = now.duration_since(modified) {
+                        if duration.as_secs() >= self.ttl_seconds {
+                            match fs::remove_file(&path) {
+                                Ok(_) => {
+                                    debug!("🗑️  删除过期缓存: {:?}", path.file_name());
+                                    deleted += 1;
+                                }
+                                Err(e) => {
+                                    warn!("⚠️  删除缓存文件失败 {:?}: {}", path, e);
                                 }
                             }
                         }
@@ -191,6 +303,11 @@ impl AudioCache {
     pub fn cache_dir(&self) -> &Path {
         &self.cache_dir
     }
+
+    /// 声音试听片段目录
+    pub fn preview_dir(&self) -> PathBuf {
+        self.cache_dir.join(PREVIEW_DIR)
+    }
 }
 
 #[cfg(test)]
@@ -229,6 +346,12 @@ mod tests {
 
         // 相同文本,有无语音参数 = 不同 ID
         assert_ne!(id1, id_with_voice1);
+
+        // 文本命名空间与原有 ID 一致, 其他命名空间互不冲突
+        assert_eq!(cache.get_file_id_in(TEXT_NAMESPACE, "Hello, world!", None), id1);
+        let id_phonemes = cache.get_file_id_in("phonemes", "Hello, world!", None);
+        assert_ne!(id_phonemes, id1);
+        assert_ne!(id_phonemes, cache.get_file_id_in("tokens", "Hello, world!", None));
     }
 
     #[test]
@@ -245,6 +368,49 @@ mod tests {
         assert_eq!(cache.exists(text, None), Some(file_id));
     }
 
+    #[test]
+    fn test_formats_use_separate_files() {
+        let cache = AudioCache::new("/tmp/test_cache_formats", 3600).unwrap();
+        let text = "Same text, different formats";
+        let wav_id = cache.save_in(TEXT_NAMESPACE, text, None, AudioFormat::Wav, b"RIFF").unwrap();
+
+        assert_eq!(cache.exists_in(TEXT_NAMESPACE, text, None, AudioFormat::Wav), Some(wav_id.clone()));
+        assert!(cache.exists_in(TEXT_NAMESPACE, text, None, AudioFormat::Mp3).is_none());
+        cache.save_in(TEXT_NAMESPACE, text, None, AudioFormat::Mp3, b"ID3").unwrap();
+        assert!(cache.cache_dir().join(format!("{}.mp3", wav_id)).exists());
+        let _ = fs::remove_dir_all(cache.cache_dir());
+    }
+
+    #[test]
+    fn test_flac_storage() {
+        let dir = "/tmp/test_cache_flac";
+        let _ = fs::remove_dir_all(dir);
+        let cache = AudioCache::new(dir, 3600).unwrap().with_storage(CacheStorage::Flac);
+        let audio: Vec<f32> = (0..12000).map(|i| (i as f32 * 0.02).sin() * 0.5).collect();
+        let wav = crate::wav_encoder::encode_wav(&audio, 24000).unwrap();
+
+        // WAV 以 FLAC 存储, 读取时还原为相同的 WAV 字节
+        let file_id = cache.save_in(TEXT_NAMESPACE, "word", None, AudioFormat::Wav, &wav).unwrap();
+        let stored = cache.cache_dir().join(format!("{}.flac", file_id));
+        assert!(fs::metadata(&stored).unwrap().len() < wav.len() as u64 / 2);
+        assert!(!cache.cache_dir().join(format!("{}.wav", file_id)).exists());
+        assert_eq!(cache.exists_in(TEXT_NAMESPACE, "word", None, AudioFormat::Wav), Some(file_id.clone()));
+        assert_eq!(cache.read(&file_id, AudioFormat::Wav).unwrap().unwrap(), wav);
+
+        // 24-bit WAV 无法存为 FLAC, 原样保存
+        let wav_24 = crate::wav_encoder::encode_wav_as(&audio, 24000, crate::wav_encoder::PcmFormat::S24).unwrap();
+        let wav_24_id = cache.save_in(TEXT_NAMESPACE, "word|pcm:s24", None, AudioFormat::Wav, &wav_24).unwrap();
+        assert!(cache.cache_dir().join(format!("{}.wav", wav_24_id)).exists());
+        assert!(cache.exists_in(TEXT_NAMESPACE, "word|pcm:s24", None, AudioFormat::Wav).is_some());
+        assert_eq!(cache.read(&wav_24_id, AudioFormat::Wav).unwrap().unwrap(), wav_24);
+
+        // 有损格式原样保存
+        let mp3_id = cache.save_in(TEXT_NAMESPACE, "word|mp3", None, AudioFormat::Mp3, b"ID3").unwrap();
+        assert_eq!(cache.read(&mp3_id, AudioFormat::Mp3).unwrap().unwrap(), b"ID3");
+        assert!(cache.read("0000000000000000", AudioFormat::Wav).unwrap().is_none());
+        let _ = fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn test_cache_expiration() {
         let cache = AudioCache::new("/tmp/test_cache_ttl", 1).unwrap(); // 1 秒过期
@@ -263,4 +429,17 @@ mod tests {
         // 检查 - 应该已过期
         assert!(cache.exists(text, None).is_none());
     }
+
+    #[test]
+    fn test_cleanup_keeps_previews() {
+        let cache = AudioCache::new("/tmp/test_cache_previews", 0).unwrap();
+        fs::create_dir_all(cache.preview_dir()).unwrap();
+        let preview = cache.preview_dir().join("af_alloy.wav");
+        fs::write(&preview, [0u8; 16]).unwrap();
+        cache.save("Old content", None, &[0u8; 16]).unwrap();
+
+        assert!(cache.cleanup().unwrap() >= 1);
+        assert!(preview.exists());
+        let _ = fs::remove_dir_all(cache.cache_dir());
+    }
 }
@@ -0,0 +1,305 @@
+//! 合成前诊断
+//!
+//! 不做推理, 只跑前端流程 (规范化 → G2P → tokenize → 分段), 展示模型实际收到的输入
+
+use serde::Serialize;
+
+use crate::espeak::EspeakError;
+use crate::homograph::{self, Resolution};
+use crate::language::Language;
+use crate::tts_engine::{self, TextOptions, MAX_TOKENS};
+use crate::verbalize::{self, SymbolOptions};
+use crate::vocab::Vocab;
+use crate::zh_g2p;
+
+/// 每个 token 的平均时长估计 (秒, 语速 1.0)
+const SECONDS_PER_TOKEN: f32 = 0.075;
+
+/// 分段之间插入的静音 (秒), 与 `synthesize_long_text` 一致
+const CHUNK_GAP_SECONDS: f32 = 0.3;
+
+/// 被词汇表过滤掉的音素字符
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DroppedChar {
+    /// 在音素串中的位置 (按字符计)
+    pub position: usize,
+    pub char: char,
+    /// U+XXXX
+    pub codepoint: String,
+}
+
+/// 某个推理分段中被丢弃的字符
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ChunkDroppedChar {
+    /// 推理分段序号 (从 0 开始)
+    pub chunk: usize,
+    #[serde(flatten)]
+    pub dropped: DroppedChar,
+}
+
+/// 读音词典中没有、G2P 时被丢弃的原文字符
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UnresolvedChar {
+    pub char: char,
+    /// U+XXXX
+    pub codepoint: String,
+}
+
+impl UnresolvedChar {
+    /// 去重并保持首次出现的顺序
+    fn collect(chars: &[char]) -> Vec<Self> {
+        let mut out: Vec<Self> = Vec::new();
+        for &c in chars {
+            if !out.iter().any(|u| u.char == c) {
+                out.push(Self { char: c, codepoint: format!("U+{:04X}", c as u32) });
+            }
+        }
+        out
+    }
+}
+
+/// 长文本中合成失败、被跳过的句子
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SkippedSentence {
+    /// 句子序号 (从 0 开始)
+    pub index: usize,
+    pub text: String,
+    /// 失败原因
+    pub reason: String,
+}
+
+/// 随每次合成返回的诊断信息
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SynthesisDiagnostics {
+    /// 被词汇表过滤掉的音素字符 (位置相对于该分段的音素串)
+    pub dropped: Vec<ChunkDroppedChar>,
+    /// 读音词典中没有、被丢弃的原文字符 (去重)
+    pub unresolved: Vec<UnresolvedChar>,
+    /// espeak-ng 不可用, 使用了 ASCII 降级 G2P
+    pub fallback_used: bool,
+    /// 推理分段数
+    pub chunks: usize,
+    /// 长文本中合成失败、被跳过的句子
+    pub skipped: Vec<SkippedSentence>,
+}
+
+impl SynthesisDiagnostics {
+    /// 记录即将推理的分段中被丢弃的字符
+    pub fn record_dropped(&mut self, phonemes: &str, vocab: &Vocab) {
+        let chunk = self.chunks;
+        self.dropped.extend(
+            dropped_chars(phonemes, vocab)
+                .into_iter()
+                .map(|dropped| ChunkDroppedChar { chunk, dropped }),
+        );
+    }
+
+    /// 记录 G2P 中没有读音的原文字符
+    pub fn record_unresolved(&mut self, chars: &[char]) {
+        for unresolved in UnresolvedChar::collect(chars) {
+            if !self.unresolved.contains(&unresolved) {
+                self.unresolved.push(unresolved);
+            }
+        }
+    }
+
+    /// 记录长文本中被跳过的句子
+    pub fn record_skipped(&mut self, index: usize, text: &str, error: &anyhow::Error) {
+        self.skipped.push(SkippedSentence {
+            index,
+            text: text.to_string(),
+            reason: format!("{:#}", error),
+        });
+    }
+}
+
+/// 长文本分段
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkInfo {
+    /// 在原文中的字符区间 [start, end)
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub tokens: usize,
+    /// 单段仍超过 token 上限
+    pub over_limit: bool,
+}
+
+/// POST /phonemize 的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PhonemizeReport {
+    pub voice: String,
+    pub language: Language,
+    pub normalized_text: String,
+    pub phonemes: String,
+    pub tokens: Vec<i64>,
+    pub dropped: Vec<DroppedChar>,
+    /// 读音词典中没有、被丢弃的原文字符 (去重)
+    pub unresolved: Vec<UnresolvedChar>,
+    pub fallback_used: bool,
+    /// 英语同形异音词的消歧结果
+    pub homographs: Vec<Resolution>,
+    pub chunks: Vec<ChunkInfo>,
+    pub estimated_duration_secs: f32,
+}
+
+/// 音素串中词汇表外的字符 (tokenize 会丢弃)
+pub fn dropped_chars(phonemes: &str, vocab: &Vocab) -> Vec<DroppedChar> {
+    phonemes
+        .chars()
+        .enumerate()
+        .filter(|&(_, c)| !vocab.contains(c))
+        .map(|(position, c)| DroppedChar {
+            position,
+            char: c,
+            codepoint: format!("U+{:04X}", c as u32),
+        })
+        .collect()
+}
+
+/// 文本规范化 (符号文字化; 普通话: 数字/日期/时间转汉字; 其他语言: 合并空白)
+pub fn normalize(text: &str, language: Language, symbols: &SymbolOptions) -> String {
+    let text = verbalize::verbalize(text, language, symbols);
+    match language {
+        Language::Mandarin => zh_g2p::normalize(&text),
+        Language::English | Language::Japanese => text.split_whitespace().collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// 按 `synthesize` 的规则计算分段: 不超过上限时整段合成, 否则按句子切分
+fn chunk_boundaries<F, E>(text: &str, total_tokens: usize, mut count_tokens: F) -> Result<Vec<ChunkInfo>, E>
+where
+    F: FnMut(&str) -> Result<usize, E>,
+{
+    let char_offset = |byte: usize| text[..byte].chars().count();
+    let chunk = |slice: &str, tokens: usize| {
+        let trimmed = slice.trim();
+        let byte_start = trimmed.as_ptr() as usize - text.as_ptr() as usize;
+        ChunkInfo {
+            start: char_offset(byte_start),
+            end: char_offset(byte_start + trimmed.len()),
+            text: trimmed.to_string(),
+            tokens,
+            over_limit: tokens > MAX_TOKENS,
+        }
+    };
+
+    if total_tokens <= MAX_TOKENS {
+        if text.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        return Ok(vec![chunk(text, total_tokens)]);
+    }
+
+    tts_engine::split_sentences(text)
+        .into_iter()
+        .map(|sentence| Ok(chunk(sentence, count_tokens(sentence.trim())?)))
+        .collect()
+}
+
+/// 估计音频时长 (秒)
+pub fn estimate_duration(chunks: &[ChunkInfo]) -> f32 {
+    let speech: f32 = chunks.iter().map(|c| c.tokens as f32 * SECONDS_PER_TOKEN).sum();
+    let gaps = chunks.len().saturating_sub(1) as f32 * CHUNK_GAP_SECONDS;
+    speech + gaps
+}
+
+/// 生成诊断报告 (不做推理)
+pub fn analyze(text: &str, voice: &str, vocab: &Vocab, options: &TextOptions) -> Result<PhonemizeReport, EspeakError> {
+    let language = Language::detect(text, voice);
+    let tts_engine::Phonemized { phonemes, fallback_used, unresolved } = tts_engine::phonemize(text, voice, options)?;
+
+    // 与音素化相同的片段切分, 只收集消歧结果
+    let mut homographs = Vec::new();
+    if language == Language::English {
+        let verbalized = verbalize::verbalize(text, language, &options.symbols);
+        tts_engine::phonemize_preserving_punctuation(&verbalized, |run| {
+            homographs.extend(homograph::disambiguate(run, &options.senses));
+            String::new()
+        });
+    }
+
+    let tokens = vocab.tokenize(&phonemes);
+    let dropped = dropped_chars(&phonemes, vocab);
+    let chunks = chunk_boundaries(text, tokens.len(), |sentence| {
+        tts_engine::phonemize(sentence, voice, options).map(|p| vocab.tokenize(&p.phonemes).len())
+    })?;
+    let estimated_duration_secs = estimate_duration(&chunks);
+
+    Ok(PhonemizeReport {
+        voice: voice.to_string(),
+        language,
+        normalized_text: normalize(text, language, &options.symbols),
+        phonemes,
+        tokens,
+        dropped,
+        unresolved: UnresolvedChar::collect(&unresolved),
+        fallback_used,
+        homographs,
+        chunks,
+        estimated_duration_secs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropped_chars() {
+        let dropped = dropped_chars("hə1lo", &crate::vocab::EMBEDDED);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].position, 2);
+        assert_eq!(dropped[0].codepoint, "U+0031");
+    }
+
+    #[test]
+    fn test_record_dropped_per_chunk() {
+        let mut diagnostics = SynthesisDiagnostics::default();
+        diagnostics.record_dropped("hə1lo", &crate::vocab::EMBEDDED);
+        diagnostics.chunks += 1;
+        diagnostics.record_dropped("ab", &crate::vocab::EMBEDDED);
+        diagnostics.record_dropped("a#", &crate::vocab::EMBEDDED);
+        diagnostics.chunks += 1;
+
+        let found: Vec<(usize, usize, char)> = diagnostics
+            .dropped
+            .iter()
+            .map(|d| (d.chunk, d.dropped.position, d.dropped.char))
+            .collect();
+        assert_eq!(found, vec![(0, 2, '1'), (1, 1, '#')]);
+
+        let json = serde_json::to_value(&diagnostics).unwrap();
+        assert_eq!(json["dropped"][1]["chunk"], 1);
+        assert_eq!(json["dropped"][1]["codepoint"], "U+0023");
+        assert_eq!(json["chunks"], 2);
+    }
+
+    #[test]
+    fn test_chunk_boundaries() {
+        let text = "第一句。 Second one!";
+        let short = chunk_boundaries::<_, ()>(text, 10, |_| unreachable!()).unwrap();
+        assert_eq!(short.len(), 1);
+        assert_eq!((short[0].start, short[0].end), (0, 16));
+
+        let long = chunk_boundaries::<_, ()>(text, MAX_TOKENS + 1, |s| Ok(s.chars().count())).unwrap();
+        assert_eq!(long.len(), 2);
+        assert_eq!((long[0].start, long[0].end, long[0].text.as_str()), (0, 4, "第一句。"));
+        assert_eq!((long[1].start, long[1].end, long[1].text.as_str()), (5, 16, "Second one!"));
+        assert!((estimate_duration(&long) - (15.0 * SECONDS_PER_TOKEN + CHUNK_GAP_SECONDS)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_analyze_mandarin() {
+        let report = analyze("今天是2025年", "zf_xiaobei", &crate::vocab::EMBEDDED, &TextOptions::default()).unwrap();
+        assert_eq!(report.language, Language::Mandarin);
+        assert_eq!(report.normalized_text, "今天是二零二五年");
+        assert_eq!(report.chunks.len(), 1);
+        assert!(report.estimated_duration_secs > 0.0);
+        assert!(report.unresolved.is_empty());
+
+        // 词典中没有的汉字报告为被丢弃
+        let report = analyze("你好䶮䶮", "zf_xiaobei", &crate::vocab::EMBEDDED, &TextOptions::default()).unwrap();
+        assert_eq!(report.unresolved, vec![UnresolvedChar { char: '䶮', codepoint: "U+4DAE".to_string() }]);
+    }
+}
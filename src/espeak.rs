@@ -0,0 +1,95 @@
+//! espeak-ng 音素化
+//!
+//! 文本通过 stdin 传给 espeak-ng (以 `-` 开头的文本不会被当成选项),
+//! 进程有超时和输出上限, 失败返回 [`EspeakError`]。
+
+use std::process::Command;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::info;
+
+use crate::subprocess::{self, Limits, SubprocessError};
+
+/// espeak-ng 运行限制: 单段文本正常在几十毫秒内完成
+const ESPEAK_LIMITS: Limits = Limits {
+    timeout: Duration::from_secs(10),
+    max_output: 1024 * 1024,
+};
+
+#[derive(Debug, Error)]
+pub enum EspeakError {
+    #[error("espeak-ng 未安装")]
+    NotInstalled,
+    #[error("espeak-ng 超时 ({0:?})")]
+    Timeout(Duration),
+    #[error("espeak-ng 输出超过 {0} 字节")]
+    OutputTooLarge(usize),
+    #[error("espeak-ng 执行失败 (退出码 {code:?}): {stderr}")]
+    Failed { code: Option<i32>, stderr: String },
+    #[error("espeak-ng 输出不是 UTF-8")]
+    InvalidUtf8,
+    #[error("调用 espeak-ng 失败: {0}")]
+    Process(SubprocessError),
+}
+
+impl From<SubprocessError> for EspeakError {
+    fn from(e: SubprocessError) -> Self {
+        match e {
+            SubprocessError::NotInstalled { .. } => EspeakError::NotInstalled,
+            SubprocessError::Timeout { timeout, .. } => EspeakError::Timeout(timeout),
+            SubprocessError::OutputTooLarge { limit, .. } => EspeakError::OutputTooLarge(limit),
+            SubprocessError::Failed { code, stderr, .. } => EspeakError::Failed { code, stderr },
+            other => EspeakError::Process(other),
+        }
+    }
+}
+
+/// Kokoro 专用替换: 把 espeak-ng 的部分音素换成 Kokoro 训练时使用的写法
+fn kokoro_fixups(phonemes: &str) -> String {
+    phonemes
+        .replace("kəkˈoːɹoʊ", "kˈoʊkəɹoʊ")
+        .replace("kəkˈɔːɹəʊ", "kˈəʊkəɹəʊ")
+        .replace("ʲ", "j")
+        .replace("r", "ɹ")
+        .replace("x", "k")
+        .replace("ɬ", "l")
+}
+
+/// 使用 espeak-ng 进行音素化 (美式英语)
+pub fn phonemize(text: &str) -> Result<String, EspeakError> {
+    info!("🔊 调用 espeak-ng: {}", text);
+    let mut command = Command::new("espeak-ng");
+    command.args(["-v", "en-us", "-q", "--ipa", "-b", "1", "--stdin"]);
+
+    let stdout = subprocess::run(command, text.as_bytes(), ESPEAK_LIMITS)?;
+    let output = String::from_utf8(stdout).map_err(|_| EspeakError::InvalidUtf8)?;
+
+    // 多行输入时 espeak-ng 按行输出, 合并成一行
+    let phonemes = output.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    // 词汇表外的字符由 tokenize 过滤 (/phonemize 会列出被丢弃的字符)
+    Ok(kokoro_fixups(&phonemes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kokoro_fixups() {
+        assert_eq!(kokoro_fixups("rˈɛd xˈɑːɬ"), "ɹˈɛd kˈɑːl");
+    }
+
+    #[test]
+    fn test_error_mapping() {
+        let e: EspeakError = SubprocessError::NotInstalled { program: "espeak-ng".to_string() }.into();
+        assert!(matches!(e, EspeakError::NotInstalled));
+
+        let e: EspeakError = SubprocessError::Timeout {
+            program: "espeak-ng".to_string(),
+            timeout: Duration::from_secs(1),
+        }
+        .into();
+        assert!(matches!(e, EspeakError::Timeout(_)));
+    }
+}
@@ -0,0 +1,554 @@
+//! FLAC Audio Encoder / Decoder
+//!
+//! 16-bit 单声道 FLAC: 定长 4096 样本的块, 每块在常数 / 原样 / 0-4 阶固定预测中
+//! 选估算最短的一种, 残差用分区 Rice 编码。STREAMINFO 中的 MD5 填 0 (表示未计算)。
+//!
+//! 解码器只覆盖本模块写出的子集 (不支持 LPC 子帧和多声道), 用于缓存以 FLAC
+//! 存储时转回 WAV。
+
+use anyhow::{bail, ensure, Context, Result};
+
+/// 每帧样本数
+const BLOCK_SIZE: usize = 4096;
+
+const BITS_PER_SAMPLE: u32 = 16;
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_PARTITION_ORDER: u32 = 6;
+
+/// 4-bit Rice 参数, 15 保留给 escape
+const MAX_RICE_PARAM: u32 = 14;
+
+/// 子帧类型
+const SUBFRAME_CONSTANT: u64 = 0;
+const SUBFRAME_VERBATIM: u64 = 1;
+const SUBFRAME_FIXED: u64 = 8;
+
+/// 帧头 CRC-8: 多项式 0x07, 初值 0
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |crc, &b| {
+        (0..8).fold(crc ^ b, |r, _| if r & 0x80 != 0 { (r << 1) ^ 0x07 } else { r << 1 })
+    })
+}
+
+/// 帧尾 CRC-16: 多项式 0x8005, 初值 0
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &b| {
+        (0..8).fold(crc ^ ((b as u16) << 8), |r, _| if r & 0x8000 != 0 { (r << 1) ^ 0x8005 } else { r << 1 })
+    })
+}
+
+/// 高位在前的位写入
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    /// 写入 `value` 的低 `n` 位 (n ≤ 32)
+    fn write(&mut self, value: u64, n: u32) {
+        if n == 0 {
+            return;
+        }
+        self.acc = (self.acc << n) | (value & ((1u64 << n) - 1));
+        self.bits += n;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.bytes.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1u64 << self.bits) - 1;
+    }
+
+    /// `q` 个 0 后跟一个 1
+    fn write_unary(&mut self, mut q: u64) {
+        while q >= 32 {
+            self.write(0, 32);
+            q -= 32;
+        }
+        self.write(1, q as u32 + 1);
+    }
+
+    /// 补 0 到字节边界
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+/// 帧号的 UTF-8 式变长编码
+fn write_utf8(w: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        w.write(value, 8);
+        return;
+    }
+    // n 字节可以容纳 5n + 1 位 (首字节 7 - n 位, 后续每字节 6 位)
+    let len = (2..=7u32).find(|&n| value < 1u64 << (5 * n + 1)).unwrap_or(7);
+    let lead = (0xFF00u64 >> len) & 0xFF;
+    w.write(lead | (value >> (6 * (len - 1))), 8);
+    for i in (0..len - 1).rev() {
+        w.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn sample_rate_code(sample_rate: u32) -> u64 {
+    match sample_rate {
+        8000 => 0b0100,
+        16000 => 0b0101,
+        22050 => 0b0110,
+        24000 => 0b0111,
+        32000 => 0b1000,
+        44100 => 0b1001,
+        48000 => 0b1010,
+        96000 => 0b1011,
+        _ => 0b0000, // 使用 STREAMINFO 中的采样率
+    }
+}
+
+/// 0-4 阶固定预测的残差 (前 `order` 个位置为 0)
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<i64> {
+    let mut residual = vec![0; samples.len()];
+    for i in order..samples.len() {
+        let x = |k: usize| samples[i - k];
+        residual[i] = match order {
+            0 => x(0),
+            1 => x(0) - x(1),
+            2 => x(0) - 2 * x(1) + x(2),
+            3 => x(0) - 3 * x(1) + 3 * x(2) - x(3),
+            _ => x(0) - 4 * x(1) + 6 * x(2) - 4 * x(3) + x(4),
+        };
+    }
+    residual
+}
+
+/// 按预测阶数还原样本 (`samples` 前 `order` 个是 warm-up, 其余是残差)
+fn restore_fixed(samples: &mut [i64], order: usize) {
+    for i in order..samples.len() {
+        let x = |k: usize| samples[i - k];
+        samples[i] += match order {
+            0 => 0,
+            1 => x(1),
+            2 => 2 * x(1) - x(2),
+            3 => 3 * x(1) - 3 * x(2) + x(3),
+            _ => 4 * x(1) - 6 * x(2) + 4 * x(3) - x(4),
+        };
+    }
+}
+
+fn zigzag(r: i64) -> u64 {
+    ((r << 1) ^ (r >> 63)) as u64
+}
+
+/// 按 `n` 个值的和估算最优 Rice 参数和位数
+fn rice_param(n: u64, sum: u64) -> (u32, u64) {
+    let mut k = 0;
+    while k < MAX_RICE_PARAM && n << (k + 1) <= sum {
+        k += 1;
+    }
+    (k, n * (k as u64 + 1) + (sum >> k))
+}
+
+/// 一种固定预测编码方案
+struct FixedPlan {
+    order: usize,
+    partition_order: u32,
+    params: Vec<u32>,
+    residual: Vec<i64>,
+    bits: u64,
+}
+
+fn plan_fixed(samples: &[i64], order: usize) -> FixedPlan {
+    let n = samples.len();
+    let residual = fixed_residual(samples, order);
+    let mut prefix = Vec::with_capacity(n + 1);
+    prefix.push(0u64);
+    for &r in &residual {
+        prefix.push(prefix.last().unwrap() + zigzag(r));
+    }
+
+    let mut best: Option<(u32, Vec<u32>, u64)> = None;
+    for p in 0..=MAX_PARTITION_ORDER {
+        if !n.is_multiple_of(1 << p) || (n >> p) <= order {
+            break;
+        }
+        let size = n >> p;
+        let mut params = Vec::with_capacity(1 << p);
+        let mut bits = 0;
+        for i in 0..1 << p {
+            let start = if i == 0 { order } else { i * size };
+            let end = (i + 1) * size;
+            let (k, cost) = rice_param((end - start) as u64, prefix[end] - prefix[start]);
+            params.push(k);
+            bits += 4 + cost;
+        }
+        if best.as_ref().is_none_or(|(_, _, b)| bits < *b) {
+            best = Some((p, params, bits));
+        }
+    }
+    let (partition_order, params, bits) = best.unwrap_or((0, vec![0], u64::MAX / 2));
+    FixedPlan { order, partition_order, params, residual, bits: 8 + 16 * order as u64 + 6 + bits }
+}
+
+fn write_subframe(w: &mut BitWriter, samples: &[i64]) {
+    let n = samples.len();
+    if samples.iter().all(|&s| s == samples[0]) {
+        w.write(SUBFRAME_CONSTANT << 1, 8);
+        w.write(samples[0] as u64, BITS_PER_SAMPLE);
+        return;
+    }
+
+    let verbatim_bits = 8 + 16 * n as u64;
+    let plan = (0..=MAX_FIXED_ORDER.min(n - 1)).map(|order| plan_fixed(samples, order)).min_by_key(|plan| plan.bits);
+    let Some(plan) = plan.filter(|plan| plan.bits < verbatim_bits) else {
+        w.write(SUBFRAME_VERBATIM << 1, 8);
+        for &s in samples {
+            w.write(s as u64, BITS_PER_SAMPLE);
+        }
+        return;
+    };
+
+    w.write((SUBFRAME_FIXED + plan.order as u64) << 1, 8);
+    for &s in &samples[..plan.order] {
+        w.write(s as u64, BITS_PER_SAMPLE);
+    }
+    w.write(0, 2); // 4-bit Rice 参数
+    w.write(plan.partition_order as u64, 4);
+    let size = n >> plan.partition_order;
+    for (i, &k) in plan.params.iter().enumerate() {
+        w.write(k as u64, 4);
+        let start = if i == 0 { plan.order } else { i * size };
+        for &r in &plan.residual[start..(i + 1) * size] {
+            let u = zigzag(r);
+            w.write_unary(u >> k);
+            w.write(u, k);
+        }
+    }
+}
+
+fn write_frame(out: &mut Vec<u8>, block: &[i16], number: u64, sample_rate: u32) {
+    let mut w = BitWriter::default();
+    w.write(0x3FFE, 14); // 同步码
+    w.write(0, 1);
+    w.write(0, 1); // 定长块
+    w.write(0b0111, 4); // 块大小在帧头末尾 (16 位)
+    w.write(sample_rate_code(sample_rate), 4);
+    w.write(0, 4); // 单声道
+    w.write(0b100, 3); // 16-bit
+    w.write(0, 1);
+    write_utf8(&mut w, number);
+    w.write(block.len() as u64 - 1, 16);
+    let mut header = w.into_bytes();
+    header.push(crc8(&header));
+
+    let mut w = BitWriter { bytes: header, ..Default::default() };
+    let samples: Vec<i64> = block.iter().map(|&s| s as i64).collect();
+    write_subframe(&mut w, &samples);
+    let frame = w.into_bytes();
+    let crc = crc16(&frame);
+    out.extend_from_slice(&frame);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// 编码 16-bit 单声道 FLAC 到内存
+pub fn encode_flac(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>> {
+    ensure!((1..1 << 20).contains(&sample_rate), "FLAC 不支持采样率 {} Hz", sample_rate);
+
+    let mut w = BitWriter::default();
+    w.write(1, 1); // 最后一个元数据块
+    w.write(0, 7); // STREAMINFO
+    w.write(34, 24);
+    w.write(BLOCK_SIZE as u64, 16);
+    w.write(BLOCK_SIZE as u64, 16);
+    w.write(0, 24); // 最小/最大帧长未知
+    w.write(0, 24);
+    w.write(sample_rate as u64, 20);
+    w.write(0, 3); // 声道数 - 1
+    w.write(BITS_PER_SAMPLE as u64 - 1, 5);
+    w.write(samples.len() as u64 >> 32, 4);
+    w.write(samples.len() as u64, 32);
+    for _ in 0..4 {
+        w.write(0, 32); // MD5 未计算
+    }
+
+    let mut out = b"fLaC".to_vec();
+    out.extend(w.into_bytes());
+    for (number, block) in samples.chunks(BLOCK_SIZE).enumerate() {
+        write_frame(&mut out, block, number as u64, sample_rate);
+    }
+    Ok(out)
+}
+
+/// 高位在前的位读取
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, n: u32) -> Result<u64> {
+        ensure!(self.pos + n as usize <= self.data.len() * 8, "FLAC 数据不完整");
+        let mut value = 0;
+        for _ in 0..n {
+            let bit = self.data[self.pos / 8] >> (7 - self.pos % 8) & 1;
+            value = (value << 1) | bit as u64;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn read_signed(&mut self, n: u32) -> Result<i64> {
+        if n == 0 {
+            return Ok(0);
+        }
+        let value = self.read(n)?;
+        Ok(((value << (64 - n)) as i64) >> (64 - n))
+    }
+
+    fn read_unary(&mut self) -> Result<u64> {
+        let mut q = 0;
+        while self.read(1)? == 0 {
+            q += 1;
+        }
+        Ok(q)
+    }
+
+    fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+
+    fn byte_pos(&self) -> usize {
+        self.pos / 8
+    }
+}
+
+fn read_residual(r: &mut BitReader, samples: &mut [i64], order: usize) -> Result<()> {
+    let param_bits = match r.read(2)? {
+        0 => 4,
+        1 => 5,
+        method => bail!("不支持的残差编码方式: {}", method),
+    };
+    let escape = (1 << param_bits) - 1;
+    let partition_order = r.read(4)? as u32;
+    let size = samples.len() >> partition_order;
+    ensure!(size << partition_order == samples.len() && size >= order, "残差分区无效");
+
+    for i in 0..1usize << partition_order {
+        let start = if i == 0 { order } else { i * size };
+        let k = r.read(param_bits)? as u32;
+        for sample in &mut samples[start..(i + 1) * size] {
+            *sample = if k == escape {
+                let bits = r.read(5)? as u32;
+                r.read_signed(bits)?
+            } else {
+                let u = (r.read_unary()? << k) | r.read(k)?;
+                (u >> 1) as i64 ^ -((u & 1) as i64)
+            };
+        }
+    }
+    Ok(())
+}
+
+fn read_subframe(r: &mut BitReader, n: usize) -> Result<Vec<i64>> {
+    ensure!(r.read(1)? == 0, "子帧填充位错误");
+    let kind = r.read(6)?;
+    let wasted = match r.read(1)? {
+        1 => r.read_unary()? as u32 + 1,
+        _ => 0,
+    };
+    ensure!(wasted < BITS_PER_SAMPLE, "子帧 wasted bits 无效");
+    let bits = BITS_PER_SAMPLE - wasted;
+
+    let mut samples = vec![0i64; n];
+    match kind {
+        SUBFRAME_CONSTANT => samples.fill(r.read_signed(bits)?),
+        SUBFRAME_VERBATIM => {
+            for sample in &mut samples {
+                *sample = r.read_signed(bits)?;
+            }
+        }
+        8..=12 => {
+            let order = (kind - SUBFRAME_FIXED) as usize;
+            ensure!(order <= n, "预测阶数超过块大小");
+            for sample in &mut samples[..order] {
+                *sample = r.read_signed(bits)?;
+            }
+            read_residual(r, &mut samples, order)?;
+            restore_fixed(&mut samples, order);
+        }
+        32..=63 => bail!("不支持 LPC 子帧"),
+        _ => bail!("子帧类型无效: {}", kind),
+    }
+    samples.iter_mut().for_each(|s| *s <<= wasted);
+    Ok(samples)
+}
+
+fn read_frame(r: &mut BitReader, out: &mut Vec<i16>) -> Result<()> {
+    let start = r.byte_pos();
+    ensure!(r.read(14)? == 0x3FFE, "帧同步码错误 (偏移 {})", start);
+    r.read(2)?;
+    let block_code = r.read(4)?;
+    let rate_code = r.read(4)?;
+    ensure!(r.read(4)? == 0, "只支持单声道 FLAC");
+    ensure!(matches!(r.read(3)?, 0 | 0b100), "只支持 16-bit FLAC");
+    r.read(1)?;
+
+    // 帧号 (UTF-8 式编码, 跳过)
+    let lead = r.read(8)?;
+    let extra = (lead as u8).leading_ones().saturating_sub(1);
+    r.read(8 * extra)?;
+
+    let n = match block_code {
+        1 => 192,
+        2..=5 => 576 << (block_code - 2),
+        6 => r.read(8)? + 1,
+        7 => r.read(16)? + 1,
+        8..=15 => 256 << (block_code - 8),
+        _ => bail!("帧块大小无效"),
+    } as usize;
+    match rate_code {
+        12 => drop(r.read(8)?),
+        13 | 14 => drop(r.read(16)?),
+        15 => bail!("帧采样率无效"),
+        _ => {}
+    }
+    let expected = crc8(&r.data[start..r.byte_pos()]);
+    ensure!(r.read(8)? as u8 == expected, "帧头 CRC 错误");
+
+    let samples = read_subframe(r, n)?;
+    r.align();
+    let expected = crc16(&r.data[start..r.byte_pos()]);
+    ensure!(r.read(16)? as u16 == expected, "帧 CRC 错误");
+
+    for s in samples {
+        out.push(i16::try_from(s).context("样本超出 16-bit 范围")?);
+    }
+    Ok(())
+}
+
+/// 解码本模块写出的 FLAC, 返回 (16-bit 样本, 采样率)
+pub fn decode_flac(data: &[u8]) -> Result<(Vec<i16>, u32)> {
+    ensure!(data.starts_with(b"fLaC"), "不是 FLAC 文件");
+    let mut r = BitReader { data, pos: 32 };
+
+    let mut stream_info = None;
+    loop {
+        let last = r.read(1)? == 1;
+        let kind = r.read(7)?;
+        let length = r.read(24)? as usize;
+        let end = r.pos + length * 8;
+        if kind == 0 {
+            r.read(16 + 16 + 24 + 24)?;
+            let sample_rate = r.read(20)? as u32;
+            let channels = r.read(3)? + 1;
+            let bits = r.read(5)? + 1;
+            let total = r.read(4)? << 32 | r.read(32)?;
+            ensure!(channels == 1 && bits == BITS_PER_SAMPLE as u64, "只支持 16-bit 单声道 FLAC");
+            stream_info = Some((sample_rate, total));
+        }
+        r.pos = end;
+        if last {
+            break;
+        }
+    }
+    let (sample_rate, total) = stream_info.context("缺少 STREAMINFO")?;
+
+    let mut samples = Vec::with_capacity(total as usize);
+    while r.byte_pos() < data.len() {
+        read_frame(&mut r, &mut samples)?;
+    }
+    if total > 0 {
+        samples.truncate(total as usize);
+    }
+    Ok((samples, sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 确定性的伪随机样本
+    fn noise(len: usize, amplitude: i32) -> Vec<i16> {
+        let mut state = 0x2545_F491u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                ((state % (2 * amplitude as u32 + 1)) as i32 - amplitude) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_crc_check_values() {
+        assert_eq!(crc8(b"123456789"), 0xF4);
+        assert_eq!(crc16(b"123456789"), 0xFEE8);
+    }
+
+    #[test]
+    fn test_utf8_frame_numbers() {
+        for value in [0u64, 0x7F, 0x80, 0x7FF, 0x800, 0xFFFF, 0x1F_FFFF, 0x3FF_FFFF] {
+            let mut w = BitWriter::default();
+            write_utf8(&mut w, value);
+            let bytes = w.into_bytes();
+            let extra = bytes[0].leading_ones().saturating_sub(1) as usize;
+            assert_eq!(bytes.len(), extra + 1, "{:#x}", value);
+            let mut decoded = match extra {
+                0 => bytes[0] as u64,
+                _ => (bytes[0] & (0x7F >> (extra + 1))) as u64,
+            };
+            for &b in &bytes[1..] {
+                decoded = (decoded << 6) | (b & 0x3F) as u64;
+            }
+            assert_eq!(decoded, value);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tone: Vec<i16> = (0..10_000)
+            .map(|i| ((i as f32 * 0.03).sin() * 12000.0 + (i as f32 * 0.11).sin() * 3000.0) as i16)
+            .collect();
+        let mut extremes = vec![i16::MIN, i16::MAX, 0, -1, 1, i16::MAX, i16::MIN];
+        extremes.extend(noise(5000, 32767));
+
+        for samples in [tone, extremes, noise(4096, 100), vec![0; 8192], vec![7], Vec::new()] {
+            let flac = encode_flac(&samples, 24000).unwrap();
+            let (decoded, sample_rate) = decode_flac(&flac).unwrap();
+            assert_eq!(sample_rate, 24000);
+            assert_eq!(decoded, samples);
+        }
+    }
+
+    #[test]
+    fn test_compresses_tonal_audio() {
+        // 带包络的谐波信号 + 轻微噪声, 接近语音的可预测性
+        let hiss = noise(24000, 40);
+        let samples: Vec<i16> = (0..24000)
+            .map(|i| {
+                let t = i as f32 / 24000.0;
+                let envelope = (t * 3.0 * std::f32::consts::PI).sin().abs();
+                let voice: f32 = (1..6).map(|h| (t * 2.0 * std::f32::consts::PI * 140.0 * h as f32).sin() / h as f32).sum();
+                (voice * envelope * 8000.0) as i16 + hiss[i]
+            })
+            .collect();
+        let flac = encode_flac(&samples, 24000).unwrap();
+        assert!(flac.len() < samples.len() * 2 * 6 / 10, "{} 字节", flac.len());
+    }
+
+    #[test]
+    fn test_rejects_corruption() {
+        let samples: Vec<i16> = (0..5000).map(|i| (i % 300) as i16).collect();
+        let mut flac = encode_flac(&samples, 24000).unwrap();
+        let last = flac.len() - 3;
+        flac[last] ^= 0x10;
+        assert!(decode_flac(&flac).is_err());
+        assert!(decode_flac(b"RIFF").is_err());
+    }
+}
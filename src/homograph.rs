@@ -0,0 +1,341 @@
+//! 英语同形异音词消歧
+//!
+//! espeak-ng 对 read / lead / live / record / wind 等词只给固定读音。
+//! 这里在音素化之前按上下文选择词典 (data/en/homographs.txt) 中的读音:
+//!
+//! 1. 调用方提示 (`senses`: 词 → 义项)
+//! 2. 词性启发式: 前一个词是 to / 情态动词 / have / 冠词 / be / 主语代词, 后一个词是宾语
+//! 3. 提示词: 前后 4 个词内出现的搭配词 (lead pipe, bow and arrow)
+//! 4. 词典中的第一个读音
+//!
+//! 上下文只看同一个无标点片段 (由 `phonemize_preserving_punctuation` 切分)。
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+const HOMOGRAPH_TABLE: &str = include_str!("../data/en/homographs.txt");
+
+/// 提示词窗口 (前后各几个词)
+const CUE_WINDOW: usize = 4;
+
+/// 调用方提示: 词 (小写) → 义项
+pub type SenseHints = HashMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pos {
+    Verb,
+    Past,
+    Noun,
+    Adj,
+}
+
+impl Pos {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "verb" => Pos::Verb,
+            "past" => Pos::Past,
+            "noun" => Pos::Noun,
+            "adj" => Pos::Adj,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Variant {
+    sense: String,
+    ipa: String,
+    pos: Vec<Pos>,
+    cues: Vec<String>,
+}
+
+fn load_homographs() -> HashMap<String, Vec<Variant>> {
+    let mut table: HashMap<String, Vec<Variant>> = HashMap::new();
+    for line in HOMOGRAPH_TABLE.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let pos: Option<Vec<Pos>> = fields.get(3).and_then(|p| p.split(',').map(Pos::parse).collect());
+        match (fields.len(), pos) {
+            (4..=5, Some(pos)) => {
+                let cues = fields
+                    .get(4)
+                    .map(|c| c.split(',').map(str::to_string).collect())
+                    .unwrap_or_default();
+                table.entry(fields[0].to_string()).or_default().push(Variant {
+                    sense: fields[1].to_string(),
+                    ipa: fields[2].to_string(),
+                    pos,
+                    cues,
+                });
+            }
+            _ => warn!("⚠️ 无效同形词条目: {}", line),
+        }
+    }
+    table
+}
+
+lazy_static! {
+    static ref HOMOGRAPHS: HashMap<String, Vec<Variant>> = load_homographs();
+}
+
+const MODALS: &[&str] = &[
+    "will", "would", "can", "could", "shall", "should", "may", "might", "must", "do", "does", "did",
+    "don't", "doesn't", "didn't", "won't", "wouldn't", "can't", "cannot", "couldn't", "shouldn't",
+    "let's", "please", "not", "never", "always", "often", "usually", "also",
+];
+const HAVE: &[&str] = &["have", "has", "had", "having", "i've", "you've", "we've", "they've", "he's", "she's"];
+const BE: &[&str] = &["is", "are", "was", "were", "be", "been", "being", "am", "it's", "that's", "seems", "seemed", "feel", "felt"];
+const DETERMINERS: &[&str] = &[
+    "a", "an", "the", "this", "that", "these", "those", "my", "your", "his", "her", "its", "our",
+    "their", "some", "any", "no", "every", "each", "which", "whose", "another", "of", "in", "on",
+];
+const SUBJECT_3SG: &[&str] = &["he", "she", "it", "who"];
+const SUBJECTS: &[&str] = &["i", "you", "we", "they"];
+const OBJECTS: &[&str] = &[
+    "a", "an", "the", "this", "that", "these", "those", "my", "your", "his", "her", "its", "our",
+    "their", "it", "them", "him", "me", "us", "up", "down",
+];
+
+/// 消歧结果
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Resolution {
+    pub word: String,
+    pub sense: String,
+    pub ipa: String,
+    /// hint / pos / cue / default
+    pub reason: &'static str,
+}
+
+/// 词性启发式: 按可能性排序的词性
+fn predict_pos(words: &[String], i: usize) -> Vec<Pos> {
+    let prev = i.checked_sub(1).map(|p| words[p].as_str());
+    let next = words.get(i + 1).map(String::as_str);
+    let is = |word: Option<&str>, set: &[&str]| word.is_some_and(|w| set.contains(&w));
+
+    if prev == Some("to") || is(prev, MODALS) {
+        return vec![Pos::Verb];
+    }
+    if is(prev, HAVE) {
+        return vec![Pos::Past];
+    }
+    if is(prev, BE) {
+        return vec![Pos::Adj, Pos::Past];
+    }
+    if is(prev, DETERMINERS) {
+        return vec![Pos::Noun, Pos::Adj];
+    }
+    if is(prev, SUBJECT_3SG) {
+        // 第三人称单数后面的原形动词只能是过去式 (否则应是 reads)
+        return vec![Pos::Past, Pos::Verb];
+    }
+    if is(prev, SUBJECTS) || is(next, OBJECTS) {
+        return vec![Pos::Verb, Pos::Past];
+    }
+    Vec::new()
+}
+
+/// 提示词得分: 窗口内出现的提示词个数
+fn cue_score(variant: &Variant, words: &[String], i: usize) -> usize {
+    let start = i.saturating_sub(CUE_WINDOW);
+    let end = (i + CUE_WINDOW + 1).min(words.len());
+    (start..end)
+        .filter(|&j| j != i && variant.cues.iter().any(|c| c == &words[j]))
+        .count()
+}
+
+/// 为 `words[i]` 选择读音 (words 已转小写); 不是同形词时返回 None
+fn resolve(words: &[String], i: usize, hints: &SenseHints) -> Option<Resolution> {
+    let word = &words[i];
+    let variants = HOMOGRAPHS.get(word)?;
+    let pick = |variant: &Variant, reason| Resolution {
+        word: word.clone(),
+        sense: variant.sense.clone(),
+        ipa: variant.ipa.clone(),
+        reason,
+    };
+
+    if let Some(sense) = hints.get(word) {
+        if let Some(variant) = variants.iter().find(|v| &v.sense == sense) {
+            return Some(pick(variant, "hint"));
+        }
+    }
+
+    let predicted = predict_pos(words, i);
+    let compatible: Vec<&Variant> = variants
+        .iter()
+        .filter(|v| v.pos.iter().any(|p| predicted.contains(p)))
+        .collect();
+    if compatible.len() == 1 {
+        return Some(pick(compatible[0], "pos"));
+    }
+
+    // 词性无法区分时看提示词, 提示词也无法区分时按预测词性的顺序
+    let candidates: Vec<&Variant> = if compatible.is_empty() { variants.iter().collect() } else { compatible };
+    let scores: Vec<usize> = candidates.iter().map(|v| cue_score(v, words, i)).collect();
+    let best = scores.iter().copied().max().unwrap_or(0);
+    if best > 0 && scores.iter().filter(|&&s| s == best).count() == 1 {
+        let winner = scores.iter().position(|&s| s == best).unwrap_or(0);
+        return Some(pick(candidates[winner], "cue"));
+    }
+    for pos in &predicted {
+        if let Some(variant) = candidates.iter().find(|v| v.pos.contains(pos)) {
+            return Some(pick(variant, "pos"));
+        }
+    }
+    Some(pick(candidates[0], "default"))
+}
+
+/// 按空白切词, 返回 (起始字节, 结束字节, 小写词) — 去掉词两端的非字母字符
+fn split_words(run: &str) -> Vec<(usize, usize, String)> {
+    let mut words = Vec::new();
+    let mut offset = 0;
+    for piece in run.split_inclusive(char::is_whitespace) {
+        let trimmed = piece.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+        if !trimmed.is_empty() {
+            let start = offset + (trimmed.as_ptr() as usize - piece.as_ptr() as usize);
+            words.push((start, start + trimmed.len(), trimmed.to_lowercase()));
+        }
+        offset += piece.len();
+    }
+    words
+}
+
+/// 对一段文本做同形词消歧 (诊断用)
+pub fn disambiguate(run: &str, hints: &SenseHints) -> Vec<Resolution> {
+    let words: Vec<String> = split_words(run).into_iter().map(|(_, _, w)| w).collect();
+    (0..words.len()).filter_map(|i| resolve(&words, i, hints)).collect()
+}
+
+/// 音素化一个无标点片段: 同形词使用消歧后的 IPA, 其余文本交给 `phonemize_text`
+pub fn phonemize_run<F>(run: &str, hints: &SenseHints, mut phonemize_text: F) -> String
+where
+    F: FnMut(&str) -> String,
+{
+    let spans = split_words(run);
+    let words: Vec<String> = spans.iter().map(|(_, _, w)| w.clone()).collect();
+
+    let mut pieces: Vec<String> = Vec::new();
+    let mut last = 0;
+    for (i, &(start, end, _)) in spans.iter().enumerate() {
+        let Some(resolution) = resolve(&words, i, hints) else {
+            continue;
+        };
+        info!("🔀 同形词 '{}' → {} ({})", resolution.word, resolution.sense, resolution.reason);
+        let before = run[last..start].trim();
+        if !before.is_empty() {
+            pieces.push(phonemize_text(before));
+        }
+        pieces.push(resolution.ipa);
+        last = end;
+    }
+
+    if last == 0 {
+        return phonemize_text(run);
+    }
+    let rest = run[last..].trim();
+    if !rest.is_empty() {
+        pieces.push(phonemize_text(rest));
+    }
+    pieces
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 校验调用方提示: 词必须在词典中, 义项必须存在
+pub fn validate_hints(hints: &SenseHints) -> Result<(), String> {
+    for (word, sense) in hints {
+        let Some(variants) = HOMOGRAPHS.get(&word.to_lowercase()) else {
+            return Err(format!("'{}' 不是已知的同形异音词", word));
+        };
+        if !variants.iter().any(|v| &v.sense == sense) {
+            let senses: Vec<&str> = variants.iter().map(|v| v.sense.as_str()).collect();
+            return Err(format!("'{}' 没有义项 '{}' (可选: {})", word, sense, senses.join(", ")));
+        }
+    }
+    Ok(())
+}
+
+/// 规范化提示: 词统一转小写
+pub fn normalize_hints(hints: &SenseHints) -> SenseHints {
+    hints.iter().map(|(w, s)| (w.to_lowercase(), s.clone())).collect()
+}
+
+/// 提示的稳定字符串形式 (用于缓存键): "lead=metal,read=past"
+pub fn hints_key(hints: &SenseHints) -> String {
+    let mut pairs: Vec<String> = hints.iter().map(|(w, s)| format!("{}={}", w, s)).collect();
+    pairs.sort();
+    pairs.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每行: <词> <期望义项> | <句子>
+    const CORPUS: &str = include_str!("../data/en/homograph_corpus.txt");
+
+    fn sense_of(sentence: &str, word: &str, hints: &SenseHints) -> Option<String> {
+        disambiguate(sentence, hints)
+            .into_iter()
+            .find(|r| r.word == word)
+            .map(|r| r.sense)
+    }
+
+    #[test]
+    fn test_corpus() {
+        let mut failures = Vec::new();
+        let mut total = 0;
+        for line in CORPUS.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (spec, sentence) = line.split_once('|').expect("语料格式: <词> <义项> | <句子>");
+            let mut spec = spec.split_whitespace();
+            let (word, expected) = (spec.next().unwrap(), spec.next().unwrap());
+            total += 1;
+            let got = sense_of(sentence.trim(), word, &SenseHints::new());
+            if got.as_deref() != Some(expected) {
+                failures.push(format!("{:?}: {} 期望 {}, 得到 {:?}", sentence.trim(), word, expected, got));
+            }
+        }
+        assert!(total >= 40);
+        assert!(failures.is_empty(), "{} / {} 句失败:\n{}", failures.len(), total, failures.join("\n"));
+    }
+
+    #[test]
+    fn test_hints_override() {
+        let mut hints = SenseHints::new();
+        hints.insert("read".to_string(), "past".to_string());
+        assert_eq!(sense_of("I read books", "read", &SenseHints::new()).as_deref(), Some("present"));
+        assert_eq!(sense_of("I read books", "read", &hints).as_deref(), Some("past"));
+
+        assert!(validate_hints(&hints).is_ok());
+        hints.insert("read".to_string(), "future".to_string());
+        assert!(validate_hints(&hints).unwrap_err().contains("present, past"));
+        let unknown: SenseHints = [("cat".to_string(), "noun".to_string())].into();
+        assert!(validate_hints(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_phonemize_run_splices_ipa() {
+        let mut calls = Vec::new();
+        let out = phonemize_run("She read the old record.", &SenseHints::new(), |t| {
+            calls.push(t.to_string());
+            format!("<{}>", t)
+        });
+        assert_eq!(calls, vec!["She", "the old", "."]);
+        assert_eq!(out, "<She> ɹˈɛd <the old> ɹˈɛkɚd <.>");
+
+        let out = phonemize_run("no homographs here", &SenseHints::new(), |t| t.to_uppercase());
+        assert_eq!(out, "NO HOMOGRAPHS HERE");
+    }
+}
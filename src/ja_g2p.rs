@@ -0,0 +1,612 @@
+//! 日本語 G2P 前端 - jf_/jm_ 声音
+//!
+//! 流程:
+//! 1. 读音词典最长匹配: 汉字/送り仮名 → ひらがな (data/ja/readings.txt);
+//!    未收录的汉字逐字取音读 (data/ja/onyomi.txt), 仍无读音的记入 `unresolved`
+//! 2. 数字 → かな读法 (三百 → さんびゃく)
+//! 3. カタカナ → ひらがな
+//! 4. かな → Kokoro 音素 (拗音、促音、撥音同化、长音)
+
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::language::map_cjk_punctuation;
+use crate::zh_g2p::is_han;
+
+const READINGS_TABLE: &str = include_str!("../data/ja/readings.txt");
+const ONYOMI_TABLE: &str = include_str!("../data/ja/onyomi.txt");
+
+fn load_readings() -> HashMap<String, String> {
+    let mut table = HashMap::new();
+    for line in READINGS_TABLE.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once(' ') {
+            Some((word, reading)) => {
+                table.insert(word.to_string(), reading.trim().to_string());
+            }
+            None => warn!("⚠️ 无效读音条目: {}", line),
+        }
+    }
+    table
+}
+
+/// 单字读音表: 每行 `<ひらがな> <漢字...>`
+fn load_onyomi() -> HashMap<char, &'static str> {
+    let mut table = HashMap::new();
+    for line in ONYOMI_TABLE.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((reading, chars)) = line.split_once(' ') else {
+            warn!("⚠️ 无效音读条目: {}", line);
+            continue;
+        };
+        for c in chars.trim().chars() {
+            table.entry(c).or_insert(reading);
+        }
+    }
+    table
+}
+
+lazy_static! {
+    static ref READINGS: HashMap<String, String> = load_readings();
+    static ref ONYOMI: HashMap<char, &'static str> = load_onyomi();
+    static ref MAX_ENTRY_CHARS: usize = READINGS.keys().map(|k| k.chars().count()).max().unwrap_or(1);
+}
+
+/// 是否为平假名或片假名 (含长音符)
+pub fn is_kana(c: char) -> bool {
+    // 不含中点 ・ (U+30FB)
+    matches!(c as u32, 0x3041..=0x3096 | 0x309D..=0x309E | 0x30A1..=0x30FA | 0x30FC..=0x30FE | 0x31F0..=0x31FF)
+}
+
+/// カタカナ → ひらがな (ー 保持不变)
+fn to_hiragana(c: char) -> char {
+    match c as u32 {
+        0x30A1..=0x30F6 => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+// ============================================================
+// 数字读法
+// ============================================================
+
+const DIGIT_READINGS: [&str; 10] = ["", "いち", "に", "さん", "よん", "ご", "ろく", "なな", "はち", "きゅう"];
+
+/// 0-9999 的读法, 处理 さんびゃく / はっぴゃく / さんぜん 等音变
+fn read_section_ja(n: u64) -> String {
+    let mut out = String::new();
+
+    let thousands = (n / 1000 % 10) as usize;
+    out.push_str(match thousands {
+        0 => "",
+        1 => "せん",
+        3 => "さんぜん",
+        8 => "はっせん",
+        d => return format!("{}せん{}", DIGIT_READINGS[d], read_section_ja(n % 1000)),
+    });
+
+    let hundreds = (n / 100 % 10) as usize;
+    out.push_str(match hundreds {
+        0 => "",
+        1 => "ひゃく",
+        3 => "さんびゃく",
+        6 => "ろっぴゃく",
+        8 => "はっぴゃく",
+        d => return format!("{}{}ひゃく{}", out, DIGIT_READINGS[d], read_section_ja(n % 100)),
+    });
+
+    let tens = (n / 10 % 10) as usize;
+    match tens {
+        0 => {}
+        1 => out.push_str("じゅう"),
+        d => {
+            out.push_str(DIGIT_READINGS[d]);
+            out.push_str("じゅう");
+        }
+    }
+
+    out.push_str(DIGIT_READINGS[(n % 10) as usize]);
+    out
+}
+
+/// 整数读法: 2025 → にせんにじゅうご
+pub fn read_number_ja(n: u64) -> String {
+    if n == 0 {
+        return "ゼロ".to_string();
+    }
+
+    const UNITS: [&str; 4] = ["", "まん", "おく", "ちょう"];
+    let mut sections = Vec::new();
+    let mut rest = n;
+    while rest > 0 {
+        sections.push(rest % 10000);
+        rest /= 10000;
+    }
+
+    let mut out = String::new();
+    for (i, &section) in sections.iter().enumerate().rev() {
+        if section == 0 {
+            continue;
+        }
+        // 一万 读作 いちまん, 一千/一百 不读 いち
+        if i > 0 && section == 1 {
+            out.push_str("いち");
+        } else {
+            out.push_str(&read_section_ja(section));
+        }
+        out.push_str(UNITS[i.min(UNITS.len() - 1)]);
+    }
+    out
+}
+
+/// 数字后的助数词读音 (3時 → さんじ)
+fn counter_reading(c: char) -> Option<&'static str> {
+    Some(match c {
+        '時' => "じ",
+        '分' => "ふん",
+        '秒' => "びょう",
+        '円' => "えん",
+        '年' => "ねん",
+        '月' => "がつ",
+        '日' => "にち",
+        '人' => "にん",
+        '歳' => "さい",
+        '回' => "かい",
+        '番' => "ばん",
+        '度' => "ど",
+        '階' => "かい",
+        _ => return None,
+    })
+}
+
+/// 数字读法的最后一个成分: 助数词前的音变只发生在这里
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tail {
+    Digit(usize),
+    Ten,
+    Hundred,
+    Other,
+}
+
+/// 数字读法拆成 (前部, 末尾成分, 末尾读音): 14 → (じゅう, 4, よん), 20 → (に, 十, じゅう)
+fn split_number(n: u64) -> (String, Tail, &'static str) {
+    let reading = read_number_ja(n);
+    let (tail, suffix) = match (n % 10, n % 100, n % 1000) {
+        (0, 0, 100) => (Tail::Hundred, "ひゃく"),
+        (0, 0, _) => return (reading, Tail::Other, ""),
+        (0, _, _) => (Tail::Ten, "じゅう"),
+        (d, _, _) => (Tail::Digit(d as usize), DIGIT_READINGS[d as usize]),
+    };
+    let prefix = reading.strip_suffix(suffix).unwrap_or(&reading).to_string();
+    (prefix, tail, suffix)
+}
+
+/// 整个数字有固定读法的组合 (1日 → ついたち, 20歳 → はたち)
+fn whole_count_reading(n: u64, counter: char) -> Option<&'static str> {
+    Some(match (counter, n) {
+        ('人', 1) => "ひとり",
+        ('人', 2) => "ふたり",
+        ('日', 1) => "ついたち",
+        ('日', 2) => "ふつか",
+        ('日', 3) => "みっか",
+        ('日', 4) => "よっか",
+        ('日', 5) => "いつか",
+        ('日', 6) => "むいか",
+        ('日', 7) => "なのか",
+        ('日', 8) => "ようか",
+        ('日', 9) => "ここのか",
+        ('日', 10) => "とおか",
+        ('日', 14) => "じゅうよっか",
+        ('日', 20) => "はつか",
+        ('日', 24) => "にじゅうよっか",
+        ('歳', 20) => "はたち",
+        _ => return None,
+    })
+}
+
+/// 数字 + 助数词: 按末尾成分做促音化、半浊音化、浊音化 (1分 → いっぷん, 14時 → じゅうよじ)
+fn read_counted(n: u64, counter: char) -> String {
+    if let Some(reading) = whole_count_reading(n, counter) {
+        return reading.to_string();
+    }
+    let base = counter_reading(counter).unwrap_or("");
+    let (prefix, tail, tail_reading) = split_number(n);
+    // 1/6/8/10/100 + か・さ・は行助数词 → 促音
+    let sokuon = match tail {
+        Tail::Digit(1) => Some("いっ"),
+        Tail::Digit(6) => Some("ろっ"),
+        Tail::Digit(8) => Some("はっ"),
+        Tail::Ten => Some("じゅっ"),
+        Tail::Hundred => Some("ひゃっ"),
+        _ => None,
+    };
+    let (tail_override, counter_kana) = match (counter, tail) {
+        ('分', Tail::Digit(3 | 4)) => (None, "ぷん"),
+        ('分', _) if sokuon.is_some() => (sokuon, "ぷん"),
+        ('回' | '階', _) if sokuon.is_some() => (sokuon, base),
+        ('階', Tail::Digit(3)) => (None, "がい"),
+        ('歳', Tail::Digit(1 | 8) | Tail::Ten | Tail::Hundred) => (sokuon, base),
+        ('時' | '人' | '円' | '年', Tail::Digit(4)) => (Some("よ"), base),
+        ('月', Tail::Digit(4)) => (Some("し"), base),
+        ('時' | '月' | '日', Tail::Digit(7)) => (Some("しち"), base),
+        ('時' | '月' | '日', Tail::Digit(9)) => (Some("く"), base),
+        _ => (None, base),
+    };
+    format!("{}{}{}", prefix, tail_override.unwrap_or(tail_reading), counter_kana)
+}
+
+// ============================================================
+// かな → 音素
+// ============================================================
+
+/// 单个假名 (或拗音组合) 的音素
+fn mora_to_ipa(kana: &str) -> Option<&'static str> {
+    Some(match kana {
+        "あ" => "a", "い" => "i", "う" => "ɯ", "え" => "e", "お" => "o",
+        "か" => "ka", "き" => "ki", "く" => "kɯ", "け" => "ke", "こ" => "ko",
+        "が" => "ɡa", "ぎ" => "ɡi", "ぐ" => "ɡɯ", "げ" => "ɡe", "ご" => "ɡo",
+        "さ" => "sa", "し" => "ɕi", "す" => "sɯ", "せ" => "se", "そ" => "so",
+        "ざ" => "za", "じ" => "ʥi", "ず" => "zɯ", "ぜ" => "ze", "ぞ" => "zo",
+        "た" => "ta", "ち" => "ʨi", "つ" => "ʦɯ", "て" => "te", "と" => "to",
+        "だ" => "da", "ぢ" => "ʥi", "づ" => "zɯ", "で" => "de", "ど" => "do",
+        "な" => "na", "に" => "ɲi", "ぬ" => "nɯ", "ね" => "ne", "の" => "no",
+        "は" => "ha", "ひ" => "çi", "ふ" => "ɸɯ", "へ" => "he", "ほ" => "ho",
+        "ば" => "ba", "び" => "bi", "ぶ" => "bɯ", "べ" => "be", "ぼ" => "bo",
+        "ぱ" => "pa", "ぴ" => "pi", "ぷ" => "pɯ", "ぺ" => "pe", "ぽ" => "po",
+        "ま" => "ma", "み" => "mi", "む" => "mɯ", "め" => "me", "も" => "mo",
+        "や" => "ja", "ゆ" => "jɯ", "よ" => "jo",
+        "ら" => "ɾa", "り" => "ɾi", "る" => "ɾɯ", "れ" => "ɾe", "ろ" => "ɾo",
+        "わ" => "wa", "ゐ" => "i", "ゑ" => "e", "を" => "o", "ん" => "ɴ",
+        "ゔ" => "vɯ",
+        "ぁ" => "a", "ぃ" => "i", "ぅ" => "ɯ", "ぇ" => "e", "ぉ" => "o",
+        "ゃ" => "ja", "ゅ" => "jɯ", "ょ" => "jo", "ゎ" => "wa",
+        // 拗音
+        "きゃ" => "kja", "きゅ" => "kjɯ", "きょ" => "kjo",
+        "ぎゃ" => "ɡja", "ぎゅ" => "ɡjɯ", "ぎょ" => "ɡjo",
+        "しゃ" => "ɕa", "しゅ" => "ɕɯ", "しょ" => "ɕo", "しぇ" => "ɕe",
+        "じゃ" => "ʥa", "じゅ" => "ʥɯ", "じょ" => "ʥo", "じぇ" => "ʥe",
+        "ちゃ" => "ʨa", "ちゅ" => "ʨɯ", "ちょ" => "ʨo", "ちぇ" => "ʨe",
+        "にゃ" => "ɲa", "にゅ" => "ɲɯ", "にょ" => "ɲo",
+        "ひゃ" => "ça", "ひゅ" => "çɯ", "ひょ" => "ço",
+        "びゃ" => "bja", "びゅ" => "bjɯ", "びょ" => "bjo",
+        "ぴゃ" => "pja", "ぴゅ" => "pjɯ", "ぴょ" => "pjo",
+        "みゃ" => "mja", "みゅ" => "mjɯ", "みょ" => "mjo",
+        "りゃ" => "ɾja", "りゅ" => "ɾjɯ", "りょ" => "ɾjo",
+        // 外来语
+        "ふぁ" => "ɸa", "ふぃ" => "ɸi", "ふぇ" => "ɸe", "ふぉ" => "ɸo",
+        "てぃ" => "ti", "でぃ" => "di", "とぅ" => "tɯ", "どぅ" => "dɯ",
+        "うぃ" => "wi", "うぇ" => "we", "うぉ" => "wo",
+        "ゔぁ" => "va", "ゔぃ" => "vi", "ゔぇ" => "ve", "ゔぉ" => "vo",
+        "つぁ" => "ʦa", "つぃ" => "ʦi", "つぇ" => "ʦe", "つぉ" => "ʦo",
+        _ => return None,
+    })
+}
+
+fn is_small_kana(c: char) -> bool {
+    matches!(c, 'ゃ' | 'ゅ' | 'ょ' | 'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' | 'ゎ')
+}
+
+fn last_vowel(ipa: &str) -> Option<char> {
+    ipa.chars().rev().find(|c| matches!(c, 'a' | 'i' | 'ɯ' | 'e' | 'o'))
+}
+
+/// 促音 (っ) 重复下一个音拍的首辅音; 塞擦音只重复闭塞部分
+fn geminate(next: &str) -> String {
+    match next.chars().next() {
+        Some('ʨ') | Some('ʦ') => "t".to_string(),
+        Some('ʥ') => "d".to_string(),
+        Some(c) if last_vowel(&c.to_string()).is_none() && c != 'j' && c != 'w' => c.to_string(),
+        _ => "ʔ".to_string(),
+    }
+}
+
+/// 撥音 (ん) 按后一个音同化
+fn moraic_nasal(next: Option<&str>) -> &'static str {
+    match next.and_then(|n| n.chars().next()) {
+        Some('p') | Some('b') | Some('m') => "m",
+        Some('t') | Some('d') | Some('n') | Some('ɾ') | Some('ʦ') | Some('ʨ') | Some('ʥ') | Some('z') => "n",
+        Some('k') | Some('ɡ') => "ŋ",
+        Some('ɲ') => "ɲ",
+        _ => "ɴ",
+    }
+}
+
+/// ひらがな/カタカナ串 → Kokoro 音素
+pub fn kana_to_ipa(kana: &str) -> String {
+    let chars: Vec<char> = kana.chars().map(to_hiragana).collect();
+
+    // 切分音拍: 先尝试 "假名 + 小写假名" 拗音
+    let mut morae: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == 'っ' || c == 'ー' {
+            morae.push(c.to_string());
+            i += 1;
+            continue;
+        }
+        if let Some(&small) = chars.get(i + 1) {
+            if is_small_kana(small) {
+                let pair: String = [c, small].iter().collect();
+                if mora_to_ipa(&pair).is_some() {
+                    morae.push(pair);
+                    i += 2;
+                    continue;
+                }
+            }
+        }
+        morae.push(c.to_string());
+        i += 1;
+    }
+
+    let ipa_of = |m: &str| mora_to_ipa(m).map(str::to_string);
+
+    let mut out = String::new();
+    let mut prev_vowel: Option<char> = None;
+    for (idx, mora) in morae.iter().enumerate() {
+        let next_ipa = morae.get(idx + 1).and_then(|m| ipa_of(m));
+        match mora.as_str() {
+            "っ" => {
+                out.push_str(&next_ipa.as_deref().map(geminate).unwrap_or_else(|| "ʔ".to_string()));
+                prev_vowel = None;
+            }
+            "ー" => {
+                if prev_vowel.is_some() {
+                    out.push('ː');
+                }
+            }
+            "ん" => {
+                out.push_str(moraic_nasal(next_ipa.as_deref()));
+                prev_vowel = None;
+            }
+            _ => match ipa_of(mora) {
+                Some(ipa) => {
+                    // 长音: おう → oː, えい → eː, 同元音连续 → ː
+                    let long = match (prev_vowel, ipa.as_str()) {
+                        (Some('o'), "ɯ") | (Some('e'), "i") => true,
+                        (Some(v), vowel) if vowel.chars().count() == 1 => vowel.starts_with(v),
+                        _ => false,
+                    };
+                    if long {
+                        out.push('ː');
+                        prev_vowel = None;
+                    } else {
+                        prev_vowel = last_vowel(&ipa);
+                        out.push_str(&ipa);
+                    }
+                }
+                None => {
+                    warn!("⚠️ 无法识别的假名: {}", mora);
+                    prev_vowel = None;
+                }
+            },
+        }
+    }
+    out
+}
+
+// ============================================================
+// 混合文本 → 音素
+// ============================================================
+
+/// 在 `chars[start..]` 上做词典最长匹配, 返回 (读音, 匹配字数)
+fn longest_match(chars: &[char], start: usize) -> Option<(&'static str, usize)> {
+    let max_len = (*MAX_ENTRY_CHARS).min(chars.len() - start);
+    (1..=max_len).rev().find_map(|len| {
+        let candidate: String = chars[start..start + len].iter().collect();
+        READINGS.get(&candidate).map(|r| (r.as_str(), len))
+    })
+}
+
+/// 日文文本 → Kokoro 音素串
+///
+/// 非日文片段 (英文单词等) 交给 `fallback` 处理; 两张读音表都没有的汉字被丢弃并记入 `unresolved`。
+pub fn phonemize<F>(text: &str, mut fallback: F, unresolved: &mut Vec<char>) -> String
+where
+    F: FnMut(&str) -> String,
+{
+    let chars: Vec<char> = text.chars().collect();
+    let mut pieces: Vec<String> = Vec::new();
+    let mut kana = String::new();
+    let mut other = String::new();
+
+    let flush_kana = |kana: &mut String, pieces: &mut Vec<String>| {
+        if !kana.is_empty() {
+            pieces.push(kana_to_ipa(kana));
+            kana.clear();
+        }
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let is_japanese = is_kana(c) || is_han(c) || c == '々';
+
+        if is_japanese || c.is_ascii_digit() || map_cjk_punctuation(c).is_some() || c.is_whitespace() {
+            if !other.trim().is_empty() {
+                flush_kana(&mut kana, &mut pieces);
+                pieces.push(fallback(other.trim()));
+            }
+            other.clear();
+        }
+
+        if is_japanese {
+            // 助词 は/へ: 紧跟在汉字词之后且后面不是平假名时读 わ/え
+            let after_kanji = i > 0 && (is_han(chars[i - 1]) || chars[i - 1] == '々');
+            let before_hiragana = chars.get(i + 1).is_some_and(|&n| ('\u{3041}'..='\u{3096}').contains(&n));
+            if (c == 'は' || c == 'へ') && after_kanji && !before_hiragana {
+                kana.push(if c == 'は' { 'わ' } else { 'え' });
+                i += 1;
+            } else if let Some((reading, len)) = longest_match(&chars, i) {
+                kana.push_str(reading);
+                i += len;
+            } else if is_kana(c) {
+                kana.push(c);
+                i += 1;
+            } else if let Some(reading) = ONYOMI.get(&c) {
+                kana.push_str(reading);
+                i += 1;
+            } else {
+                warn!("⚠️ 读音词典缺少汉字: {}", c);
+                unresolved.push(c);
+                i += 1;
+            }
+        } else if c.is_ascii_digit() {
+            let end = (i..chars.len()).find(|&j| !chars[j].is_ascii_digit()).unwrap_or(chars.len());
+            let digits: String = chars[i..end].iter().collect();
+            let counter = chars.get(end).copied().filter(|&c| counter_reading(c).is_some());
+            match (digits.parse::<u64>(), counter) {
+                (Ok(n), Some(counter)) => kana.push_str(&read_counted(n, counter)),
+                (Ok(n), None) => kana.push_str(&read_number_ja(n)),
+                (Err(_), _) => {
+                    digits
+                        .chars()
+                        .filter_map(|d| d.to_digit(10))
+                        .for_each(|d| kana.push_str(if d == 0 { "ぜろ" } else { DIGIT_READINGS[d as usize] }));
+                    if let Some(reading) = counter.and_then(counter_reading) {
+                        kana.push_str(reading);
+                    }
+                }
+            }
+            i = if counter.is_some() { end + 1 } else { end };
+        } else if let Some(p) = map_cjk_punctuation(c) {
+            flush_kana(&mut kana, &mut pieces);
+            match pieces.last_mut() {
+                Some(last) => last.push_str(p),
+                None => pieces.push(p.to_string()),
+            }
+            i += 1;
+        } else if c.is_whitespace() || c == '・' {
+            flush_kana(&mut kana, &mut pieces);
+            i += 1;
+        } else {
+            flush_kana(&mut kana, &mut pieces);
+            other.push(c);
+            i += 1;
+        }
+    }
+    flush_kana(&mut kana, &mut pieces);
+    if !other.trim().is_empty() {
+        pieces.push(fallback(other.trim()));
+    }
+
+    pieces
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phonemize_ja(text: &str) -> String {
+        phonemize(text, |other| format!("<{}>", other), &mut Vec::new())
+    }
+
+    #[test]
+    fn test_kana_basic() {
+        assert_eq!(kana_to_ipa("さくら"), "sakɯɾa");
+        assert_eq!(kana_to_ipa("ありがとう"), "aɾiɡatoː");
+        assert_eq!(kana_to_ipa("しんぶん"), "ɕimbɯɴ");
+    }
+
+    #[test]
+    fn test_kana_yoon_sokuon() {
+        assert_eq!(kana_to_ipa("きょう"), "kjoː");
+        assert_eq!(kana_to_ipa("がっこう"), "ɡakkoː");
+        assert_eq!(kana_to_ipa("まっちゃ"), "matʨa");
+    }
+
+    #[test]
+    fn test_katakana() {
+        assert_eq!(kana_to_ipa("コーヒー"), "koːçiː");
+        assert_eq!(kana_to_ipa("パーティー"), "paːtiː");
+    }
+
+    #[test]
+    fn test_kana_only_sentence() {
+        assert_eq!(phonemize_ja("こんにちは。"), "koɲɲiʨiwa.");
+        assert_eq!(phonemize_ja("おはよう、さくら！"), "ohajoː, sakɯɾa!");
+    }
+
+    #[test]
+    fn test_mixed_script_sentence() {
+        assert_eq!(phonemize_ja("日本語を話します。"), "ɲihoŋɡoːhanaɕimasɯ.");
+        assert_eq!(phonemize_ja("今日は良い天気ですね？"), "kjoːwajoiteŋkidesɯne?");
+        assert_eq!(phonemize_ja("私はKokoroです"), "wataɕiwa <Kokoro> desɯ");
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(read_number_ja(300), "さんびゃく");
+        assert_eq!(read_number_ja(2025), "にせんにじゅうご");
+        assert_eq!(read_number_ja(10000), "いちまん");
+        assert_eq!(phonemize_ja("3時"), "sanʥi");
+        assert_eq!(phonemize_ja("2人"), "ɸɯtaɾi");
+    }
+
+    #[test]
+    fn test_irregular_counters() {
+        assert_eq!(phonemize_ja("4時"), kana_to_ipa("よじ"));
+        assert_eq!(phonemize_ja("9時"), kana_to_ipa("くじ"));
+        assert_eq!(phonemize_ja("4月"), kana_to_ipa("しがつ"));
+        assert_eq!(phonemize_ja("1分"), kana_to_ipa("いっぷん"));
+        assert_eq!(phonemize_ja("3分"), kana_to_ipa("さんぷん"));
+        assert_eq!(phonemize_ja("1回"), kana_to_ipa("いっかい"));
+        assert_eq!(phonemize_ja("4人"), kana_to_ipa("よにん"));
+        assert_eq!(phonemize_ja("1日"), kana_to_ipa("ついたち"));
+        assert_eq!(phonemize_ja("2日"), kana_to_ipa("ふつか"));
+        // 音变看末位: 14時、19時、24人
+        assert_eq!(phonemize_ja("14時"), kana_to_ipa("じゅうよじ"));
+        assert_eq!(phonemize_ja("19時"), kana_to_ipa("じゅうくじ"));
+        assert_eq!(phonemize_ja("24人"), kana_to_ipa("にじゅうよにん"));
+        // 分: 促音 + 半浊音
+        assert_eq!(phonemize_ja("4分"), kana_to_ipa("よんぷん"));
+        assert_eq!(phonemize_ja("6分"), kana_to_ipa("ろっぷん"));
+        assert_eq!(phonemize_ja("8分"), kana_to_ipa("はっぷん"));
+        assert_eq!(phonemize_ja("10分"), kana_to_ipa("じゅっぷん"));
+        assert_eq!(phonemize_ja("30分"), kana_to_ipa("さんじゅっぷん"));
+        assert_eq!(phonemize_ja("5分"), kana_to_ipa("ごふん"));
+        assert_eq!(phonemize_ja("100分"), kana_to_ipa("ひゃっぷん"));
+        // 回・階・歳
+        assert_eq!(phonemize_ja("6回"), kana_to_ipa("ろっかい"));
+        assert_eq!(phonemize_ja("3階"), kana_to_ipa("さんがい"));
+        assert_eq!(phonemize_ja("18歳"), kana_to_ipa("じゅうはっさい"));
+        assert_eq!(phonemize_ja("20歳"), kana_to_ipa("はたち"));
+        // 日・月
+        assert_eq!(phonemize_ja("3日"), kana_to_ipa("みっか"));
+        assert_eq!(phonemize_ja("10日"), kana_to_ipa("とおか"));
+        assert_eq!(phonemize_ja("14日"), kana_to_ipa("じゅうよっか"));
+        assert_eq!(phonemize_ja("20日"), kana_to_ipa("はつか"));
+        assert_eq!(phonemize_ja("24日"), kana_to_ipa("にじゅうよっか"));
+        assert_eq!(phonemize_ja("17日"), kana_to_ipa("じゅうしちにち"));
+        assert_eq!(phonemize_ja("7月"), kana_to_ipa("しちがつ"));
+        assert_eq!(phonemize_ja("9月"), kana_to_ipa("くがつ"));
+        assert_eq!(phonemize_ja("12月"), kana_to_ipa("じゅうにがつ"));
+    }
+
+    #[test]
+    fn test_onyomi_fallback() {
+        // 词典未收录的词逐字取音读
+        assert!(!READINGS.contains_key("議論"));
+        assert_eq!(phonemize_ja("議論"), "ɡiɾoɴ");
+        assert_eq!(phonemize_ja("鑑賞"), "kaɴɕoː");
+    }
+
+    #[test]
+    fn test_unresolved_chars() {
+        let mut unresolved = Vec::new();
+        let out = phonemize("議会の㐂", |other| other.to_string(), &mut unresolved);
+        assert_eq!(out, "ɡikaino");
+        assert_eq!(unresolved, vec!['㐂']);
+    }
+}
@@ -0,0 +1,80 @@
+//! 语言识别
+//!
+//! 根据声音名前缀 (Kokoro 命名: 首字母表示语言) 和文本字符选择 G2P 前端
+
+use serde::Serialize;
+
+use crate::ja_g2p;
+use crate::zh_g2p;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// 英语 (espeak-ng)
+    English,
+    /// 普通话 (zh_g2p)
+    Mandarin,
+    /// 日语 (ja_g2p)
+    Japanese,
+}
+
+impl Language {
+    /// 根据声音名推断语言: zf_xiaobei → 普通话, jf_alpha → 日语
+    pub fn from_voice(voice: &str) -> Self {
+        match voice.chars().next() {
+            Some('z') => Language::Mandarin,
+            Some('j') => Language::Japanese,
+            _ => Language::English,
+        }
+    }
+
+    /// 选择 G2P 语言
+    ///
+    /// - 含假名 → 日语
+    /// - 只含汉字 → 日语声音按日语读, 其他按普通话读
+    /// - 中日声音始终走各自前端 (夹杂的英文由前端回退到 espeak-ng)
+    pub fn detect(text: &str, voice: &str) -> Self {
+        let voice_language = Self::from_voice(voice);
+        if text.chars().any(ja_g2p::is_kana) {
+            return Language::Japanese;
+        }
+        if text.chars().any(zh_g2p::is_han) && voice_language == Language::English {
+            return Language::Mandarin;
+        }
+        voice_language
+    }
+}
+
+/// 中日文标点 → Kokoro 词汇表中的标点
+pub fn map_cjk_punctuation(c: char) -> Option<&'static str> {
+    Some(match c {
+        '，' | '、' | ',' => ",",
+        '。' | '．' | '.' => ".",
+        '！' | '!' => "!",
+        '？' | '?' => "?",
+        '：' | ':' => ":",
+        '；' | ';' => ";",
+        '…' => "…",
+        '—' | '－' => "—",
+        '“' | '「' | '『' | '《' | '【' | '«' => "“",
+        '”' | '」' | '』' | '》' | '】' | '»' => "”",
+        '（' | '(' => "(",
+        '）' | ')' => ")",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Language::detect("Hello", "af_alloy"), Language::English);
+        assert_eq!(Language::detect("Hello", "zf_xiaobei"), Language::Mandarin);
+        assert_eq!(Language::detect("你好", "af_alloy"), Language::Mandarin);
+        assert_eq!(Language::detect("こんにちは", "af_alloy"), Language::Japanese);
+        assert_eq!(Language::detect("日本語", "jf_gongitsune"), Language::Japanese);
+        assert_eq!(Language::detect("日本語", "zf_xiaobei"), Language::Mandarin);
+    }
+}
@@ -1,25 +1,59 @@
 use axum::{
-    extract::Path,
-    routing::{get, post},
+    body::Bytes,
+    extract::{Path, Query},
+    routing::{delete, get, post},
     Router,
     Json,
     http::{StatusCode, header},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::net::SocketAddr;
 use std::sync::OnceLock;
+use std::time::Instant;
 use tower_http::cors::{CorsLayer, Any};
-use tracing::{info, error, Level};
+use tracing::{info, error, warn, Level};
 
+mod audio_format;
 mod cache;
+mod diagnostics;
+mod espeak;
+mod flac;
+mod homograph;
+mod ja_g2p;
+mod language;
+mod mp3_encoder;
+mod ogg;
+mod opus_encoder;
+mod resampler;
+mod ssml;
+mod style_space;
+mod style_table;
+mod subprocess;
 mod tts_engine;
+mod verbalize;
 mod vocab;
+mod voice_alias;
+mod voice_import;
+mod voice_preview;
+mod voice_store;
+mod voices;
 mod wav_encoder;
-
-use cache::AudioCache;
-use tts_engine::TTSEngine;
-use wav_encoder::encode_wav;
+mod zh_g2p;
+
+use audio_format::{AudioFormat, EncodeOptions};
+use cache::{AudioCache, CacheStorage};
+use diagnostics::SynthesisDiagnostics;
+use homograph::SenseHints;
+use tts_engine::{Synthesis, SynthesisInput, TTSEngine, TextOptions};
+use verbalize::SymbolOptions;
+use style_space::DesignError;
+use voice_alias::UnknownVoice;
+use vocab::Vocab;
+use voice_preview::PreviewStore;
+use voices::{Gender, VoiceError, VoiceMetadata};
+use wav_encoder::{encode_wav, PcmFormat};
 
 // 全局 TTS 引擎 (单例模式)
 static TTS_ENGINE: OnceLock<std::sync::Mutex<TTSEngine>> = OnceLock::new();
@@ -27,6 +61,19 @@ static TTS_ENGINE: OnceLock<std::sync::Mutex<TTSEngine>> = OnceLock::new();
 // 全局音频缓存 (单例模式)
 static AUDIO_CACHE: OnceLock<AudioCache> = OnceLock::new();
 
+// 声音试听片段
+static PREVIEWS: OnceLock<PreviewStore> = OnceLock::new();
+
+// 模型文件标识 (音频缓存键)
+static MODEL_FINGERPRINT: OnceLock<String> = OnceLock::new();
+
+/// 音频缓存目录和过期时间 (秒)
+const CACHE_DIR: &str = "cache/audio";
+const CACHE_TTL: u64 = 3600;
+
+/// Kokoro ONNX 模型
+const MODEL_PATH: &str = "checkpoints/kokoro-v1.0.onnx";
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ApiResponse<T> {
     success: bool,
@@ -58,26 +105,500 @@ async fn get_server_info() -> Json<ApiResponse<ServerInfo>> {
     })
 }
 
+#[derive(Debug, Serialize)]
+struct HealthStatus {
+    /// healthy / degraded (有声音加载失败)
+    status: String,
+    engine_loaded: bool,
+    /// 加载失败的声音: 名称 → 错误信息
+    voice_errors: BTreeMap<String, String>,
+}
+
 /// GET /health - Health check
-async fn health_check() -> Json<ApiResponse<String>> {
+async fn health_check() -> Json<ApiResponse<HealthStatus>> {
+    let voice_errors = voices::load_errors();
+    let status = if voice_errors.is_empty() { "healthy" } else { "degraded" };
     Json(ApiResponse {
         success: true,
-        data: Some("healthy".to_string()),
+        data: Some(HealthStatus {
+            status: status.to_string(),
+            engine_loaded: TTS_ENGINE.get().is_some(),
+            voice_errors,
+        }),
         error: None,
     })
 }
 
 #[derive(Debug, Deserialize)]
 struct SynthesizeRequest {
-    text: String,
-    #[serde(default = "default_format")]
-    #[allow(dead_code)]
-    format: String,  // 保留用于未来扩展 (mp3, ogg等)
+    /// 要合成的文本 (text / phonemes / tokens / ssml 四选一)
+    text: Option<String>,
+    /// IPA 音素串: 跳过 G2P, 直接 tokenize
+    phonemes: Option<String>,
+    /// token ID 数组: 跳过 G2P 和 tokenize, 直接推理
+    tokens: Option<Vec<i64>>,
+    /// SSML 子集 (<speak> 根元素)
+    ssml: Option<String>,
+    /// 英语同形异音词读音提示: {"read": "past"}
+    #[serde(default)]
+    senses: SenseHints,
+    /// 符号/emoji 读法: {"emoji": "skip", "arrow": "describe"}
+    #[serde(default)]
+    symbols: SymbolOptions,
+    /// 输出格式: wav (默认) / mp3 / ogg / flac
+    #[serde(default)]
+    format: AudioFormat,
+    /// 有损格式的码率 (kbps), mp3 默认 64, ogg 默认 24
+    bitrate: Option<u32>,
+    /// 输出采样率 (Hz): 8000 / 16000 / 22050 / 24000 / 44100 / 48000, 默认 24000
+    sample_rate: Option<u32>,
+    /// WAV 样本格式: s16 (默认, TPDF 抖动) / s24 / f32
+    #[serde(default)]
+    sample_format: PcmFormat,
     voice: Option<String>,  // 可选的声音参数
 }
 
-fn default_format() -> String {
-    "wav".to_string()
+impl SynthesizeRequest {
+    /// 解析输入模式, 必须且只能提供 text / phonemes / tokens / ssml 中的一个
+    fn input(&self) -> Result<SynthesisInput, String> {
+        match (&self.text, &self.phonemes, &self.tokens, &self.ssml) {
+            (Some(text), None, None, None) => Ok(SynthesisInput::Text(text.clone())),
+            (None, Some(phonemes), None, None) => Ok(SynthesisInput::Phonemes(phonemes.clone())),
+            (None, None, Some(tokens), None) => Ok(SynthesisInput::Tokens(tokens.clone())),
+            (None, None, None, Some(ssml)) => Ok(SynthesisInput::Ssml(ssml.clone())),
+            (None, None, None, None) => Err("缺少输入: 需要 text、phonemes、tokens 或 ssml".to_string()),
+            _ => Err("text、phonemes、tokens、ssml 只能提供一个".to_string()),
+        }
+    }
+}
+
+/// 文本前端选项: 校验并规范化同形词提示
+fn text_options(senses: &SenseHints, symbols: SymbolOptions) -> Result<TextOptions, String> {
+    let senses = homograph::normalize_hints(senses);
+    homograph::validate_hints(&senses)?;
+    Ok(TextOptions { senses, symbols })
+}
+
+/// JSON 错误响应
+fn json_error(status: StatusCode, message: String) -> (StatusCode, [(header::HeaderName, &'static str); 1], String) {
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/json")],
+        serde_json::json!({ "error": message }).to_string(),
+    )
+}
+
+/// 声音不存在: 404, 附带最接近的声音名
+fn unknown_voice_error(e: &UnknownVoice) -> (StatusCode, [(header::HeaderName, &'static str); 1], String) {
+    error!("❌ {}", e);
+    (
+        StatusCode::NOT_FOUND,
+        [(header::CONTENT_TYPE, "application/json")],
+        serde_json::json!({ "error": e.to_string(), "suggestions": e.suggestions }).to_string(),
+    )
+}
+
+/// 解析请求中的声音: 别名 → 声音名; 未指定时按文本语言选择默认声音
+fn resolve_voice(requested: Option<&str>, text: Option<&str>) -> Result<String, UnknownVoice> {
+    let aliases = voice_alias::aliases();
+    match requested {
+        Some(name) => voice_alias::resolve_installed(name),
+        None => Ok(text.map_or(aliases.default_voice(), |text| aliases.default_for_text(text)).to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PhonemizeRequest {
+    text: String,
+    voice: Option<String>,
+    #[serde(default)]
+    senses: SenseHints,
+    #[serde(default)]
+    symbols: SymbolOptions,
+}
+
+/// POST /phonemize - 诊断: 返回规范化文本、音素、token、被丢弃字符、分段和时长估计 (不推理)
+async fn phonemize(
+    Json(payload): Json<PhonemizeRequest>
+) -> impl IntoResponse {
+    if payload.text.trim().is_empty() {
+        return json_error(StatusCode::BAD_REQUEST, "text 不能为空".to_string());
+    }
+    let options = match text_options(&payload.senses, payload.symbols) {
+        Ok(options) => options,
+        Err(message) => return json_error(StatusCode::BAD_REQUEST, message),
+    };
+    let voice = match resolve_voice(payload.voice.as_deref(), Some(&payload.text)) {
+        Ok(voice) => voice,
+        Err(e) => return unknown_voice_error(&e),
+    };
+    let preview: String = payload.text.chars().take(50).collect();
+    info!("🔍 音素诊断请求: \"{}\" (声音: {})", preview, voice);
+
+    let vocab = match model_vocab() {
+        Ok(vocab) => vocab,
+        Err(e) => return json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("无法加载词汇表: {:#}", e)),
+    };
+
+    // espeak-ng 是阻塞调用
+    let text = payload.text.clone();
+    match tokio::task::spawn_blocking(move || diagnostics::analyze(&text, &voice, &vocab, &options)).await {
+        Ok(Ok(report)) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            serde_json::to_string(&report).unwrap(),
+        ),
+        Ok(Err(e)) => {
+            error!("❌ 音素化失败: {}", e);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("音素化失败: {}", e))
+        }
+        Err(e) => {
+            error!("❌ 音素诊断失败: {}", e);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("音素诊断失败: {}", e))
+        }
+    }
+}
+
+/// 模型的词汇表: 引擎已加载时用引擎的, 否则按引擎相同的规则读取 (不加载模型)
+fn model_vocab() -> anyhow::Result<Vocab> {
+    match TTS_ENGINE.get() {
+        Some(engine) => Ok(engine.lock().unwrap().vocab().clone()),
+        None => Vocab::for_model(MODEL_PATH),
+    }
+}
+
+/// 获取或初始化音频缓存
+fn audio_cache() -> &'static AudioCache {
+    AUDIO_CACHE.get_or_init(|| {
+        info!("🔧 初始化音频缓存...");
+        AudioCache::new(CACHE_DIR, CACHE_TTL).expect("无法初始化缓存")
+    })
+}
+
+/// 获取或初始化声音试听目录 (缓存目录下, 不参与 TTL 清理)
+fn previews() -> &'static PreviewStore {
+    PREVIEWS.get_or_init(|| PreviewStore::new(audio_cache().preview_dir()).expect("无法初始化试听目录"))
+}
+
+/// 获取或初始化 TTS 引擎
+fn tts_engine() -> &'static std::sync::Mutex<TTSEngine> {
+    TTS_ENGINE.get_or_init(|| {
+        info!("🔧 首次初始化 TTS 引擎...");
+
+        match TTSEngine::new(MODEL_PATH) {
+            Ok(engine) => {
+                info!("✅ TTS 引擎初始化成功");
+                std::sync::Mutex::new(engine)
+            },
+            Err(e) => {
+                error!("❌ TTS 引擎初始化失败: {}", e);
+                panic!("无法加载 TTS 模型: {}", e);
+            }
+        }
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct VoicesQuery {
+    /// 语言代码: en / en-gb / zh / ja ...
+    language: Option<String>,
+    gender: Option<Gender>,
+}
+
+/// GET /voices - 声音列表 (元数据来自 data/voices/index.json)
+async fn list_voices(Query(query): Query<VoicesQuery>) -> impl IntoResponse {
+    match voices::list(voices::VOICES_DIR, query.language.as_deref(), query.gender) {
+        Ok(voices) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            serde_json::json!({ "voices": voices }).to_string(),
+        ),
+        Err(e) => {
+            error!("❌ 读取声音列表失败: {:#}", e);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("读取声音列表失败: {:#}", e))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SimilarQuery {
+    language: Option<String>,
+    gender: Option<Gender>,
+    /// 返回几个声音 (默认 5)
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct SimilarVoice {
+    #[serde(flatten)]
+    info: voices::VoiceInfo,
+    /// 平均 style 向量的余弦相似度
+    similarity: f32,
+}
+
+/// GET /voices/:name/similar?language=&gender=&limit= - 按 style 向量找相似的声音
+async fn similar_voices(Path(name): Path<String>, Query(query): Query<SimilarQuery>) -> impl IntoResponse {
+    let name = match resolve_voice(Some(&name), None) {
+        Ok(name) => name,
+        Err(e) => return unknown_voice_error(&e),
+    };
+    let candidates = match voices::list(voices::VOICES_DIR, query.language.as_deref(), query.gender) {
+        Ok(list) => list.into_iter().map(|info| (info.name.clone(), info)).collect::<BTreeMap<_, _>>(),
+        Err(e) => {
+            error!("❌ 读取声音列表失败: {:#}", e);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("读取声音列表失败: {:#}", e));
+        }
+    };
+
+    let store = tts_engine().lock().unwrap().voices();
+    let space = store.style_space();
+    let Some(neighbors) = space.nearest(&name, query.limit.unwrap_or(5), |other| candidates.contains_key(other)) else {
+        // 在索引中但加载失败的声音
+        let reason = voices::load_errors().remove(&name).unwrap_or_default();
+        return json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("声音 '{}' 不可用: {}", name, reason));
+    };
+
+    let similar: Vec<SimilarVoice> = neighbors
+        .into_iter()
+        .filter_map(|n| candidates.get(&n.name).map(|info| SimilarVoice { info: info.clone(), similarity: n.similarity }))
+        .collect();
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        serde_json::json!({ "voice": name, "similar": similar }).to_string(),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct VoiceUploadQuery {
+    name: String,
+    language: Option<String>,
+    accent: Option<String>,
+    gender: Option<Gender>,
+    display_name: Option<String>,
+}
+
+fn voice_error_status(e: &VoiceError) -> StatusCode {
+    match e {
+        VoiceError::InvalidName(_) | VoiceError::InvalidSize { .. } | VoiceError::NonFinite { .. } => StatusCode::BAD_REQUEST,
+        VoiceError::AlreadyExists(_) => StatusCode::CONFLICT,
+        VoiceError::NotFound(_) => StatusCode::NOT_FOUND,
+        VoiceError::BuiltIn(_) => StatusCode::FORBIDDEN,
+        VoiceError::LoadFailed { .. } | VoiceError::Index(_) | VoiceError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// POST /voices?name=... - 上传自定义声音 (请求体: 510×256 个小端 f32 的 .bin 数据)
+async fn upload_voice(Query(query): Query<VoiceUploadQuery>, body: Bytes) -> impl IntoResponse {
+    info!("📤 上传声音: {} ({} 字节)", query.name, body.len());
+    let metadata = VoiceMetadata {
+        language: query.language,
+        accent: query.accent,
+        gender: query.gender,
+        display_name: query.display_name,
+    };
+    let table = match voices::add_custom_voice(voices::VOICES_DIR, &query.name, metadata, &body) {
+        Ok(table) => table,
+        Err(e) => {
+            error!("❌ 上传声音失败: {}", e);
+            return json_error(voice_error_status(&e), e.to_string());
+        }
+    };
+
+    // 引擎已加载时直接注册; 未加载时初始化会从 index.json 读到
+    if let Some(engine) = TTS_ENGINE.get() {
+        engine.lock().unwrap().insert_voice(&query.name, table);
+    }
+
+    let index = voices::load_index(voices::VOICES_DIR).unwrap_or_default();
+    let info = index.get(&query.name).map(|entry| voices::VoiceInfo::from_entry(&query.name, entry));
+    info!("✅ 声音已添加: {}", query.name);
+    (
+        StatusCode::CREATED,
+        [(header::CONTENT_TYPE, "application/json")],
+        serde_json::to_string(&info).unwrap(),
+    )
+}
+
+/// GET /voices/axes - 可用于设计声音的 style 轴
+async fn voice_axes() -> impl IntoResponse {
+    let store = tts_engine().lock().unwrap().voices();
+    let space = store.style_space();
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        serde_json::json!({ "axes": space.axes(), "max_offset": style_space::MAX_OFFSET }).to_string(),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DesignRequest {
+    /// 新声音名
+    name: String,
+    /// 基础声音 (可用别名)
+    base: String,
+    /// 轴 → 偏移 (标准差): {"pc1": 1.5, "gender": -1.0}
+    #[serde(default)]
+    offsets: BTreeMap<String, f32>,
+    language: Option<String>,
+    accent: Option<String>,
+    gender: Option<Gender>,
+    display_name: Option<String>,
+}
+
+/// POST /voices/design - 由 base 声音沿 style 轴偏移得到新声音, 保存到 data/voices
+async fn design_voice(Json(request): Json<DesignRequest>) -> impl IntoResponse {
+    info!("🎛️ 设计声音: {} (base: {}, 偏移: {:?})", request.name, request.base, request.offsets);
+    if let Err(e) = voices::validate_name(&request.name) {
+        return json_error(voice_error_status(&e), e.to_string());
+    }
+    let base = match resolve_voice(Some(&request.base), None) {
+        Ok(base) => base,
+        Err(e) => return unknown_voice_error(&e),
+    };
+
+    let mut engine = tts_engine().lock().unwrap();
+    let store = engine.voices();
+    let designed = store
+        .get(&base)
+        .map_err(DesignError::from)
+        .and_then(|table| store.style_space().design(&table, &request.offsets));
+    let designed = match designed {
+        Ok(table) => table,
+        Err(e) => {
+            error!("❌ 设计声音失败: {}", e);
+            let status = match &e {
+                DesignError::Voice(e) => voice_error_status(e),
+                DesignError::UnknownAxis { .. } | DesignError::OutOfRange { .. } => StatusCode::BAD_REQUEST,
+            };
+            return json_error(status, e.to_string());
+        }
+    };
+
+    // 元数据缺省沿用 base 声音
+    let index = voices::load_index(voices::VOICES_DIR).unwrap_or_default();
+    let base_info = index.get(&base).map(|entry| voices::VoiceInfo::from_entry(&base, entry));
+    let metadata = VoiceMetadata {
+        language: request.language.or_else(|| base_info.as_ref().map(|info| info.language.clone())),
+        accent: request.accent.or_else(|| base_info.as_ref().and_then(|info| info.accent.clone())),
+        gender: request.gender.or_else(|| base_info.as_ref().and_then(|info| info.gender)),
+        display_name: request.display_name,
+    };
+    let table = match voices::add_custom_voice(voices::VOICES_DIR, &request.name, metadata, &designed.to_le_bytes()) {
+        Ok(table) => table,
+        Err(e) => {
+            error!("❌ 保存声音失败: {}", e);
+            return json_error(voice_error_status(&e), e.to_string());
+        }
+    };
+    engine.insert_voice(&request.name, table);
+    drop(engine);
+
+    let index = voices::load_index(voices::VOICES_DIR).unwrap_or_default();
+    let info = index.get(&request.name).map(|entry| voices::VoiceInfo::from_entry(&request.name, entry));
+    info!("✅ 声音已设计: {}", request.name);
+    (
+        StatusCode::CREATED,
+        [(header::CONTENT_TYPE, "application/json")],
+        serde_json::to_string(&info).unwrap(),
+    )
+}
+
+/// DELETE /voices/:name - 删除用户上传的声音
+async fn delete_voice(Path(name): Path<String>) -> impl IntoResponse {
+    info!("🗑️ 删除声音: {}", name);
+    if let Err(e) = voices::remove_custom_voice(voices::VOICES_DIR, &name) {
+        error!("❌ 删除声音失败: {}", e);
+        return json_error(voice_error_status(&e), e.to_string());
+    }
+    if let Some(engine) = TTS_ENGINE.get() {
+        engine.lock().unwrap().remove_voice(&name);
+    }
+    previews().remove(&name);
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        serde_json::json!({ "deleted": name }).to_string(),
+    )
+}
+
+/// 试听片段: 指纹一致时直接读取, 否则合成并保存 (阻塞调用)
+fn render_preview(name: &str) -> anyhow::Result<Vec<u8>> {
+    let text = voices::preview_text(name);
+    let voice_path = voice_preview::voice_path(std::path::Path::new(voices::VOICES_DIR), name)?;
+    let fingerprint = voice_preview::fingerprint(std::path::Path::new(MODEL_PATH), &voice_path, text)?;
+    if let Some(wav) = previews().get(name, &fingerprint) {
+        return Ok(wav);
+    }
+
+    info!("🎧 生成试听: {}", name);
+    let mut engine = tts_engine().lock().unwrap();
+    let input = SynthesisInput::Text(text.to_string());
+    let synthesis = engine.synthesize_input(&input, Some(name), &TextOptions::default())?;
+    let wav = encode_wav(&synthesis.audio, engine.sample_rate())?;
+    drop(engine);
+
+    previews().save(name, &fingerprint, &wav)?;
+    Ok(wav)
+}
+
+/// 模型文件标识 (进程内只读取一次)
+fn model_fingerprint() -> anyhow::Result<&'static str> {
+    if let Some(fingerprint) = MODEL_FINGERPRINT.get() {
+        return Ok(fingerprint);
+    }
+    let fingerprint = voice_preview::model_fingerprint(std::path::Path::new(MODEL_PATH))?;
+    Ok(MODEL_FINGERPRINT.get_or_init(|| fingerprint))
+}
+
+/// 音频缓存键中的声音部分: 按名字排序的 `名字#指纹` 列表 (声音指纹在加载/注册时计算)
+fn cache_voice_key(names: &BTreeSet<String>) -> anyhow::Result<String> {
+    let model = model_fingerprint()?;
+    let store = tts_engine().lock().unwrap().voices();
+    let keys = names
+        .iter()
+        .map(|name| Ok(voice_preview::cache_key(model, name, &store.fingerprint(name)?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(keys.join(","))
+}
+
+/// 启动时在后台为所有声音预生成试听片段 (--pregenerate-previews)
+fn pregenerate_previews() {
+    let started = Instant::now();
+    let names: Vec<String> = voices::load_index(voices::VOICES_DIR).map(|index| index.into_keys().collect()).unwrap_or_default();
+    info!("🎧 预生成 {} 个声音的试听...", names.len());
+    let failed = names
+        .iter()
+        .filter(|name| match render_preview(name) {
+            Ok(_) => false,
+            Err(e) => {
+                warn!("⚠️ 试听生成失败 {}: {:#}", name, e);
+                true
+            }
+        })
+        .count();
+    info!("✅ 试听预生成完成: {} 个成功, {} 个失败 ({:.0?})", names.len() - failed, failed, started.elapsed());
+}
+
+/// GET /voices/:name/preview - 声音试听: 每个声音一段固定文本, 保存在不过期的试听目录
+async fn voice_preview(Path(name): Path<String>) -> Response {
+    let name = match resolve_voice(Some(&name), None) {
+        Ok(name) => name,
+        Err(e) => return unknown_voice_error(&e).into_response(),
+    };
+
+    match tokio::task::spawn_blocking(move || render_preview(&name)).await {
+        Ok(Ok(wav)) => (StatusCode::OK, [(header::CONTENT_TYPE, "audio/wav")], wav).into_response(),
+        Ok(Err(e)) => {
+            error!("❌ 试听生成失败: {:#}", e);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("试听生成失败: {:#}", e)).into_response()
+        }
+        Err(e) => {
+            error!("❌ 试听生成失败: {}", e);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("试听生成失败: {}", e)).into_response()
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -85,29 +606,113 @@ struct SynthesizeResponse {
     file_id: String,
     url: String,
     cached: bool,
+    /// 本次合成的诊断信息 (缓存命中时没有)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diagnostics: Option<SynthesisDiagnostics>,
 }
 
 /// POST /synthesize - TTS synthesis (使用文件缓存)
 async fn synthesize(
     Json(payload): Json<SynthesizeRequest>
 ) -> impl IntoResponse {
-    let text_preview: String = payload.text.chars().take(50).collect();
-    info!("🎵 TTS 合成请求: \"{}\"", text_preview);
+    let input = match payload.input() {
+        Ok(input) => input,
+        Err(message) => return json_error(StatusCode::BAD_REQUEST, message),
+    };
+    info!("🎵 TTS 合成请求 ({}): \"{}\"", input.cache_namespace(), input.preview());
+
+    // 原始音素/token 输入: 在加载引擎前按模型的词汇表校验
+    let vocab = match model_vocab() {
+        Ok(vocab) => vocab,
+        Err(e) => {
+            error!("❌ 无法加载词汇表: {:#}", e);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("无法加载词汇表: {:#}", e));
+        }
+    };
+    if let Err(e) = input.validate(&vocab) {
+        error!("❌ 原始输入无效: {}", e);
+        return json_error(StatusCode::BAD_REQUEST, e.to_string());
+    }
 
-    // 获取或初始化缓存
-    let cache = AUDIO_CACHE.get_or_init(|| {
-        info!("🔧 初始化音频缓存...");
-        AudioCache::new("cache/audio", 3600).expect("无法初始化缓存")
-    });
+    // 别名 → 声音名; 未指定时按文本语言选择默认声音 (解析后的名字进入缓存键)
+    let text = match &input {
+        SynthesisInput::Text(text) | SynthesisInput::Ssml(text) => Some(text.as_str()),
+        SynthesisInput::Phonemes(_) | SynthesisInput::Tokens(_) => None,
+    };
+    let voice = match resolve_voice(payload.voice.as_deref(), text) {
+        Ok(voice) => voice,
+        Err(e) => return unknown_voice_error(&e),
+    };
+
+    let mut used_voices = BTreeSet::from([voice.clone()]);
+
+    // SSML: 在加载引擎前检查语法, 错误指向出错位置
+    if let SynthesisInput::Ssml(ssml) = &input {
+        let resolve = |name: &str| voice_alias::resolve_installed(name).ok();
+        let items = match ssml::compile(ssml, &voice, &vocab, &resolve) {
+            Ok(items) => items,
+            Err(e) => {
+                error!("❌ {}", e);
+                return (
+                    StatusCode::BAD_REQUEST,
+                    [(header::CONTENT_TYPE, "application/json")],
+                    serde_json::json!({
+                        "error": e.to_string(),
+                        "line": e.line,
+                        "column": e.column,
+                        "offset": e.offset,
+                    })
+                    .to_string(),
+                );
+            }
+        };
+        // <voice name="..."> 中的声音也要存在
+        for item in &items {
+            if let ssml::SsmlItem::Speech(ssml::SsmlChunk { voice: Some(name), .. }) = item {
+                match resolve_voice(Some(name), None) {
+                    Ok(name) => used_voices.insert(name),
+                    Err(e) => return unknown_voice_error(&e),
+                };
+            }
+        }
+    }
+    // 缓存键带上每个用到的声音的内容指纹: 删除后重新创建的同名声音不会命中旧音频
+    let voice_key = match cache_voice_key(&used_voices) {
+        Ok(key) => key,
+        Err(e) => {
+            error!("❌ 无法计算声音指纹: {:#}", e);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, format!("无法读取声音文件: {:#}", e));
+        }
+    };
+    let options = match text_options(&payload.senses, payload.symbols) {
+        Ok(options) => options,
+        Err(message) => return json_error(StatusCode::BAD_REQUEST, message),
+    };
+    let encode_options = EncodeOptions {
+        format: payload.format,
+        bitrate: payload.bitrate,
+        sample_rate: payload.sample_rate,
+        sample_format: payload.sample_format,
+    };
+    if let Err(message) = encode_options.validate() {
+        return json_error(StatusCode::BAD_REQUEST, message);
+    }
+    let format = encode_options.format;
+    let namespace = input.cache_namespace();
+    // 同形词提示、符号读法和输出格式都会改变结果, 需要进入缓存键
+    let cache_input = format!("{}{}{}", input.cache_input(), options.cache_suffix(), encode_options.cache_suffix());
+
+    let cache = audio_cache();
 
     // 检查缓存 (包含声音参数)
-    if let Some(file_id) = cache.exists(&payload.text, payload.voice.as_deref()) {
+    if let Some(file_id) = cache.exists_in(namespace, &cache_input, Some(&voice_key), format) {
         info!("✅ 缓存命中: {}", file_id);
 
         let response = SynthesizeResponse {
             file_id: file_id.clone(),
-            url: format!("http://localhost:9527/audio/{}.wav", file_id),
+            url: format!("http://localhost:9527/audio/{}.{}", file_id, format.extension()),
             cached: true,
+            diagnostics: None,
         };
 
         return (
@@ -119,41 +724,26 @@ async fn synthesize(
 
     info!("❌ 缓存未命中，开始合成...");
 
-    // 获取或初始化 TTS 引擎
-    let engine_mutex = TTS_ENGINE.get_or_init(|| {
-        info!("🔧 首次初始化 TTS 引擎...");
-
-        match TTSEngine::new("checkpoints/kokoro-v1.0.onnx") {
-            Ok(engine) => {
-                info!("✅ TTS 引擎初始化成功");
-                std::sync::Mutex::new(engine)
-            },
-            Err(e) => {
-                error!("❌ TTS 引擎初始化失败: {}", e);
-                panic!("无法加载 TTS 模型: {}", e);
-            }
-        }
-    });
-
-    let mut engine = engine_mutex.lock().unwrap();
+    let mut engine = tts_engine().lock().unwrap();
 
     // 合成音频 (传递 voice 参数)
-    match engine.synthesize(&payload.text, payload.voice.as_deref()) {
-        Ok(audio_samples) => {
-            info!("✅ 音频合成成功 ({} 样本)", audio_samples.len());
+    match engine.synthesize_input(&input, Some(&voice), &options) {
+        Ok(Synthesis { audio: audio_samples, diagnostics }) => {
+            info!("✅ 音频合成成功 ({} 样本, {} 段)", audio_samples.len(), diagnostics.chunks);
 
-            // 编码为 WAV
-            match encode_wav(&audio_samples, engine.sample_rate()) {
-                Ok(wav_bytes) => {
-                    info!("✅ WAV 编码完成 ({} 字节)", wav_bytes.len());
+            // 编码为请求的格式
+            match audio_format::encode(&audio_samples, engine.sample_rate(), &encode_options) {
+                Ok(audio_bytes) => {
+                    info!("✅ {} 编码完成 ({} 字节)", format.extension().to_uppercase(), audio_bytes.len());
 
                     // 保存到缓存 (包含声音参数)
-                    match cache.save(&payload.text, payload.voice.as_deref(), &wav_bytes) {
+                    match cache.save_in(namespace, &cache_input, Some(&voice_key), format, &audio_bytes) {
                         Ok(file_id) => {
                             let response = SynthesizeResponse {
                                 file_id: file_id.clone(),
-                                url: format!("http://localhost:9527/audio/{}.wav", file_id),
+                                url: format!("http://localhost:9527/audio/{}.{}", file_id, format.extension()),
                                 cached: false,
+                                diagnostics: Some(diagnostics),
                             };
 
                             (
@@ -173,12 +763,13 @@ async fn synthesize(
                     }
                 },
                 Err(e) => {
-                    error!("❌ WAV 编码失败: {}", e);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        [(header::CONTENT_TYPE, "application/json")],
-                        format!(r#"{{"error": "WAV 编码失败: {}"}}"#, e)
-                    )
+                    error!("❌ {} 编码失败: {:#}", format, e);
+                    // 编码器未安装 (如 lame / libopus) 时返回 501
+                    let status = match audio_format::is_unavailable(&e) {
+                        true => StatusCode::NOT_IMPLEMENTED,
+                        false => StatusCode::INTERNAL_SERVER_ERROR,
+                    };
+                    json_error(status, format!("{} 编码失败: {:#}", format, e))
                 }
             }
         },
@@ -193,56 +784,59 @@ async fn synthesize(
     }
 }
 
-/// GET /audio/:filename - 静态音频文件服务
+/// GET /audio/:filename - 缓存音频文件服务
+///
+/// 缓存以 FLAC 存储时, .wav 请求从 .flac 转换而来
 async fn serve_audio(Path(filename): Path<String>) -> impl IntoResponse {
-    use tokio::fs::File;
-    use tokio::io::AsyncReadExt;
-
     info!("📁 请求音频文件: {}", filename);
 
-    // 安全检查: 只允许 .wav 文件
-    if !filename.ends_with(".wav") {
-        error!("❌ 非法文件扩展名: {}", filename);
+    // 安全检查: 文件 ID 只能是十六进制, 扩展名只能是缓存的音频格式
+    let parsed = filename
+        .rsplit_once('.')
+        .filter(|(file_id, _)| !file_id.is_empty() && file_id.chars().all(|c| c.is_ascii_hexdigit()))
+        .and_then(|(file_id, extension)| Some((file_id.to_string(), AudioFormat::from_extension(extension)?)));
+    let Some((file_id, format)) = parsed else {
+        error!("❌ 非法文件名: {}", filename);
         return (
             StatusCode::BAD_REQUEST,
             [(header::CONTENT_TYPE, "text/plain")],
             Vec::new()
         );
-    }
+    };
 
-    // 构建文件路径
-    let file_path = format!("cache/audio/{}", filename);
-
-    // 读取文件
-    match File::open(&file_path).await {
-        Ok(mut file) => {
-            let mut contents = Vec::new();
-            match file.read_to_end(&mut contents).await {
-                Ok(_) => {
-                    info!("✅ 读取音频文件: {} ({} 字节)", filename, contents.len());
-                    (
-                        StatusCode::OK,
-                        [(header::CONTENT_TYPE, "audio/wav")],
-                        contents
-                    )
-                },
-                Err(e) => {
-                    error!("❌ 读取文件失败: {}", e);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        [(header::CONTENT_TYPE, "text/plain")],
-                        Vec::new()
-                    )
-                }
-            }
+    // 读取文件 (可能需要解码 FLAC, 放到阻塞线程)
+    match tokio::task::spawn_blocking(move || audio_cache().read(&file_id, format)).await {
+        Ok(Ok(Some(contents))) => {
+            info!("✅ 读取音频文件: {} ({} 字节)", filename, contents.len());
+            (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, format.content_type())],
+                contents
+            )
         },
-        Err(_) => {
+        Ok(Ok(None)) => {
             error!("❌ 文件不存在: {}", filename);
             (
                 StatusCode::NOT_FOUND,
                 [(header::CONTENT_TYPE, "text/plain")],
                 Vec::new()
             )
+        },
+        Ok(Err(e)) => {
+            error!("❌ 读取文件失败: {:#}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(header::CONTENT_TYPE, "text/plain")],
+                Vec::new()
+            )
+        },
+        Err(e) => {
+            error!("❌ 读取任务失败: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(header::CONTENT_TYPE, "text/plain")],
+                Vec::new()
+            )
         }
     }
 }
@@ -252,7 +846,7 @@ async fn ensure_models_downloaded() -> anyhow::Result<()> {
     use tokio::process::Command;
     use std::path::Path;
 
-    let model_path = Path::new("checkpoints/kokoro-v1.0.onnx");
+    let model_path = Path::new(MODEL_PATH);
 
     if !model_path.exists() {
         info!("📥 模型文件不存在，开始自动下载...");
@@ -285,16 +879,40 @@ async fn main() -> anyhow::Result<()> {
         .with_max_level(Level::INFO)
         .init();
 
+    // 子命令: tts-server voices import <文件>...
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("voices") {
+        return voice_import::run_cli(&args[1..]);
+    }
+
     info!("🚀 启动 TTS 服务器 (Candle Framework)...");
 
     // 确保模型已下载
     ensure_models_downloaded().await?;
 
+    // 无损格式的缓存以 FLAC 存储, 读取时再转换 (约节省一半磁盘)
+    if args.iter().any(|arg| arg == "--cache-flac") {
+        info!("🗜️ 缓存以 FLAC 存储");
+        let _ = AUDIO_CACHE.set(AudioCache::new(CACHE_DIR, CACHE_TTL)?.with_storage(CacheStorage::Flac));
+    }
+
+    // 后台预生成声音试听 (不阻塞启动)
+    if args.iter().any(|arg| arg == "--pregenerate-previews") {
+        tokio::task::spawn_blocking(pregenerate_previews);
+    }
+
     // 创建路由
     let app = Router::new()
         .route("/", get(get_server_info))
         .route("/health", get(health_check))
         .route("/synthesize", post(synthesize))
+        .route("/phonemize", post(phonemize))
+        .route("/voices", get(list_voices).post(upload_voice))
+        .route("/voices/axes", get(voice_axes))
+        .route("/voices/design", post(design_voice))
+        .route("/voices/:name", delete(delete_voice))
+        .route("/voices/:name/preview", get(voice_preview))
+        .route("/voices/:name/similar", get(similar_voices))
         .route("/audio/:filename", get(serve_audio))
         .layer(
             CorsLayer::new()
@@ -0,0 +1,84 @@
+//! MP3 Audio Encoder
+//!
+//! 通过外部 `lame` 编码: 16-bit WAV 从 stdin 输入, MP3 从 stdout 读出 (恒定码率)。
+
+use anyhow::Result;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::subprocess::{self, Limits};
+use crate::wav_encoder::encode_wav;
+
+/// 默认码率 (kbps), 语音单声道足够清晰
+pub const DEFAULT_BITRATE: u32 = 64;
+
+/// MPEG-1 (32 / 44.1 / 48 kHz) 的码率 (kbps)
+const MPEG1_BITRATES: [u32; 14] = [32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+
+/// MPEG-2 / 2.5 (8–24 kHz) 的码率 (kbps), 最高 160
+const MPEG2_BITRATES: [u32; 14] = [8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// lame 运行限制: 长文本的音频编码也应在几秒内完成
+const LAME_LIMITS: Limits = Limits {
+    timeout: Duration::from_secs(60),
+    max_output: 64 * 1024 * 1024,
+};
+
+/// 采样率可用的码率: 32 kHz 以下是 MPEG-2/2.5 帧
+pub fn bitrates_for(sample_rate: u32) -> &'static [u32] {
+    if sample_rate >= 32000 { &MPEG1_BITRATES } else { &MPEG2_BITRATES }
+}
+
+/// 校验码率 (lame 会把不合法的组合悄悄改成别的码率)
+pub fn validate_bitrate(bitrate: u32, sample_rate: u32) -> Result<(), String> {
+    let allowed = bitrates_for(sample_rate);
+    match allowed.contains(&bitrate) {
+        true => Ok(()),
+        false => Err(format!("MP3 码率无效: {} kbps 不适用于 {} Hz (可选: {:?})", bitrate, sample_rate, allowed)),
+    }
+}
+
+fn lame_command(bitrate: u32) -> Command {
+    let mut command = Command::new("lame");
+    command.args(["--quiet", "--cbr", "-b", &bitrate.to_string(), "-", "-"]);
+    command
+}
+
+/// 编码 MP3 音频到内存
+///
+/// lame 未安装时返回 `SubprocessError::NotInstalled` (可用 `downcast_ref` 判断)
+pub fn encode_mp3(audio: &[f32], sample_rate: u32, bitrate: u32) -> Result<Vec<u8>> {
+    let wav = encode_wav(audio, sample_rate)?;
+    Ok(subprocess::run(lame_command(bitrate), &wav, LAME_LIMITS)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subprocess::SubprocessError;
+
+    #[test]
+    fn test_validate_bitrate() {
+        for rate in [8000, 16000, 22050, 24000, 44100, 48000] {
+            assert!(validate_bitrate(DEFAULT_BITRATE, rate).is_ok(), "{} Hz", rate);
+        }
+        assert!(validate_bitrate(65, 24000).unwrap_err().contains("65"));
+        // MPEG-2/2.5 最高 160 kbps, MPEG-1 没有 8–24 和 144
+        assert!(validate_bitrate(160, 24000).is_ok());
+        assert!(validate_bitrate(192, 24000).is_err());
+        assert!(validate_bitrate(320, 22050).is_err());
+        assert!(validate_bitrate(320, 48000).is_ok());
+        assert!(validate_bitrate(144, 44100).is_err());
+        assert!(validate_bitrate(8, 48000).is_err());
+    }
+
+    #[test]
+    fn test_encode_or_not_installed() {
+        let audio: Vec<f32> = (0..2400).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        match encode_mp3(&audio, 24000, 64) {
+            // MPEG 帧同步字: 11 个 1 (可能前面有 ID3 标签)
+            Ok(mp3) => assert!(mp3.starts_with(b"ID3") || (mp3[0] == 0xFF && mp3[1] & 0xE0 == 0xE0)),
+            Err(e) => assert!(matches!(e.downcast_ref(), Some(SubprocessError::NotInstalled { .. })), "{}", e),
+        }
+    }
+}
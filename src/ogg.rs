@@ -0,0 +1,166 @@
+//! Ogg 容器 (RFC 3533) 写入
+//!
+//! 只支持单个逻辑流, 包不跨页: 每页装若干完整的包, 页的 granule position
+//! 是该页最后一个包结束时的位置。Opus 包最大 1275 字节, 不需要跨页续包。
+
+/// 页头标志
+const FLAG_BOS: u8 = 0x02;
+const FLAG_EOS: u8 = 0x04;
+
+/// 每页最多 255 个 lacing 段
+const MAX_SEGMENTS: usize = 255;
+
+/// 超过这个数据量就换页 (libogg 的默认值)
+const PAGE_FILL: usize = 4096;
+
+/// Ogg 使用的 CRC-32: 多项式 0x04c11db7, 初值 0, 不反转, 不异或
+fn crc_table() -> &'static [u32; 256] {
+    static TABLE: std::sync::OnceLock<[u32; 256]> = std::sync::OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0u32; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut r = (i as u32) << 24;
+            for _ in 0..8 {
+                r = if r & 0x8000_0000 != 0 { (r << 1) ^ 0x04c1_1db7 } else { r << 1 };
+            }
+            *entry = r;
+        }
+        table
+    })
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let table = crc_table();
+    data.iter().fold(0u32, |crc, &b| (crc << 8) ^ table[((crc >> 24) as u8 ^ b) as usize])
+}
+
+/// 把包写成 Ogg 页
+pub struct OggWriter {
+    serial: u32,
+    sequence: u32,
+    out: Vec<u8>,
+    /// 当前页的 lacing 段和数据
+    segments: Vec<u8>,
+    data: Vec<u8>,
+    granule: i64,
+}
+
+impl OggWriter {
+    pub fn new(serial: u32) -> Self {
+        Self { serial, sequence: 0, out: Vec::new(), segments: Vec::new(), data: Vec::new(), granule: 0 }
+    }
+
+    /// 添加一个完整的包, `granule` 是包结束时的位置; 当前页放不下时先换页
+    pub fn write_packet(&mut self, packet: &[u8], granule: i64) {
+        let lacing = packet.len() / 255 + 1;
+        if !self.segments.is_empty() && (self.segments.len() + lacing > MAX_SEGMENTS || self.data.len() >= PAGE_FILL) {
+            self.write_page(0);
+        }
+        self.segments.extend(std::iter::repeat_n(255u8, packet.len() / 255));
+        self.segments.push((packet.len() % 255) as u8);
+        self.data.extend_from_slice(packet);
+        self.granule = granule;
+    }
+
+    /// 立即结束当前页 (头部包必须单独成页)
+    pub fn flush_page(&mut self) {
+        if !self.segments.is_empty() {
+            self.write_page(0);
+        }
+    }
+
+    /// 写出最后一页 (带 EOS 标志), 返回整个流
+    pub fn finish(mut self) -> Vec<u8> {
+        self.write_page(FLAG_EOS);
+        self.out
+    }
+
+    fn write_page(&mut self, mut flags: u8) {
+        if self.sequence == 0 {
+            flags |= FLAG_BOS;
+        }
+        let start = self.out.len();
+        self.out.extend_from_slice(b"OggS");
+        self.out.push(0); // 版本
+        self.out.push(flags);
+        self.out.extend_from_slice(&self.granule.to_le_bytes());
+        self.out.extend_from_slice(&self.serial.to_le_bytes());
+        self.out.extend_from_slice(&self.sequence.to_le_bytes());
+        self.out.extend_from_slice(&[0; 4]); // CRC 占位
+        self.out.push(self.segments.len() as u8);
+        self.out.append(&mut self.segments);
+        self.out.append(&mut self.data);
+
+        let crc = crc32(&self.out[start..]);
+        self.out[start + 22..start + 26].copy_from_slice(&crc.to_le_bytes());
+        self.sequence += 1;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// 解析后的页: (标志, granule, 序号, 包)
+    pub fn parse_pages(mut stream: &[u8]) -> Vec<(u8, i64, u32, Vec<Vec<u8>>)> {
+        let mut pages = Vec::new();
+        while !stream.is_empty() {
+            assert_eq!(&stream[..4], b"OggS");
+            let flags = stream[5];
+            let granule = i64::from_le_bytes(stream[6..14].try_into().unwrap());
+            let sequence = u32::from_le_bytes(stream[18..22].try_into().unwrap());
+            let crc = u32::from_le_bytes(stream[22..26].try_into().unwrap());
+            let count = stream[26] as usize;
+            let table = &stream[27..27 + count];
+            let length = 27 + count + table.iter().map(|&s| s as usize).sum::<usize>();
+
+            let mut page = stream[..length].to_vec();
+            page[22..26].fill(0);
+            assert_eq!(crc32(&page), crc, "页 {} CRC 错误", sequence);
+
+            let mut packets = Vec::new();
+            let mut packet = Vec::new();
+            let mut offset = 27 + count;
+            for &segment in table {
+                packet.extend_from_slice(&stream[offset..offset + segment as usize]);
+                offset += segment as usize;
+                if segment < 255 {
+                    packets.push(std::mem::take(&mut packet));
+                }
+            }
+            pages.push((flags, granule, sequence, packets));
+            stream = &stream[length..];
+        }
+        pages
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0x89A1_897F);
+    }
+
+    #[test]
+    fn test_pages() {
+        let mut writer = OggWriter::new(7);
+        writer.write_packet(b"head", 0);
+        writer.flush_page();
+        let big = vec![0xAB; 510];
+        for i in 1..=100 {
+            writer.write_packet(&big[..i * 5], i as i64 * 960);
+        }
+        let stream = writer.finish();
+
+        let pages = parse_pages(&stream);
+        assert_eq!(pages[0].0, FLAG_BOS);
+        assert_eq!(pages[0].3, vec![b"head".to_vec()]);
+        assert_eq!(pages.last().unwrap().0, FLAG_EOS);
+        assert_eq!(pages.last().unwrap().1, 100 * 960);
+        assert!(pages.iter().enumerate().all(|(i, page)| page.2 == i as u32));
+
+        // 包完整且顺序不变, 510 字节的包占 3 个段 (255 + 255 + 0)
+        let packets: Vec<Vec<u8>> = pages[1..].iter().flat_map(|page| page.3.clone()).collect();
+        assert_eq!(packets.len(), 100);
+        assert!(packets.iter().enumerate().all(|(i, p)| p.len() == (i + 1) * 5));
+        assert!(pages.len() > 3);
+    }
+}
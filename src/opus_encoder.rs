@@ -0,0 +1,321 @@
+//! Ogg Opus Audio Encoder
+//!
+//! 运行时通过 dlopen 加载系统的 libopus (不是编译依赖, 没装时 ogg 格式不可用),
+//! 编码单声道 20ms 帧, 再用 `ogg` 模块封装成 Ogg Opus (RFC 7845)。
+//!
+//! Opus 只接受 8/12/16/24/48 kHz 输入, 其他采样率先重采样到不低于原采样率的支持值。
+//! granule position 固定以 48 kHz 计, 包含 pre-skip; 最后一页的 granule 按原始长度截断
+//! 编码器补的尾部静音 (end trimming)。
+
+use anyhow::Result;
+use std::os::raw::{c_int, c_void};
+use std::sync::OnceLock;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::ogg::OggWriter;
+use crate::resampler;
+
+/// 默认码率 (kbps), 24 kHz 单声道语音
+pub const DEFAULT_BITRATE: u32 = 24;
+
+/// 允许的码率范围 (kbps)
+pub const MIN_BITRATE: u32 = 6;
+pub const MAX_BITRATE: u32 = 256;
+
+/// Opus 支持的输入采样率
+pub const SAMPLE_RATES: [u32; 5] = [8000, 12000, 16000, 24000, 48000];
+
+/// Ogg Opus 的 granule position 采样率
+const GRANULE_RATE: u32 = 48000;
+
+/// 单个 Opus 包的最大长度
+const MAX_PACKET: usize = 1275;
+
+/// 逻辑流序列号 (每个文件只有一个流)
+const STREAM_SERIAL: u32 = 0x4B6F_6B6F;
+
+// opus_defines.h
+const OPUS_OK: c_int = 0;
+const OPUS_APPLICATION_VOIP: c_int = 2048;
+const OPUS_SET_BITRATE_REQUEST: c_int = 4002;
+const OPUS_SET_SIGNAL_REQUEST: c_int = 4024;
+const OPUS_GET_LOOKAHEAD_REQUEST: c_int = 4027;
+const OPUS_SIGNAL_VOICE: c_int = 3001;
+
+#[derive(Debug, Error)]
+pub enum OpusError {
+    #[error("libopus 未安装")]
+    NotInstalled,
+    #[error("创建 Opus 编码器失败 (错误码 {0})")]
+    Create(i32),
+    #[error("Opus 编码失败 (错误码 {0})")]
+    Encode(i32),
+}
+
+type CreateFn = unsafe extern "C" fn(i32, c_int, c_int, *mut c_int) -> *mut c_void;
+type EncodeFloatFn = unsafe extern "C" fn(*mut c_void, *const f32, c_int, *mut u8, i32) -> i32;
+type CtlFn = unsafe extern "C" fn(*mut c_void, c_int, ...) -> c_int;
+type DestroyFn = unsafe extern "C" fn(*mut c_void);
+
+/// libopus 的函数表 (库加载后不再卸载)
+struct LibOpus {
+    create: CreateFn,
+    encode_float: EncodeFloatFn,
+    ctl: CtlFn,
+    destroy: DestroyFn,
+}
+
+#[cfg(unix)]
+const LIBRARY_NAMES: [&str; 5] = [
+    "libopus.so.0",
+    "libopus.so",
+    "libopus.0.dylib",
+    "/opt/homebrew/lib/libopus.0.dylib",
+    "/usr/local/lib/libopus.0.dylib",
+];
+
+#[cfg(unix)]
+fn load_libopus() -> Option<LibOpus> {
+    use std::ffi::CString;
+
+    let handle = LIBRARY_NAMES.iter().find_map(|name| {
+        let name = CString::new(*name).ok()?;
+        // SAFETY: name 是以 0 结尾的有效字符串
+        let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        (!handle.is_null()).then_some(handle)
+    })?;
+
+    let symbol = |name: &str| {
+        let name = CString::new(name).ok()?;
+        // SAFETY: handle 来自成功的 dlopen
+        let ptr = unsafe { libc::dlsym(handle, name.as_ptr()) };
+        (!ptr.is_null()).then_some(ptr)
+    };
+    let (create, encode_float, ctl, destroy) = match (
+        symbol("opus_encoder_create"),
+        symbol("opus_encode_float"),
+        symbol("opus_encoder_ctl"),
+        symbol("opus_encoder_destroy"),
+    ) {
+        (Some(a), Some(b), Some(c), Some(d)) => (a, b, c, d),
+        _ => {
+            warn!("⚠️ libopus 缺少编码器符号, ogg 格式不可用");
+            return None;
+        }
+    };
+
+    // SAFETY: 符号签名与 opus.h 中的声明一致
+    unsafe {
+        Some(LibOpus {
+            create: std::mem::transmute::<*mut c_void, CreateFn>(create),
+            encode_float: std::mem::transmute::<*mut c_void, EncodeFloatFn>(encode_float),
+            ctl: std::mem::transmute::<*mut c_void, CtlFn>(ctl),
+            destroy: std::mem::transmute::<*mut c_void, DestroyFn>(destroy),
+        })
+    }
+}
+
+#[cfg(not(unix))]
+fn load_libopus() -> Option<LibOpus> {
+    None
+}
+
+fn libopus() -> Option<&'static LibOpus> {
+    static LIB: OnceLock<Option<LibOpus>> = OnceLock::new();
+    LIB.get_or_init(|| {
+        let lib = load_libopus();
+        if lib.is_some() {
+            info!("✅ 已加载 libopus");
+        }
+        lib
+    })
+    .as_ref()
+}
+
+/// 单声道编码器, Drop 时销毁
+struct Encoder {
+    lib: &'static LibOpus,
+    state: *mut c_void,
+}
+
+impl Encoder {
+    fn new(lib: &'static LibOpus, sample_rate: u32, bitrate: u32) -> Result<Self, OpusError> {
+        let mut error: c_int = 0;
+        // SAFETY: 参数均为合法取值, error 指向有效的 c_int
+        let state = unsafe { (lib.create)(sample_rate as i32, 1, OPUS_APPLICATION_VOIP, &mut error) };
+        if state.is_null() || error != OPUS_OK {
+            return Err(OpusError::Create(error));
+        }
+        let encoder = Self { lib, state };
+        // SAFETY: state 有效; SET 请求的参数是 opus_int32
+        unsafe {
+            (lib.ctl)(state, OPUS_SET_BITRATE_REQUEST, (bitrate * 1000) as i32);
+            (lib.ctl)(state, OPUS_SET_SIGNAL_REQUEST, OPUS_SIGNAL_VOICE);
+        }
+        Ok(encoder)
+    }
+
+    /// 编码器延迟 (输入采样率下的样本数)
+    fn lookahead(&self) -> usize {
+        let mut lookahead: i32 = 0;
+        // SAFETY: GET 请求的参数是 opus_int32*
+        let status = unsafe { (self.lib.ctl)(self.state, OPUS_GET_LOOKAHEAD_REQUEST, &mut lookahead as *mut i32) };
+        if status == OPUS_OK { lookahead.max(0) as usize } else { 0 }
+    }
+
+    fn encode(&mut self, frame: &[f32]) -> Result<Vec<u8>, OpusError> {
+        let mut packet = vec![0u8; MAX_PACKET];
+        // SAFETY: frame 是完整的一帧, packet 有 MAX_PACKET 字节
+        let len = unsafe {
+            (self.lib.encode_float)(self.state, frame.as_ptr(), frame.len() as c_int, packet.as_mut_ptr(), MAX_PACKET as i32)
+        };
+        if len < 0 {
+            return Err(OpusError::Encode(len));
+        }
+        packet.truncate(len as usize);
+        Ok(packet)
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        // SAFETY: state 来自 opus_encoder_create, 只销毁一次
+        unsafe { (self.lib.destroy)(self.state) }
+    }
+}
+
+/// 校验码率
+pub fn validate_bitrate(bitrate: u32) -> Result<(), String> {
+    match (MIN_BITRATE..=MAX_BITRATE).contains(&bitrate) {
+        true => Ok(()),
+        false => Err(format!("Opus 码率无效: {} kbps (范围 {}-{})", bitrate, MIN_BITRATE, MAX_BITRATE)),
+    }
+}
+
+/// 编码器使用的采样率: 不低于输入的最小支持值
+pub fn encoder_rate(sample_rate: u32) -> u32 {
+    SAMPLE_RATES.into_iter().find(|&rate| rate >= sample_rate).unwrap_or(GRANULE_RATE)
+}
+
+/// OpusHead 识别头 (RFC 7845 5.1), 单声道, 映射族 0
+fn opus_head(pre_skip: u16, input_rate: u32) -> Vec<u8> {
+    let mut head = b"OpusHead".to_vec();
+    head.push(1); // 版本
+    head.push(1); // 声道数
+    head.extend_from_slice(&pre_skip.to_le_bytes());
+    head.extend_from_slice(&input_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // 输出增益
+    head.push(0); // 映射族
+    head
+}
+
+/// OpusTags 注释头 (RFC 7845 5.2), 没有用户注释
+fn opus_tags() -> Vec<u8> {
+    let vendor = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor.as_bytes());
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    tags
+}
+
+/// 把 Opus 包封装成 Ogg 流
+///
+/// `frame_size` 是每包的 48 kHz 样本数, `length` 是原始音频的 48 kHz 样本数
+fn mux(packets: &[Vec<u8>], frame_size: u64, pre_skip: u16, input_rate: u32, length: u64) -> Vec<u8> {
+    let mut writer = OggWriter::new(STREAM_SERIAL);
+    writer.write_packet(&opus_head(pre_skip, input_rate), 0);
+    writer.flush_page();
+    writer.write_packet(&opus_tags(), 0);
+    writer.flush_page();
+
+    let end = pre_skip as u64 + length;
+    for (i, packet) in packets.iter().enumerate() {
+        let granule = ((i as u64 + 1) * frame_size).min(end);
+        writer.write_packet(packet, granule as i64);
+    }
+    writer.finish()
+}
+
+/// 编码 Ogg Opus 音频到内存
+///
+/// libopus 未安装时返回 `OpusError::NotInstalled` (可用 `downcast_ref` 判断)
+pub fn encode_opus(audio: &[f32], sample_rate: u32, bitrate: u32) -> Result<Vec<u8>> {
+    let lib = libopus().ok_or(OpusError::NotInstalled)?;
+
+    let rate = encoder_rate(sample_rate);
+    let resampled;
+    let samples = if rate == sample_rate {
+        audio
+    } else {
+        resampled = resampler::resample(audio, sample_rate, rate);
+        &resampled
+    };
+
+    let mut encoder = Encoder::new(lib, rate, bitrate)?;
+    let lookahead = encoder.lookahead();
+    let scale = (GRANULE_RATE / rate) as u64;
+    let pre_skip = (lookahead as u64 * scale) as u16;
+
+    // 末尾补 lookahead 个静音样本, 让编码器输出全部音频, 再补齐到整帧
+    let frame = (rate / 50) as usize;
+    let mut input = samples.to_vec();
+    let padded = (samples.len() + lookahead).div_ceil(frame).max(1) * frame;
+    input.resize(padded, 0.0);
+
+    let packets = input.chunks(frame).map(|chunk| encoder.encode(chunk)).collect::<Result<Vec<_>, _>>()?;
+    Ok(mux(&packets, frame as u64 * scale, pre_skip, sample_rate, samples.len() as u64 * scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ogg::tests::parse_pages;
+
+    #[test]
+    fn test_encoder_rate() {
+        assert_eq!(encoder_rate(24000), 24000);
+        assert_eq!(encoder_rate(22050), 24000);
+        assert_eq!(encoder_rate(44100), 48000);
+        assert_eq!(encoder_rate(96000), 48000);
+        assert!(validate_bitrate(DEFAULT_BITRATE).is_ok());
+        assert!(validate_bitrate(1000).is_err());
+    }
+
+    #[test]
+    fn test_mux_granule_positions() {
+        // 24 kHz 的 20ms 帧 = 960 个 48 kHz 样本; 原始 1 秒音频, pre-skip 312
+        let packets: Vec<Vec<u8>> = (0..51).map(|i| vec![i as u8; 40]).collect();
+        let stream = mux(&packets, 960, 312, 24000, 48000);
+        let pages = parse_pages(&stream);
+
+        let head = &pages[0].3[0];
+        assert_eq!(&head[..8], b"OpusHead");
+        assert_eq!(u16::from_le_bytes([head[10], head[11]]), 312);
+        assert_eq!(u32::from_le_bytes(head[12..16].try_into().unwrap()), 24000);
+        assert_eq!(pages[0].1, 0);
+        assert!(pages[1].3[0].starts_with(b"OpusTags"));
+        assert_eq!(pages[1].1, 0);
+
+        // 音频页的 granule 单调递增, 最后一页截到 pre-skip + 原始长度
+        let granules: Vec<i64> = pages[2..].iter().map(|page| page.1).collect();
+        assert!(granules.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(*granules.last().unwrap(), 312 + 48000);
+        assert_eq!(pages[2..].iter().map(|page| page.3.len()).sum::<usize>(), 51);
+    }
+
+    #[test]
+    fn test_encode_or_not_installed() {
+        let audio: Vec<f32> = (0..24000).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        match encode_opus(&audio, 24000, DEFAULT_BITRATE) {
+            Ok(ogg) => {
+                let pages = parse_pages(&ogg);
+                assert!(pages[0].3[0].starts_with(b"OpusHead"));
+                // 1 秒 24kbps 约 3KB, 远小于 48KB 的 WAV
+                assert!(ogg.len() < 10_000, "{}", ogg.len());
+            }
+            Err(e) => assert!(matches!(e.downcast_ref(), Some(OpusError::NotInstalled)), "{}", e),
+        }
+    }
+}
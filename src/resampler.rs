@@ -0,0 +1,169 @@
+//! 采样率转换
+//!
+//! 带 Kaiser 窗的 sinc 插值 (windowed-sinc)。转换比按最大公约数化为 up/down,
+//! 输出样本对应的输入位置只有 up 种小数相位, 每种相位的滤波器系数预先算好。
+//! 截止频率取输入/输出中较低的 Nyquist 频率 × ROLLOFF, 降采样时滤波器相应加长。
+
+/// 允许的输出采样率
+pub const OUTPUT_RATES: [u32; 6] = [8000, 16000, 22050, 24000, 44100, 48000];
+
+/// 截止频率占较低 Nyquist 频率的比例 (过渡带留在 Nyquist 以下)
+const ROLLOFF: f64 = 0.94;
+
+/// 截止频率下滤波器单侧的过零点个数
+const ZERO_CROSSINGS: f64 = 24.0;
+
+/// Kaiser 窗参数 (阻带约 -90 dB)
+const KAISER_BETA: f64 = 9.0;
+
+/// 校验输出采样率
+pub fn validate_rate(sample_rate: u32) -> Result<(), String> {
+    match OUTPUT_RATES.contains(&sample_rate) {
+        true => Ok(()),
+        false => Err(format!("采样率无效: {} Hz (可选: {:?})", sample_rate, OUTPUT_RATES)),
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// 第一类零阶修正 Bessel 函数 (级数展开)
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..64 {
+        term *= (half / k as f64) * (half / k as f64);
+        sum += term;
+        if term < sum * 1e-15 {
+            break;
+        }
+    }
+    sum
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// 转换采样率, 输出长度为 `len × to / from` 四舍五入
+pub fn resample(audio: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || audio.is_empty() {
+        return audio.to_vec();
+    }
+    let g = gcd(from as u64, to as u64);
+    let (up, down) = (to as u64 / g, from as u64 / g);
+
+    // 截止频率 (相对输入 Nyquist) 和单侧长度 (输入样本数)
+    let cutoff = (to as f64 / from as f64).min(1.0) * ROLLOFF;
+    let half = (ZERO_CROSSINGS / cutoff).ceil() as i64;
+    let taps = 2 * half as usize;
+
+    // 相位 p: 输出位置在 base + p/up, 第 j 个系数对应输入 base + j - half + 1
+    let mut table = vec![0f32; up as usize * taps];
+    for (p, row) in table.chunks_mut(taps).enumerate() {
+        let frac = p as f64 / up as f64;
+        let coefficients: Vec<f64> = (0..taps)
+            .map(|j| {
+                let d = frac - (j as i64 - half + 1) as f64;
+                let r = d / half as f64;
+                match r.abs() < 1.0 {
+                    true => cutoff * sinc(cutoff * d) * bessel_i0(KAISER_BETA * (1.0 - r * r).sqrt()),
+                    false => 0.0,
+                }
+            })
+            .collect();
+        // 每个相位归一化为单位直流增益
+        let gain: f64 = coefficients.iter().sum();
+        for (c, value) in row.iter_mut().zip(&coefficients) {
+            *c = (value / gain) as f32;
+        }
+    }
+
+    let len = audio.len() as i64;
+    let out_len = (audio.len() as u64 * up + down / 2) / down;
+    (0..out_len)
+        .map(|n| {
+            let pos = n * down;
+            let base = (pos / up) as i64 - half + 1;
+            let row = &table[(pos % up) as usize * taps..][..taps];
+            // 只累加落在输入范围内的系数 (范围外视为静音)
+            let first = (-base).max(0) as usize;
+            let last = (len - base).clamp(0, taps as i64) as usize;
+            (first..last).map(|j| row[j] * audio[(base + j as i64) as usize]).sum()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn sine(frequency: f64, rate: u32, len: usize) -> Vec<f32> {
+        (0..len).map(|i| (2.0 * PI * frequency * i as f64 / rate as f64).sin() as f32 * 0.5).collect()
+    }
+
+    /// 去掉两端各 10% 后, 指定频率分量的幅度 (相关法)
+    fn amplitude(audio: &[f32], frequency: f64, rate: u32) -> f64 {
+        let skip = audio.len() / 10;
+        let (mut re, mut im) = (0.0, 0.0);
+        for (i, &x) in audio.iter().enumerate().skip(skip).take(audio.len() - 2 * skip) {
+            let phase = 2.0 * PI * frequency * i as f64 / rate as f64;
+            re += x as f64 * phase.cos();
+            im += x as f64 * phase.sin();
+        }
+        2.0 * (re * re + im * im).sqrt() / (audio.len() - 2 * skip) as f64
+    }
+
+    #[test]
+    fn test_output_length() {
+        for &to in &OUTPUT_RATES {
+            // 1 秒输入 → 正好 1 秒输出
+            assert_eq!(resample(&vec![0.0; 24000], 24000, to).len(), to as usize);
+            let expected = (1001.0 * to as f64 / 24000.0).round() as usize;
+            assert_eq!(resample(&vec![0.0; 1001], 24000, to).len(), expected, "{} Hz", to);
+        }
+        assert!(resample(&[], 24000, 16000).is_empty());
+    }
+
+    #[test]
+    fn test_passband_is_flat() {
+        let input = sine(1000.0, 24000, 24000);
+        for &to in &OUTPUT_RATES {
+            let output = resample(&input, 24000, to);
+            let gain = amplitude(&output, 1000.0, to) / 0.5;
+            assert!((gain - 1.0).abs() < 0.005, "{} Hz: 增益 {}", to, gain);
+        }
+        // 接近截止频率 (16 kHz 输出的 Nyquist 为 8 kHz)
+        let output = resample(&sine(6500.0, 24000, 24000), 24000, 16000);
+        assert!((amplitude(&output, 6500.0, 16000) / 0.5 - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_stopband_rejects_aliases() {
+        // 10 kHz 超出 16 kHz 输出的 Nyquist, 不应折叠成 6 kHz
+        let output = resample(&sine(10000.0, 24000, 24000), 24000, 16000);
+        assert!(amplitude(&output, 6000.0, 16000) < 0.5e-3);
+        let rms = (output.iter().map(|&x| (x * x) as f64).sum::<f64>() / output.len() as f64).sqrt();
+        assert!(rms < 1e-3, "残留 {}", rms);
+
+        // 升采样不产生镜像: 5 kHz → 48 kHz 时 19 kHz 处没有能量
+        let output = resample(&sine(5000.0, 24000, 24000), 24000, 48000);
+        assert!((amplitude(&output, 5000.0, 48000) / 0.5 - 1.0).abs() < 0.005);
+        assert!(amplitude(&output, 19000.0, 48000) < 0.5e-3);
+    }
+
+    #[test]
+    fn test_validate_rate() {
+        assert!(validate_rate(16000).is_ok());
+        assert!(validate_rate(22050).is_ok());
+        assert!(validate_rate(11025).unwrap_err().contains("11025"));
+    }
+}
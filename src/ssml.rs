@@ -0,0 +1,744 @@
+//! SSML 子集解析
+//!
+//! 支持的元素:
+//! - `<speak>` 根元素
+//! - `<break time="500ms"|"1s" strength="weak|medium|strong|...">`
+//! - `<prosody rate="slow|fast|80%|1.2">`
+//! - `<say-as interpret-as="characters|cardinal|date" format="ymd|mdy|dmy">`
+//! - `<phoneme alphabet="ipa" ph="...">`
+//! - `<sub alias="...">`
+//! - `<voice name="...">`
+//!
+//! 其他元素按透明处理 (保留内容)。解析结果编译为按声音/语速分段的
+//! [`SsmlItem`] 序列, 由 `TTSEngine::synthesize_ssml` 逐段合成。
+
+use thiserror::Error;
+use tracing::warn;
+
+use crate::language::Language;
+use crate::vocab::Vocab;
+
+/// SSML 语法错误, 带出错位置
+#[derive(Debug, Error)]
+#[error("SSML 错误 (第 {line} 行, 第 {column} 列): {message}")]
+pub struct SsmlError {
+    pub message: String,
+    /// 字节偏移
+    pub offset: usize,
+    /// 行号 (从 1 开始)
+    pub line: usize,
+    /// 列号 (从 1 开始, 按字符计)
+    pub column: usize,
+}
+
+impl SsmlError {
+    fn at(src: &str, offset: usize, message: impl Into<String>) -> Self {
+        let before = &src[..offset.min(src.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+        Self {
+            message: message.into(),
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+/// 一段语音中的片段: 普通文本走 G2P, 音素直接使用
+#[derive(Debug, Clone, PartialEq)]
+pub enum SsmlPart {
+    Text(String),
+    Phonemes(String),
+}
+
+/// 同一声音、同一语速下连续合成的一段
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsmlChunk {
+    pub voice: Option<String>,
+    pub rate: f32,
+    pub parts: Vec<SsmlPart>,
+}
+
+/// 编译结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum SsmlItem {
+    Speech(SsmlChunk),
+    /// 停顿 (毫秒)
+    Break(u32),
+}
+
+// ============================================================
+// 解析: 文本 → 元素树
+// ============================================================
+
+#[derive(Debug)]
+enum Node {
+    Element {
+        name: String,
+        attrs: Vec<(String, String, usize)>,
+        children: Vec<Node>,
+        offset: usize,
+    },
+    Text(String),
+}
+
+/// 元素最大嵌套层数 (解析是递归的, 限制深度防止恶意输入耗尽栈)
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// 当前元素嵌套层数
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> SsmlError {
+        SsmlError::at(self.src, offset, message)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.src.len() - trimmed.len();
+    }
+
+    /// 跳过 <?xml ...?> 和 <!-- ... -->
+    fn skip_misc(&mut self) -> Result<bool, SsmlError> {
+        for (open, close) in [("<?", "?>"), ("<!--", "-->")] {
+            if self.rest().starts_with(open) {
+                let start = self.pos;
+                match self.rest().find(close) {
+                    Some(end) => {
+                        self.pos += end + close.len();
+                        return Ok(true);
+                    }
+                    None => return Err(self.error(start, format!("缺少 '{}'", close))),
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    fn parse_name(&mut self) -> Result<String, SsmlError> {
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.error(self.pos, "缺少名称"));
+        }
+        let name = self.rest()[..len].to_string();
+        self.pos += len;
+        Ok(name)
+    }
+
+    fn unescape(&self, raw: &str, offset: usize) -> Result<String, SsmlError> {
+        let mut out = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(amp) = rest.find('&') {
+            out.push_str(&rest[..amp]);
+            let entity_offset = offset + (raw.len() - rest.len()) + amp;
+            let after = &rest[amp + 1..];
+            let semi = after
+                .find(';')
+                .filter(|&i| i <= 10)
+                .ok_or_else(|| self.error(entity_offset, "'&' 必须写成 &amp;"))?;
+            let entity = &after[..semi];
+            let decoded = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error(entity_offset, format!("无效的字符引用 &{};", entity)))?,
+                _ if entity.starts_with('#') => entity[1..]
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error(entity_offset, format!("无效的字符引用 &{};", entity)))?,
+                _ => return Err(self.error(entity_offset, format!("未知实体 &{};", entity))),
+            };
+            out.push(decoded);
+            rest = &after[semi + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn parse_element(&mut self) -> Result<Node, SsmlError> {
+        let offset = self.pos;
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(offset, format!("元素嵌套超过 {} 层", MAX_DEPTH)));
+        }
+        self.pos += 1; // '<'
+        let name = self.parse_name()?;
+        let mut attrs = Vec::new();
+
+        loop {
+            let before_ws = self.pos;
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(Node::Element { name, attrs, children: Vec::new(), offset });
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            if self.rest().is_empty() {
+                return Err(self.error(offset, format!("标签 <{}> 未结束", name)));
+            }
+            if self.pos == before_ws {
+                return Err(self.error(self.pos, "属性之间缺少空白"));
+            }
+
+            let attr_offset = self.pos;
+            let attr_name = self.parse_name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error(self.pos, format!("属性 {} 缺少 '='", attr_name)));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(self.error(self.pos, format!("属性 {} 的值必须加引号", attr_name))),
+            };
+            let value_start = self.pos + 1;
+            let Some(len) = self.src[value_start..].find(quote) else {
+                return Err(self.error(self.pos, format!("属性 {} 的引号未闭合", attr_name)));
+            };
+            let raw = &self.src[value_start..value_start + len];
+            if raw.contains('<') {
+                return Err(self.error(value_start, "属性值中不能包含 '<'"));
+            }
+            let value = self.unescape(raw, value_start)?;
+            self.pos = value_start + len + 1;
+
+            if attrs.iter().any(|(n, _, _): &(String, String, usize)| n == &attr_name) {
+                return Err(self.error(attr_offset, format!("重复的属性 {}", attr_name)));
+            }
+            attrs.push((attr_name, value, attr_offset));
+        }
+
+        self.depth += 1;
+        let children = self.parse_content(&name, offset)?;
+        self.depth -= 1;
+        Ok(Node::Element { name, attrs, children, offset })
+    }
+
+    /// 解析元素内容直到 `</name>`
+    fn parse_content(&mut self, name: &str, open_offset: usize) -> Result<Vec<Node>, SsmlError> {
+        let mut children = Vec::new();
+        loop {
+            if self.rest().is_empty() {
+                return Err(self.error(open_offset, format!("元素 <{}> 未闭合", name)));
+            }
+            if self.skip_misc()? {
+                continue;
+            }
+            if self.rest().starts_with("</") {
+                let close_offset = self.pos;
+                self.pos += 2;
+                let close_name = self.parse_name()?;
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err(self.error(self.pos, format!("结束标签 </{}> 缺少 '>'", close_name)));
+                }
+                self.pos += 1;
+                if close_name != name {
+                    return Err(self.error(
+                        close_offset,
+                        format!("结束标签 </{}> 与开始标签 <{}> 不匹配", close_name, name),
+                    ));
+                }
+                return Ok(children);
+            }
+            if self.rest().starts_with('<') {
+                children.push(self.parse_element()?);
+                continue;
+            }
+
+            let start = self.pos;
+            let len = self.rest().find('<').unwrap_or(self.rest().len());
+            let raw = &self.src[start..start + len];
+            if let Some(gt) = raw.find('>') {
+                return Err(self.error(start + gt, "文本中的 '>' 必须写成 &gt;"));
+            }
+            children.push(Node::Text(self.unescape(raw, start)?));
+            self.pos += len;
+        }
+    }
+
+    fn parse_document(&mut self) -> Result<Node, SsmlError> {
+        loop {
+            self.skip_whitespace();
+            if !self.skip_misc()? {
+                break;
+            }
+        }
+        if !self.rest().starts_with('<') {
+            return Err(self.error(self.pos, "SSML 必须以 <speak> 元素开始"));
+        }
+        let root = self.parse_element()?;
+        if let Node::Element { name, offset, .. } = &root {
+            if name != "speak" {
+                return Err(self.error(*offset, format!("根元素必须是 <speak>, 而不是 <{}>", name)));
+            }
+        }
+        loop {
+            self.skip_whitespace();
+            if !self.skip_misc()? {
+                break;
+            }
+        }
+        if !self.rest().is_empty() {
+            return Err(self.error(self.pos, "</speak> 之后不能有其他内容"));
+        }
+        Ok(root)
+    }
+}
+
+// ============================================================
+// 编译: 元素树 → 分段
+// ============================================================
+
+#[derive(Clone)]
+struct Context {
+    voice: Option<String>,
+    rate: f32,
+}
+
+struct Compiler<'a> {
+    src: &'a str,
+    default_voice: &'a str,
+    vocab: &'a Vocab,
+    resolve_voice: &'a dyn Fn(&str) -> Option<String>,
+    items: Vec<SsmlItem>,
+}
+
+fn attr<'n>(attrs: &'n [(String, String, usize)], name: &str) -> Option<(&'n str, usize)> {
+    attrs
+        .iter()
+        .find(|(n, _, _)| n == name)
+        .map(|(_, v, offset)| (v.as_str(), *offset))
+}
+
+/// 语速: x-slow / slow / medium / fast / x-fast / 百分比 / 倍数
+fn parse_rate(value: &str) -> Option<f32> {
+    let rate = match value.trim() {
+        "x-slow" => 0.5,
+        "slow" => 0.75,
+        "medium" | "default" => 1.0,
+        "fast" => 1.25,
+        "x-fast" => 1.5,
+        v => match v.strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<f32>().ok()? / 100.0,
+            None => v.parse::<f32>().ok()?,
+        },
+    };
+    (rate.is_finite() && rate > 0.0).then(|| rate.clamp(0.5, 2.0))
+}
+
+/// 停顿时长: "500ms" / "1.5s"
+fn parse_time(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let millis = if let Some(ms) = value.strip_suffix("ms") {
+        ms.trim().parse::<f32>().ok()?
+    } else if let Some(s) = value.strip_suffix('s') {
+        s.trim().parse::<f32>().ok()? * 1000.0
+    } else {
+        return None;
+    };
+    (millis.is_finite() && millis >= 0.0).then(|| millis.min(10_000.0) as u32)
+}
+
+fn strength_millis(value: &str) -> Option<u32> {
+    Some(match value {
+        "none" => 0,
+        "x-weak" => 100,
+        "weak" => 200,
+        "medium" => 400,
+        "strong" => 700,
+        "x-strong" => 1000,
+        _ => return None,
+    })
+}
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+const ORDINALS: [&str; 31] = [
+    "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth",
+    "eleventh", "twelfth", "thirteenth", "fourteenth", "fifteenth", "sixteenth", "seventeenth",
+    "eighteenth", "nineteenth", "twentieth", "twenty-first", "twenty-second", "twenty-third",
+    "twenty-fourth", "twenty-fifth", "twenty-sixth", "twenty-seventh", "twenty-eighth",
+    "twenty-ninth", "thirtieth", "thirty-first",
+];
+
+/// say-as date: 按 format (ymd/mdy/dmy) 解析, 按声音语言输出读法
+fn verbalize_date(text: &str, format: &str, language: Language) -> Option<String> {
+    let fields: Vec<u32> = text
+        .split(['-', '/', '.'])
+        .map(|f| f.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    if fields.len() != 3 {
+        return None;
+    }
+    let (year, month, day) = match format {
+        "ymd" => (fields[0], fields[1], fields[2]),
+        "mdy" => (fields[2], fields[0], fields[1]),
+        "dmy" => (fields[2], fields[1], fields[0]),
+        _ => return None,
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(match language {
+        Language::Mandarin | Language::Japanese => format!("{}年{}月{}日", year, month, day),
+        Language::English => format!(
+            "{} {}, {}",
+            MONTHS[month as usize - 1],
+            ORDINALS[day as usize - 1],
+            year
+        ),
+    })
+}
+
+fn collect_text(children: &[Node]) -> String {
+    children
+        .iter()
+        .map(|child| match child {
+            Node::Text(t) => t.clone(),
+            Node::Element { children, .. } => collect_text(children),
+        })
+        .collect()
+}
+
+impl<'a> Compiler<'a> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> SsmlError {
+        SsmlError::at(self.src, offset, message)
+    }
+
+    /// 追加片段: 与上一段声音/语速相同则合并
+    fn push_part(&mut self, ctx: &Context, part: SsmlPart) {
+        if let SsmlPart::Text(text) = &part {
+            if text.trim().is_empty() {
+                return;
+            }
+        }
+        if let Some(SsmlItem::Speech(chunk)) = self.items.last_mut() {
+            if chunk.voice == ctx.voice && chunk.rate == ctx.rate {
+                match (chunk.parts.last_mut(), &part) {
+                    (Some(SsmlPart::Text(prev)), SsmlPart::Text(text)) => prev.push_str(text),
+                    _ => chunk.parts.push(part),
+                }
+                return;
+            }
+        }
+        self.items.push(SsmlItem::Speech(SsmlChunk {
+            voice: ctx.voice.clone(),
+            rate: ctx.rate,
+            parts: vec![part],
+        }));
+    }
+
+    fn language(&self, ctx: &Context) -> Language {
+        Language::from_voice(ctx.voice.as_deref().unwrap_or(self.default_voice))
+    }
+
+    fn compile_nodes(&mut self, nodes: &[Node], ctx: &Context) -> Result<(), SsmlError> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => {
+                    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    let padded = if text.starts_with(char::is_whitespace) || text.ends_with(char::is_whitespace) {
+                        format!(" {} ", collapsed)
+                    } else {
+                        collapsed
+                    };
+                    self.push_part(ctx, SsmlPart::Text(padded));
+                }
+                Node::Element { name, attrs, children, offset } => {
+                    self.compile_element(name, attrs, children, *offset, ctx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_element(
+        &mut self,
+        name: &str,
+        attrs: &[(String, String, usize)],
+        children: &[Node],
+        offset: usize,
+        ctx: &Context,
+    ) -> Result<(), SsmlError> {
+        match name {
+            "speak" => self.compile_nodes(children, ctx),
+            "break" => {
+                let millis = match (attr(attrs, "time"), attr(attrs, "strength")) {
+                    (Some((time, at)), _) => parse_time(time)
+                        .ok_or_else(|| self.error(at, format!("无效的停顿时长: {}", time)))?,
+                    (None, Some((strength, at))) => strength_millis(strength)
+                        .ok_or_else(|| self.error(at, format!("无效的停顿强度: {}", strength)))?,
+                    (None, None) => 400,
+                };
+                self.items.push(SsmlItem::Break(millis));
+                Ok(())
+            }
+            "prosody" => {
+                let mut inner = ctx.clone();
+                if let Some((rate, at)) = attr(attrs, "rate") {
+                    inner.rate = parse_rate(rate)
+                        .ok_or_else(|| self.error(at, format!("无效的语速: {}", rate)))?;
+                }
+                self.compile_nodes(children, &inner)
+            }
+            "voice" => {
+                let (voice, _) = attr(attrs, "name")
+                    .ok_or_else(|| self.error(offset, "<voice> 缺少 name 属性"))?;
+                // 别名解析为声音名, 后面的 say-as 才能按真实声音的语言读
+                let voice = (self.resolve_voice)(voice).unwrap_or_else(|| voice.to_string());
+                let inner = Context {
+                    voice: Some(voice),
+                    rate: ctx.rate,
+                };
+                self.compile_nodes(children, &inner)
+            }
+            "sub" => {
+                let (alias, _) = attr(attrs, "alias")
+                    .ok_or_else(|| self.error(offset, "<sub> 缺少 alias 属性"))?;
+                self.push_part(ctx, SsmlPart::Text(alias.to_string()));
+                Ok(())
+            }
+            "phoneme" => {
+                let (ph, ph_at) = attr(attrs, "ph")
+                    .ok_or_else(|| self.error(offset, "<phoneme> 缺少 ph 属性"))?;
+                if let Some((alphabet, at)) = attr(attrs, "alphabet") {
+                    if alphabet != "ipa" {
+                        return Err(self.error(at, format!("不支持的音标: {} (只支持 ipa)", alphabet)));
+                    }
+                }
+                if let Err(e) = self.vocab.tokenize_strict(ph) {
+                    return Err(self.error(ph_at, e.to_string()));
+                }
+                self.push_part(ctx, SsmlPart::Phonemes(ph.to_string()));
+                Ok(())
+            }
+            "say-as" => {
+                let (interpret_as, at) = attr(attrs, "interpret-as")
+                    .ok_or_else(|| self.error(offset, "<say-as> 缺少 interpret-as 属性"))?;
+                let text = collect_text(children);
+                let spoken = match interpret_as {
+                    "characters" | "spell-out" => text
+                        .chars()
+                        .filter(|c| !c.is_whitespace())
+                        .map(|c| c.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    "cardinal" | "number" => {
+                        let digits: String = text.chars().filter(|c| !matches!(c, ',' | '_' | ' ')).collect();
+                        if digits.is_empty() || !digits.trim_start_matches('-').chars().all(|c| c.is_ascii_digit() || c == '.') {
+                            return Err(self.error(offset, format!("不是有效的数字: {}", text.trim())));
+                        }
+                        digits
+                    }
+                    "date" => {
+                        let format = attr(attrs, "format").map_or("ymd", |(f, _)| f);
+                        verbalize_date(text.trim(), format, self.language(ctx))
+                            .ok_or_else(|| self.error(offset, format!("无法按 {} 解析日期: {}", format, text.trim())))?
+                    }
+                    other => return Err(self.error(at, format!("不支持的 interpret-as: {}", other))),
+                };
+                self.push_part(ctx, SsmlPart::Text(format!(" {} ", spoken)));
+                Ok(())
+            }
+            other => {
+                warn!("⚠️ 忽略不支持的 SSML 元素 <{}>", other);
+                self.compile_nodes(children, ctx)
+            }
+        }
+    }
+}
+
+/// 解析并编译 SSML
+///
+/// `default_voice` 用于决定 say-as date 等的读法语言; `<phoneme>` 按模型的 `vocab` 校验;
+/// `<voice name>` 经 `resolve_voice` 解析别名, 解析不了的名字原样保留, 由调用方报告。
+pub fn compile(
+    src: &str,
+    default_voice: &str,
+    vocab: &Vocab,
+    resolve_voice: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<SsmlItem>, SsmlError> {
+    let mut parser = Parser { src, pos: 0, depth: 0 };
+    let root = parser.parse_document()?;
+
+    let mut compiler = Compiler {
+        src,
+        default_voice,
+        vocab,
+        resolve_voice,
+        items: Vec::new(),
+    };
+    let ctx = Context { voice: None, rate: 1.0 };
+    compiler.compile_nodes(std::slice::from_ref(&root), &ctx)?;
+
+    // 去掉每段首尾多余空白
+    for item in compiler.items.iter_mut() {
+        if let SsmlItem::Speech(chunk) = item {
+            for part in chunk.parts.iter_mut() {
+                if let SsmlPart::Text(text) = part {
+                    *text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                }
+            }
+        }
+    }
+    Ok(compiler.items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vocab::EMBEDDED;
+
+    fn compile_ssml(src: &str, default_voice: &str) -> Result<Vec<SsmlItem>, SsmlError> {
+        compile(src, default_voice, &EMBEDDED, &|_| None)
+    }
+
+    fn text_chunk(text: &str) -> SsmlItem {
+        SsmlItem::Speech(SsmlChunk {
+            voice: None,
+            rate: 1.0,
+            parts: vec![SsmlPart::Text(text.to_string())],
+        })
+    }
+
+    #[test]
+    fn test_plain_speak() {
+        let items = compile_ssml("<speak>Hello &amp; welcome.</speak>", "af_alloy").unwrap();
+        assert_eq!(items, vec![text_chunk("Hello & welcome.")]);
+    }
+
+    #[test]
+    fn test_break_and_prosody() {
+        let items = compile_ssml(
+            r#"<speak>One<break time="500ms"/>two <prosody rate="slow">three</prosody><break strength="strong"/></speak>"#,
+            "af_alloy",
+        )
+        .unwrap();
+        assert_eq!(items.len(), 5);
+        assert_eq!(items[1], SsmlItem::Break(500));
+        match &items[3] {
+            SsmlItem::Speech(chunk) => assert_eq!(chunk.rate, 0.75),
+            other => panic!("unexpected item: {:?}", other),
+        }
+        assert_eq!(items[4], SsmlItem::Break(700));
+    }
+
+    #[test]
+    fn test_voice_switch_sub_and_phoneme() {
+        let items = compile_ssml(
+            r#"<speak>Hi <voice name="bm_george">I am <sub alias="World Wide Web Consortium">W3C</sub></voice> <phoneme alphabet="ipa" ph="təmˈɑːɹoʊ">tomorrow</phoneme></speak>"#,
+            "af_alloy",
+        )
+        .unwrap();
+        assert_eq!(items.len(), 3);
+        match &items[1] {
+            SsmlItem::Speech(chunk) => {
+                assert_eq!(chunk.voice.as_deref(), Some("bm_george"));
+                assert_eq!(chunk.parts, vec![SsmlPart::Text("I am World Wide Web Consortium".to_string())]);
+            }
+            other => panic!("unexpected item: {:?}", other),
+        }
+        match &items[2] {
+            SsmlItem::Speech(chunk) => assert_eq!(chunk.parts[0], SsmlPart::Phonemes("təmˈɑːɹoʊ".to_string())),
+            other => panic!("unexpected item: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_say_as() {
+        let items = compile_ssml(
+            r#"<speak><say-as interpret-as="characters">TTS</say-as> <say-as interpret-as="cardinal">1,234</say-as> <say-as interpret-as="date" format="mdy">10/18/2025</say-as></speak>"#,
+            "af_alloy",
+        )
+        .unwrap();
+        assert_eq!(items, vec![text_chunk("T T S 1234 October eighteenth, 2025")]);
+
+        let items = compile_ssml(r#"<speak><say-as interpret-as="date">2025-10-18</say-as></speak>"#, "zf_xiaobei").unwrap();
+        assert_eq!(items, vec![text_chunk("2025年10月18日")]);
+    }
+
+    #[test]
+    fn test_voice_alias_and_vocab() {
+        // 别名先解析, say-as date 按解析后声音的语言读
+        let resolve = |name: &str| (name == "xiaobei").then(|| "zf_xiaobei".to_string());
+        let src = r#"<speak><voice name="xiaobei"><say-as interpret-as="date">2025-10-18</say-as></voice></speak>"#;
+        let items = compile(src, "af_alloy", &EMBEDDED, &resolve).unwrap();
+        match &items[0] {
+            SsmlItem::Speech(chunk) => {
+                assert_eq!(chunk.voice.as_deref(), Some("zf_xiaobei"));
+                assert_eq!(chunk.parts, vec![SsmlPart::Text("2025年10月18日".to_string())]);
+            }
+            other => panic!("unexpected item: {:?}", other),
+        }
+
+        // <phoneme> 按模型的词汇表校验, 而不是内置表
+        let vocab = Vocab::from_tokenizer_json(r#"{"model": {"vocab": {"$": 0, "h": 50, "l": 54, "o": 57}}}"#).unwrap();
+        let src = r#"<speak><phoneme ph="həlo">hello</phoneme></speak>"#;
+        assert!(compile(src, "af_alloy", &EMBEDDED, &resolve).is_ok());
+        let err = compile(src, "af_alloy", &vocab, &resolve).unwrap_err();
+        assert_eq!(err.offset, 16);
+        assert!(compile(r#"<speak><phoneme ph="hlo">x</phoneme></speak>"#, "af_alloy", &vocab, &resolve).is_ok());
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let err = compile_ssml("<speak>\n  Hello <break time=\"5 parsecs\"/></speak>", "af_alloy").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 16));
+
+        let err = compile_ssml("<speak>Hello</voice>", "af_alloy").unwrap_err();
+        assert_eq!(err.offset, 12);
+        assert!(err.message.contains("不匹配"));
+
+        let err = compile_ssml("<speak>Hello", "af_alloy").unwrap_err();
+        assert_eq!(err.offset, 0);
+
+        let err = compile_ssml("<speak>A & B</speak>", "af_alloy").unwrap_err();
+        assert_eq!(err.column, 10);
+
+        let err = compile_ssml("<voice>Hi</voice>", "af_alloy").unwrap_err();
+        assert!(err.message.contains("<speak>"));
+
+        let err = compile_ssml(r#"<speak><phoneme ph="hə1lo">x</phoneme></speak>"#, "af_alloy").unwrap_err();
+        assert_eq!(err.offset, 16);
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        // 允许的最大深度正常解析
+        let depth = MAX_DEPTH - 1;
+        let ok = format!("<speak>{}Hi{}</speak>", "<s>".repeat(depth), "</s>".repeat(depth));
+        assert_eq!(compile_ssml(&ok, "af_alloy").unwrap(), vec![text_chunk("Hi")]);
+
+        // 深度嵌套在到达上限时报错, 不会递归到栈溢出
+        let nested = format!("<speak>\n{}Hi{}</speak>", "<emphasis>".repeat(50_000), "</emphasis>".repeat(50_000));
+        let err = compile_ssml(&nested, "af_alloy").unwrap_err();
+        assert!(err.message.contains("嵌套"), "{}", err.message);
+        let offset = "<speak>\n".len() + (MAX_DEPTH - 1) * "<emphasis>".len();
+        assert_eq!((err.offset, err.line, err.column), (offset, 2, offset - "<speak>\n".len() + 1));
+    }
+}
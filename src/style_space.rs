@@ -0,0 +1,365 @@
+//! 声音的 style 空间: 每个声音取 510 行 style 的平均值作为它的位置
+//!
+//! 加载完声音后预先计算:
+//!
+//! - 两两之间的余弦相似度, 用于 "和 bm_george 相似的女声" 这类查询
+//! - 主轴: 平均向量的主成分 (pc1, pc2 ...) 和女声/男声中心之差 (gender),
+//!   用于在 base 声音上沿这些轴移动来设计新声音
+//!
+//! 主成分用幂迭代在 Gram 矩阵 (声音数 × 声音数) 上求, 比 256×256 的协方差矩阵小得多。
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::style_table::StyleTable;
+use crate::voices::{Gender, VoiceError, STYLE_DIM};
+
+/// 主成分轴个数
+const PRINCIPAL_AXES: usize = 8;
+
+/// 幂迭代最大次数
+const POWER_ITERATIONS: usize = 500;
+
+/// 每个轴两端列出的声音数
+const AXIS_EXAMPLES: usize = 3;
+
+/// 设计声音时每个轴的最大偏移 (单位: 标准差)
+pub const MAX_OFFSET: f32 = 4.0;
+
+/// 相似声音查询结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighbor {
+    pub name: String,
+    pub similarity: f32,
+}
+
+/// style 空间中的一个方向
+#[derive(Debug, Clone, Serialize)]
+pub struct StyleAxis {
+    pub name: String,
+    /// 声音在该轴上投影的标准差: 偏移 1.0 = 移动一个标准差
+    pub scale: f32,
+    /// 解释的方差比例 (只有主成分轴有)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explained_variance: Option<f32>,
+    /// 投影最大的几个声音 (正方向)
+    pub positive: Vec<String>,
+    /// 投影最小的几个声音 (负方向)
+    pub negative: Vec<String>,
+    #[serde(skip)]
+    direction: Vec<f32>,
+}
+
+/// 设计声音失败
+#[derive(Debug, Error)]
+pub enum DesignError {
+    #[error("未知的轴: {axis} (可用: {})", .available.join(", "))]
+    UnknownAxis { axis: String, available: Vec<String> },
+    #[error("轴 {axis} 的偏移 {value} 超出范围 (±{MAX_OFFSET})")]
+    OutOfRange { axis: String, value: f32 },
+    #[error(transparent)]
+    Voice(#[from] VoiceError),
+}
+
+/// 所有已加载声音的相似度矩阵和主轴
+#[derive(Debug, Default)]
+pub struct StyleSpace {
+    names: Vec<String>,
+    /// names.len() × names.len(), 按行存储
+    similarity: Vec<f32>,
+    axes: Vec<StyleAxis>,
+}
+
+/// 按行平均: [510][256] → [256]
+pub fn mean_style(values: &[f32]) -> Vec<f32> {
+    let rows = (values.len() / STYLE_DIM).max(1);
+    let mut mean = vec![0.0f32; STYLE_DIM];
+    for row in values.chunks_exact(STYLE_DIM) {
+        for (m, v) in mean.iter_mut().zip(row) {
+            *m += v;
+        }
+    }
+    mean.iter_mut().for_each(|m| *m /= rows as f32);
+    mean
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let norm = (dot(a, a) * dot(b, b)).sqrt();
+    match norm > 0.0 {
+        true => dot(a, b) / norm,
+        false => 0.0,
+    }
+}
+
+fn normalize(v: &mut [f32]) -> bool {
+    let norm = dot(v, v).sqrt();
+    if norm <= f32::EPSILON {
+        return false;
+    }
+    v.iter_mut().for_each(|x| *x /= norm);
+    true
+}
+
+/// 中心化后的平均向量的前 `count` 个主成分: (单位方向, 方差)
+fn principal_components(centered: &[Vec<f32>], count: usize) -> Vec<(Vec<f32>, f32)> {
+    let n = centered.len();
+    if n < 2 {
+        return Vec::new();
+    }
+
+    // Gram 矩阵 G = X Xᵀ, 特征向量 u 对应主成分 Xᵀu
+    let mut gram: Vec<f64> = (0..n * n).map(|k| dot(&centered[k / n], &centered[k % n]) as f64).collect();
+    let total: f64 = (0..n).map(|i| gram[i * n + i]).sum();
+    let mut components = Vec::new();
+
+    for k in 0..count.min(n - 1) {
+        let mut u: Vec<f64> = (0..n).map(|i| 1.0 + ((i + k) % 7) as f64 * 0.1).collect();
+        let mut eigenvalue = 0.0;
+        for _ in 0..POWER_ITERATIONS {
+            let mut next: Vec<f64> = (0..n).map(|i| (0..n).map(|j| gram[i * n + j] * u[j]).sum()).collect();
+            let norm = next.iter().map(|x| x * x).sum::<f64>().sqrt();
+            if norm <= total * 1e-9 {
+                eigenvalue = 0.0;
+                break;
+            }
+            next.iter_mut().for_each(|x| *x /= norm);
+            let converged = next.iter().zip(&u).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max) < 1e-10;
+            u = next;
+            eigenvalue = norm;
+            if converged {
+                break;
+            }
+        }
+        if eigenvalue <= total * 1e-6 {
+            break;
+        }
+
+        // 去掉已找到的分量
+        for i in 0..n {
+            for j in 0..n {
+                gram[i * n + j] -= eigenvalue * u[i] * u[j];
+            }
+        }
+
+        let mut direction = vec![0.0f32; STYLE_DIM];
+        for (x, &weight) in centered.iter().zip(&u) {
+            for (d, v) in direction.iter_mut().zip(x) {
+                *d += v * weight as f32;
+            }
+        }
+        if !normalize(&mut direction) {
+            break;
+        }
+        // 符号约定: 绝对值最大的分量为正
+        let largest = direction.iter().copied().fold(0.0f32, |a, b| if b.abs() > a.abs() { b } else { a });
+        if largest < 0.0 {
+            direction.iter_mut().for_each(|d| *d = -*d);
+        }
+        components.push((direction, (eigenvalue / total) as f32));
+    }
+    components
+}
+
+impl StyleSpace {
+    /// 由 (声音名, 平均 style 向量) 构建, 按名字排序; `genders` 用于计算 gender 轴
+    pub fn new(mut voices: Vec<(String, Vec<f32>)>, genders: &BTreeMap<String, Gender>) -> Self {
+        voices.sort_by(|a, b| a.0.cmp(&b.0));
+        let (names, means): (Vec<String>, Vec<Vec<f32>>) = voices.into_iter().unzip();
+
+        let n = names.len();
+        let mut similarity = vec![0.0f32; n * n];
+        for i in 0..n {
+            similarity[i * n + i] = 1.0;
+            for j in i + 1..n {
+                let value = cosine(&means[i], &means[j]);
+                similarity[i * n + j] = value;
+                similarity[j * n + i] = value;
+            }
+        }
+        let axes = build_axes(&names, &means, genders);
+        Self { names, similarity, axes }
+    }
+
+    /// 可用于设计声音的轴
+    pub fn axes(&self) -> &[StyleAxis] {
+        &self.axes
+    }
+
+    /// 在 base 声音的每一行上加 Σ 偏移 × 标准差 × 轴方向, 得到新的 style 表
+    pub fn design(&self, base: &StyleTable, offsets: &BTreeMap<String, f32>) -> Result<StyleTable, DesignError> {
+        let mut shift = vec![0.0f32; STYLE_DIM];
+        for (name, &offset) in offsets {
+            let axis = self.axes.iter().find(|axis| axis.name == *name).ok_or_else(|| DesignError::UnknownAxis {
+                axis: name.clone(),
+                available: self.axes.iter().map(|axis| axis.name.clone()).collect(),
+            })?;
+            if !offset.is_finite() || offset.abs() > MAX_OFFSET {
+                return Err(DesignError::OutOfRange { axis: name.clone(), value: offset });
+            }
+            for (s, d) in shift.iter_mut().zip(&axis.direction) {
+                *s += offset * axis.scale * d;
+            }
+        }
+
+        let values = base
+            .as_slice()
+            .chunks_exact(STYLE_DIM)
+            .flat_map(|row| row.iter().zip(&shift).map(|(v, s)| v + s))
+            .collect();
+        Ok(StyleTable::from_values(values)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.names.binary_search_by(|n| n.as_str().cmp(name)).ok()
+    }
+
+    /// 与 `name` 最相似的声音 (不含自身), `accept` 过滤候选; 声音不在空间中时返回 None
+    pub fn nearest(&self, name: &str, limit: usize, accept: impl Fn(&str) -> bool) -> Option<Vec<Neighbor>> {
+        let i = self.position(name)?;
+        let row = &self.similarity[i * self.len()..(i + 1) * self.len()];
+        let mut neighbors: Vec<Neighbor> = self
+            .names
+            .iter()
+            .zip(row)
+            .filter(|(other, _)| *other != name && accept(other))
+            .map(|(other, &similarity)| Neighbor { name: other.clone(), similarity })
+            .collect();
+        neighbors.sort_by(|a, b| b.similarity.total_cmp(&a.similarity).then_with(|| a.name.cmp(&b.name)));
+        neighbors.truncate(limit);
+        Some(neighbors)
+    }
+}
+
+/// 主成分轴 + gender 轴 (女声中心 - 男声中心, 正方向偏女声)
+fn build_axes(names: &[String], means: &[Vec<f32>], genders: &BTreeMap<String, Gender>) -> Vec<StyleAxis> {
+    let centroid = |selected: &[&Vec<f32>]| {
+        let mut c = vec![0.0f32; STYLE_DIM];
+        for v in selected {
+            c.iter_mut().zip(v.iter()).for_each(|(c, v)| *c += v / selected.len() as f32);
+        }
+        c
+    };
+    let all: Vec<&Vec<f32>> = means.iter().collect();
+    let center = centroid(&all);
+    let centered: Vec<Vec<f32>> = means.iter().map(|m| m.iter().zip(&center).map(|(v, c)| v - c).collect()).collect();
+
+    let mut directions: Vec<(String, Vec<f32>, Option<f32>)> = principal_components(&centered, PRINCIPAL_AXES)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (direction, explained))| (format!("pc{}", i + 1), direction, Some(explained)))
+        .collect();
+
+    let by_gender = |gender: Gender| -> Vec<&Vec<f32>> {
+        names.iter().zip(means).filter(|(name, _)| genders.get(*name) == Some(&gender)).map(|(_, m)| m).collect()
+    };
+    let (female, male) = (by_gender(Gender::Female), by_gender(Gender::Male));
+    if !female.is_empty() && !male.is_empty() {
+        let mut direction: Vec<f32> = centroid(&female).iter().zip(centroid(&male)).map(|(f, m)| f - m).collect();
+        if normalize(&mut direction) {
+            directions.push(("gender".to_string(), direction, None));
+        }
+    }
+
+    directions
+        .into_iter()
+        .map(|(name, direction, explained_variance)| {
+            let mut projections: Vec<(f32, &String)> = centered.iter().map(|x| dot(x, &direction)).zip(names).collect();
+            let variance = projections.iter().map(|(p, _)| p * p).sum::<f32>() / (projections.len().max(2) - 1) as f32;
+            projections.sort_by(|a, b| b.0.total_cmp(&a.0));
+            let examples = |iter: &mut dyn Iterator<Item = &(f32, &String)>| iter.take(AXIS_EXAMPLES).map(|(_, n)| (*n).clone()).collect();
+            StyleAxis {
+                name,
+                scale: variance.sqrt(),
+                explained_variance,
+                positive: examples(&mut projections.iter()),
+                negative: examples(&mut projections.iter().rev()),
+                direction,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(values: &[f32]) -> Vec<f32> {
+        let mut v = vec![0.0; STYLE_DIM];
+        v[..values.len()].copy_from_slice(values);
+        v
+    }
+
+    #[test]
+    fn test_mean_style() {
+        let mut values = vec![1.0f32; STYLE_DIM * 2];
+        values[STYLE_DIM] = 3.0;
+        let mean = mean_style(&values);
+        assert_eq!((mean[0], mean[1]), (2.0, 1.0));
+    }
+
+    #[test]
+    fn test_nearest() {
+        let space = StyleSpace::new(vec![
+            ("c".to_string(), vector(&[0.0, 1.0])),
+            ("a".to_string(), vector(&[1.0, 0.0])),
+            ("b".to_string(), vector(&[1.0, 0.1])),
+            ("d".to_string(), vector(&[-1.0, 0.0])),
+        ], &BTreeMap::new());
+        let from_a = space.nearest("a", 10, |_| true).unwrap();
+        assert!((from_a[2].similarity + 1.0).abs() < 1e-6);
+        assert_eq!(from_a[0].similarity, space.nearest("b", 1, |_| true).unwrap()[0].similarity);
+
+        let names = |n: Vec<Neighbor>| n.into_iter().map(|n| n.name).collect::<Vec<_>>();
+        assert_eq!(names(space.nearest("a", 10, |_| true).unwrap()), vec!["b", "c", "d"]);
+        assert_eq!(names(space.nearest("a", 1, |n| n != "b").unwrap()), vec!["c"]);
+        assert!(space.nearest("zz", 3, |_| true).is_none());
+    }
+
+    #[test]
+    fn test_principal_axes() {
+        // 方差主要在第 0 维, 其次第 1 维
+        let voices = vec![
+            ("a".to_string(), vector(&[3.0, 0.5, 1.0])),
+            ("b".to_string(), vector(&[-3.0, -0.5, 1.0])),
+            ("c".to_string(), vector(&[1.0, -1.0, 1.0])),
+            ("d".to_string(), vector(&[-1.0, 1.0, 1.0])),
+        ];
+        let genders = BTreeMap::from([("a".to_string(), Gender::Female), ("b".to_string(), Gender::Male)]);
+        let space = StyleSpace::new(voices, &genders);
+        let names: Vec<&str> = space.axes().iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["pc1", "pc2", "gender"]);
+
+        let pc1 = &space.axes()[0];
+        assert!(pc1.direction[0] > 0.99);
+        assert!(pc1.explained_variance.unwrap() > space.axes()[1].explained_variance.unwrap());
+        assert_eq!((pc1.positive[0].as_str(), pc1.negative[0].as_str()), ("a", "b"));
+        assert_eq!(space.axes()[2].positive[0], "a");
+    }
+
+    #[test]
+    fn test_design() {
+        let voices = vec![("a".to_string(), vector(&[1.0])), ("b".to_string(), vector(&[-1.0]))];
+        let space = StyleSpace::new(voices, &BTreeMap::new());
+        let base = StyleTable::from_values(vec![0.5; crate::voices::STYLE_ROWS * STYLE_DIM]).unwrap();
+
+        let scale = space.axes()[0].scale;
+        let designed = space.design(&base, &BTreeMap::from([("pc1".to_string(), 2.0)])).unwrap();
+        assert!((designed.row(0)[0] - (0.5 + 2.0 * scale)).abs() < 1e-5);
+        assert!((designed.row(509)[0] - designed.row(0)[0]).abs() < 1e-6);
+        assert_eq!(designed.row(0)[1], 0.5);
+
+        let unknown = space.design(&base, &BTreeMap::from([("pc9".to_string(), 1.0)]));
+        assert!(matches!(unknown, Err(DesignError::UnknownAxis { .. })));
+        let too_far = space.design(&base, &BTreeMap::from([("pc1".to_string(), 10.0)]));
+        assert!(matches!(too_far, Err(DesignError::OutOfRange { .. })));
+    }
+}
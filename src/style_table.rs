@@ -0,0 +1,192 @@
+//! 声音 style 表: 510 行 × 256 维 f32, 连续存储
+//!
+//! 磁盘上的 .bin 文件通过 mmap 映射 (只读), 不再为每一行单独分配 Vec;
+//! 上传/导入的声音用一块堆内存保存。两种方式都会校验大小和 NaN/Inf。
+
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use crate::voices::{VoiceError, STYLE_DIM, STYLE_ROWS, VOICE_FILE_BYTES};
+
+/// 只读内存映射
+#[cfg(unix)]
+struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// 映射只读且在 Drop 前一直有效, 可以跨线程共享
+#[cfg(unix)]
+unsafe impl Send for Mmap {}
+#[cfg(unix)]
+unsafe impl Sync for Mmap {}
+
+#[cfg(unix)]
+impl Mmap {
+    fn map(file: &File, len: usize) -> std::io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        // SAFETY: 只读私有映射, 长度来自文件元数据且不为 0
+        let ptr = unsafe {
+            libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: ptr 指向 len 字节的有效映射, 生命周期与 self 相同
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        // SAFETY: ptr/len 来自成功的 mmap 调用
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+enum Storage {
+    /// 映射的 .bin 文件 (小端 f32, 页对齐)
+    #[cfg(all(unix, target_endian = "little"))]
+    Mapped(Mmap),
+    Owned(Vec<f32>),
+}
+
+/// 一个声音的 style 表
+pub struct StyleTable {
+    storage: Storage,
+}
+
+impl fmt::Debug for StyleTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StyleTable").field("mapped", &self.is_mapped()).finish()
+    }
+}
+
+/// 检查 NaN/Inf, 报告第一个出错的位置
+fn check_finite(values: &[f32]) -> Result<(), VoiceError> {
+    match values.iter().position(|v| !v.is_finite()) {
+        Some(i) => Err(VoiceError::NonFinite { row: i / STYLE_DIM, column: i % STYLE_DIM, value: values[i] }),
+        None => Ok(()),
+    }
+}
+
+fn check_size(actual: usize) -> Result<(), VoiceError> {
+    match actual == VOICE_FILE_BYTES {
+        true => Ok(()),
+        false => Err(VoiceError::InvalidSize { expected: VOICE_FILE_BYTES, actual }),
+    }
+}
+
+impl StyleTable {
+    /// 从连续的 f32 值创建 (长度必须是 510×256)
+    pub fn from_values(values: Vec<f32>) -> Result<Self, VoiceError> {
+        check_size(values.len() * 4)?;
+        check_finite(&values)?;
+        Ok(Self { storage: Storage::Owned(values) })
+    }
+
+    /// 从 .bin 字节 (小端 f32) 创建
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VoiceError> {
+        check_size(bytes.len())?;
+        let values = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        Self::from_values(values)
+    }
+
+    /// 映射 .bin 文件 (非 unix / 大端平台退回到读入内存)
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, VoiceError> {
+        let file = File::open(path.as_ref())?;
+        let len = file.metadata()?.len() as usize;
+        check_size(len)?;
+
+        #[cfg(all(unix, target_endian = "little"))]
+        {
+            let table = Self { storage: Storage::Mapped(Mmap::map(&file, len)?) };
+            check_finite(table.as_slice())?;
+            Ok(table)
+        }
+        #[cfg(not(all(unix, target_endian = "little")))]
+        {
+            drop(file);
+            Self::from_bytes(&std::fs::read(path)?)
+        }
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        !matches!(self.storage, Storage::Owned(_))
+    }
+
+    /// 全部值, 按行连续排列
+    pub fn as_slice(&self) -> &[f32] {
+        match &self.storage {
+            #[cfg(all(unix, target_endian = "little"))]
+            Storage::Mapped(mmap) => {
+                let bytes = mmap.as_bytes();
+                // SAFETY: mmap 页对齐, 满足 f32 对齐; 长度已校验为 4 的倍数; 小端平台字节序一致
+                unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const f32, bytes.len() / 4) }
+            }
+            Storage::Owned(values) => values,
+        }
+    }
+
+    /// 第 `index` 行 (按 token 数索引)
+    pub fn row(&self, index: usize) -> &[f32] {
+        let start = index.min(STYLE_ROWS - 1) * STYLE_DIM;
+        &self.as_slice()[start..start + STYLE_DIM]
+    }
+
+    /// 写回 .bin 格式
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        self.as_slice().iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voices::VOICES_DIR;
+
+    #[test]
+    fn test_mapped_matches_file() {
+        let path = Path::new(VOICES_DIR).join("af_alloy.bin");
+        let table = StyleTable::open(&path).unwrap();
+        let owned = StyleTable::from_bytes(&std::fs::read(&path).unwrap()).unwrap();
+
+        assert_eq!(table.is_mapped(), cfg!(all(unix, target_endian = "little")));
+        assert!(!owned.is_mapped());
+        assert_eq!(table.as_slice(), owned.as_slice());
+        assert_eq!(table.as_slice().len(), STYLE_ROWS * STYLE_DIM);
+        assert_eq!(table.row(5), &owned.as_slice()[5 * STYLE_DIM..6 * STYLE_DIM]);
+        assert_eq!(table.row(10_000), table.row(STYLE_ROWS - 1));
+    }
+
+    #[test]
+    fn test_rejects_corrupt_files() {
+        let dir = std::env::temp_dir().join(format!("tts_style_table_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let truncated = dir.join("truncated.bin");
+        std::fs::write(&truncated, vec![0u8; VOICE_FILE_BYTES - 256]).unwrap();
+        assert!(matches!(StyleTable::open(&truncated), Err(VoiceError::InvalidSize { .. })));
+
+        let mut values = vec![0.25f32; STYLE_ROWS * STYLE_DIM];
+        values[2 * STYLE_DIM + 9] = f32::INFINITY;
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let infinite = dir.join("infinite.bin");
+        std::fs::write(&infinite, &bytes).unwrap();
+        assert!(matches!(StyleTable::open(&infinite), Err(VoiceError::NonFinite { row: 2, column: 9, .. })));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
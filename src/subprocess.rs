@@ -0,0 +1,195 @@
+//! 外部进程调用
+//!
+//! 输入通过 stdin 传递 (不放进 argv, 避免被当成命令行选项), 带超时和输出大小上限,
+//! 超时或输出过大时杀掉进程, 防止挂起的子进程一直占着引擎锁。
+
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// 保留的 stderr 长度 (字节), 只用于错误信息
+const STDERR_LIMIT: usize = 4096;
+
+/// 轮询子进程状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Error)]
+pub enum SubprocessError {
+    #[error("{program} 未安装")]
+    NotInstalled { program: String },
+    #[error("无法启动 {program}: {source}")]
+    Spawn { program: String, source: io::Error },
+    #[error("{program} 超时 ({timeout:?}), 已终止")]
+    Timeout { program: String, timeout: Duration },
+    #[error("{program} 输出超过 {limit} 字节, 已终止")]
+    OutputTooLarge { program: String, limit: usize },
+    #[error("{program} 执行失败 (退出码 {code:?}): {stderr}")]
+    Failed { program: String, code: Option<i32>, stderr: String },
+    #[error("与 {program} 通信失败: {source}")]
+    Io { program: String, source: io::Error },
+}
+
+/// 运行限制
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub timeout: Duration,
+    pub max_output: usize,
+}
+
+/// 读取最多 `limit` 字节, 超出时置位 `overflow` 并停止读取
+fn read_limited<R: Read>(mut reader: R, limit: usize, overflow: &AtomicBool) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(out);
+        }
+        if out.len() + n > limit {
+            overflow.store(true, Ordering::SeqCst);
+            return Ok(out);
+        }
+        out.extend_from_slice(&buf[..n]);
+    }
+}
+
+/// 运行外部程序: `input` 写入 stdin, 返回 stdout
+pub fn run(mut command: Command, input: &[u8], limits: Limits) -> Result<Vec<u8>, SubprocessError> {
+    let program = command.get_program().to_string_lossy().into_owned();
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|source| match source.kind() {
+            io::ErrorKind::NotFound => SubprocessError::NotInstalled { program: program.clone() },
+            _ => SubprocessError::Spawn { program: program.clone(), source },
+        })?;
+
+    let io_error = |source: io::Error| SubprocessError::Io { program: program.clone(), source };
+
+    // stdin / stdout / stderr 分别在线程中处理, 避免管道写满互相阻塞
+    let mut stdin = child.stdin.take().expect("stdin 已设置为 piped");
+    let stdout = child.stdout.take().expect("stdout 已设置为 piped");
+    let stderr = child.stderr.take().expect("stderr 已设置为 piped");
+
+    let input = input.to_vec();
+    let writer = thread::spawn(move || {
+        // 进程提前退出时写入会 BrokenPipe, 交给退出状态判断
+        let result = stdin.write_all(&input);
+        drop(stdin);
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            other => other,
+        }
+    });
+
+    let overflow = Arc::new(AtomicBool::new(false));
+    let stdout_overflow = Arc::clone(&overflow);
+    let max_output = limits.max_output;
+    let stdout_reader = thread::spawn(move || read_limited(stdout, max_output, &stdout_overflow));
+    let stderr_reader = thread::spawn(move || {
+        let ignored = AtomicBool::new(false);
+        let mut stderr = stderr;
+        let head = read_limited(&mut stderr, STDERR_LIMIT, &ignored);
+        // 丢弃剩余内容, 保证子进程不会卡在写 stderr 上
+        let _ = io::copy(&mut stderr, &mut io::sink());
+        head
+    });
+
+    let deadline = Instant::now() + limits.timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(io_error)? {
+            break status;
+        }
+        if overflow.load(Ordering::SeqCst) || Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = writer.join();
+            let _ = stdout_reader.join();
+            let _ = stderr_reader.join();
+            return Err(if overflow.load(Ordering::SeqCst) {
+                SubprocessError::OutputTooLarge { program, limit: limits.max_output }
+            } else {
+                SubprocessError::Timeout { program, timeout: limits.timeout }
+            });
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    writer.join().expect("stdin 线程 panic").map_err(io_error)?;
+    let stdout = stdout_reader.join().expect("stdout 线程 panic").map_err(io_error)?;
+    let stderr = stderr_reader.join().expect("stderr 线程 panic").map_err(io_error)?;
+
+    if overflow.load(Ordering::SeqCst) {
+        return Err(SubprocessError::OutputTooLarge { program, limit: limits.max_output });
+    }
+    if !status.success() {
+        return Err(SubprocessError::Failed {
+            program,
+            code: status.code(),
+            stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+        });
+    }
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        timeout: Duration::from_secs(5),
+        max_output: 1024,
+    };
+
+    #[test]
+    fn test_stdin_is_data() {
+        let out = run(Command::new("cat"), b"--help -v", LIMITS).unwrap();
+        assert_eq!(out, b"--help -v");
+    }
+
+    #[test]
+    fn test_not_installed() {
+        let err = run(Command::new("definitely-not-a-real-program"), b"", LIMITS).unwrap_err();
+        assert!(matches!(err, SubprocessError::NotInstalled { .. }));
+    }
+
+    #[test]
+    fn test_timeout_kills() {
+        let mut command = Command::new("sleep");
+        command.arg("10");
+        let started = Instant::now();
+        let limits = Limits { timeout: Duration::from_millis(100), ..LIMITS };
+        let err = run(command, b"", limits).unwrap_err();
+        assert!(matches!(err, SubprocessError::Timeout { .. }));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_output_limit() {
+        let mut command = Command::new("yes");
+        command.arg("spam");
+        let err = run(command, b"", LIMITS).unwrap_err();
+        assert!(matches!(err, SubprocessError::OutputTooLarge { limit: 1024, .. }));
+    }
+
+    #[test]
+    fn test_failure_reports_stderr() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo boom >&2; exit 3"]);
+        let err = run(command, b"", LIMITS).unwrap_err();
+        match err {
+            SubprocessError::Failed { code, stderr, .. } => {
+                assert_eq!(code, Some(3));
+                assert_eq!(stderr, "boom");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+}
@@ -1,36 +1,137 @@
-/// TTS Engine - Kokoro-82M ONNX 实现
-///
-/// 模型: Kokoro-82M (82M 参数 TTS 模型)
-/// 运行时: ONNX Runtime 2.0-rc
-///
-/// 输入:
-/// - tokens: i64 数组 [batch, seq_len]  (音素 token IDs)
-/// - style: f32 数组 [1, 256]           (说话人风格向量)
-/// - speed: f32 数组 [1]                (语速控制)
-///
-/// 输出:
-/// - audio: f32 数组 [batch, audio_len] (24kHz 音频波形)
+//! TTS Engine - Kokoro-82M ONNX 实现
+//!
+//! 模型: Kokoro-82M (82M 参数 TTS 模型)
+//! 运行时: ONNX Runtime 2.0-rc
+//!
+//! 输入:
+//! - tokens: i64 数组 [batch, seq_len]  (音素 token IDs)
+//! - style: f32 数组 [1, 256]           (说话人风格向量)
+//! - speed: f32 数组 [1]                (语速控制)
+//!
+//! 输出:
+//! - audio: f32 数组 [batch, audio_len] (24kHz 音频波形)
 
 use anyhow::{Context, Result};
 use ort::session::{builder::GraphOptimizationLevel, Session};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
 use std::path::Path;
-use std::process::Command;
-use tracing::info;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::diagnostics::SynthesisDiagnostics;
+use crate::espeak::{self, EspeakError};
+use crate::homograph::{self, SenseHints};
+use crate::verbalize::{self, SymbolOptions};
+use crate::style_table::StyleTable;
+use crate::voice_alias;
+use crate::voice_store::{self, VoiceStore};
+use crate::voices::{self, VoiceError};
+use crate::ja_g2p;
+use crate::language::{map_cjk_punctuation, Language};
+use crate::ssml::{SsmlItem, SsmlPart};
+use crate::vocab::{Vocab, VocabError};
+use crate::zh_g2p;
+
+/// 单次推理的 token 上限 (安全限制)
+pub const MAX_TOKENS: usize = 400;
+
+/// 模型输出采样率 (Hz)
+pub const SAMPLE_RATE: u32 = 24000;
+
+/// 文本前端选项 (只影响 `Text` / `Ssml` 输入)
+#[derive(Debug, Clone, Default)]
+pub struct TextOptions {
+    /// 英语同形异音词读音提示: read → past
+    pub senses: SenseHints,
+    /// 符号/emoji 的处理方式
+    pub symbols: SymbolOptions,
+}
+
+impl TextOptions {
+    /// 追加到缓存键的后缀 (默认选项为空, 不影响已有缓存)
+    pub fn cache_suffix(&self) -> String {
+        let mut suffix = String::new();
+        if !self.senses.is_empty() {
+            suffix.push_str(&format!("|senses:{}", homograph::hints_key(&self.senses)));
+        }
+        if !self.symbols.is_default() {
+            suffix.push_str(&format!("|symbols:{}", self.symbols.cache_key()));
+        }
+        suffix
+    }
+}
+
+/// 合成输入
+///
+/// - `Text`: 完整流程 (G2P → tokenize → 推理)
+/// - `Phonemes`: IPA 音素串, 跳过 G2P
+/// - `Tokens`: token ID, 跳过 G2P 和 tokenize
+/// - `Ssml`: SSML 子集, 按段合成
+#[derive(Debug, Clone)]
+pub enum SynthesisInput {
+    Text(String),
+    Phonemes(String),
+    Tokens(Vec<i64>),
+    Ssml(String),
+}
+
+impl SynthesisInput {
+    /// 缓存命名空间: 原始输入与文本分开缓存
+    pub fn cache_namespace(&self) -> &'static str {
+        match self {
+            SynthesisInput::Text(_) => crate::cache::TEXT_NAMESPACE,
+            SynthesisInput::Phonemes(_) => "phonemes",
+            SynthesisInput::Tokens(_) => "tokens",
+            SynthesisInput::Ssml(_) => "ssml",
+        }
+    }
+
+    /// 用于缓存哈希的输入内容
+    pub fn cache_input(&self) -> String {
+        match self {
+            SynthesisInput::Text(text) | SynthesisInput::Phonemes(text) | SynthesisInput::Ssml(text) => text.clone(),
+            SynthesisInput::Tokens(tokens) => tokens
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+
+    /// 按词汇表校验原始输入 (文本输入不需要校验, SSML 由 `ssml::compile` 校验)
+    pub fn validate(&self, vocab: &Vocab) -> Result<(), VocabError> {
+        match self {
+            SynthesisInput::Text(_) | SynthesisInput::Ssml(_) => Ok(()),
+            SynthesisInput::Phonemes(phonemes) => vocab.tokenize_strict(phonemes).map(|_| ()),
+            SynthesisInput::Tokens(tokens) => vocab.validate_tokens(tokens),
+        }
+    }
+
+    /// 日志预览
+    pub fn preview(&self) -> String {
+        self.cache_input().chars().take(50).collect()
+    }
+}
+
+/// 合成结果
+pub struct Synthesis {
+    pub audio: Vec<f32>,
+    pub diagnostics: SynthesisDiagnostics,
+}
 
 pub struct TTSEngine {
     session: Session,
     sample_rate: u32,
-    voices: HashMap<String, Vec<Vec<f32>>>, // voice_name -> [510 tokens, 256 dims]
+    voices: Arc<VoiceStore>, // voice_name -> [510 tokens, 256 dims], 按需加载
     default_voice: String,
+    vocab: Vocab,
 }
 
 impl TTSEngine {
     /// 初始化 TTS 引擎
     pub fn new<P: AsRef<Path>>(model_path: P) -> Result<Self> {
         info!("🔧 TTS 引擎初始化");
+        let started = Instant::now();
 
         let model_path = model_path.as_ref();
         info!("📂 加载模型: {:?}", model_path);
@@ -55,137 +156,261 @@ impl TTSEngine {
             info!("  - 名称: {}, 类型: {:?}", output.name, output.output_type);
         }
 
-        // 加载所有 voices
-        info!("📂 加载所有声音...");
-        let voices = Self::load_all_voices("data/voices")?;
-        info!("✅ 加载 {} 个声音", voices.len());
+        // 词汇表: 模型同目录下的 tokenizer.json
+        let vocab = Vocab::for_model(model_path)?;
 
-        let default_voice = "af_alloy".to_string();
+        // 声音索引: 文件在首次使用时映射, 其余的在后台并行预加载
+        let voices = Arc::new(VoiceStore::open(voices::VOICES_DIR)?);
+        info!("📂 登记 {} 个声音 (后台预加载)", voices.len());
+        VoiceStore::preload_in_background(Arc::clone(&voices));
+
+        let default_voice = voice_alias::aliases().default_voice().to_string();
         info!("🎵 默认声音: {}", default_voice);
+        info!(
+            "⏱️ 引擎启动耗时 {:.0?}, 常驻内存 {}",
+            started.elapsed(),
+            voice_store::describe_memory()
+        );
 
         Ok(Self {
             session,
-            sample_rate: 24000,
+            sample_rate: SAMPLE_RATE,
             voices,
             default_voice,
+            vocab,
         })
     }
 
-    /// 加载所有声音的 style vectors
-    fn load_all_voices<P: AsRef<Path>>(voices_dir: P) -> Result<HashMap<String, Vec<Vec<f32>>>> {
-        use std::fs;
+    /// 当前模型的词汇表
+    pub fn vocab(&self) -> &Vocab {
+        &self.vocab
+    }
 
-        let voices_dir = voices_dir.as_ref();
-        let index_path = voices_dir.join("index.json");
+    /// 声音表 (可在释放引擎锁后使用)
+    pub fn voices(&self) -> Arc<VoiceStore> {
+        Arc::clone(&self.voices)
+    }
 
-        // 读取索引文件
-        let index_content = fs::read_to_string(&index_path)
-            .with_context(|| format!("无法读取 index.json: {:?}", index_path))?;
+    /// 注册新声音 (上传后无需重启即可使用)
+    pub fn insert_voice(&mut self, name: &str, table: StyleTable) {
+        info!("➕ 注册声音: {}", name);
+        self.voices.insert(name, table);
+    }
 
-        let index: serde_json::Value = serde_json::from_str(&index_content)?;
-        let voices_obj = index.as_object()
-            .context("index.json 格式错误")?;
+    /// 移除声音
+    pub fn remove_voice(&mut self, name: &str) -> bool {
+        info!("➖ 移除声音: {}", name);
+        self.voices.remove(name)
+    }
 
-        let mut voices = HashMap::new();
+    /// 文本转语音 - ONNX 推理 (指定语速)
+    fn synthesize_with_speed(
+        &mut self,
+        text: &str,
+        voice: Option<&str>,
+        speed: f32,
+        options: &TextOptions,
+        diagnostics: &mut SynthesisDiagnostics,
+    ) -> Result<Vec<f32>> {
+        let voice_name = voice.unwrap_or(&self.default_voice);
+        // 安全截断：使用字符迭代器
+        let text_preview: String = text.chars().take(50).collect();
+        info!("🎵 合成文本: \"{}\" (声音: {})", text_preview, voice_name);
 
-        for (voice_name, voice_info) in voices_obj {
-            let file_name = voice_info["file"].as_str()
-                .context("缺少 file 字段")?;
+        // 1. 检查文本长度，如果太长则分段处理
+        // 先进行音素化以获取实际 token 数
+        let tokens = text_tokens(text, voice_name, options, &self.vocab, diagnostics)?;
 
-            let file_path = voices_dir.join(file_name);
-            let vectors = Self::load_voice_file(&file_path)?;
+        // 如果 tokens 数超过限制，按句子分割文本重新合成
+        if tokens.len() > MAX_TOKENS {
+            info!("⚠️ 文本过长 ({} tokens > {} 限制)，自动分段处理", tokens.len(), MAX_TOKENS);
+            return self.synthesize_long_text(text, voice, speed, options, diagnostics);
+        }
 
-            voices.insert(voice_name.clone(), vectors);
+        if tokens.is_empty() {
+            return Ok(vec![0.0; 24000]); // 1秒静音
         }
 
-        Ok(voices)
+        let voice_name = voice_name.to_string();
+        self.synthesize_tokens(&tokens, &voice_name, speed, diagnostics)
+    }
+
+    /// 按输入模式合成: 文本走完整 G2P, 原始音素/token 跳过 G2P
+    ///
+    /// 返回音频和本次合成的诊断信息 (被丢弃的字符、是否降级、分段数)。
+    /// `options` 控制文本前端: 英语同形异音词读音提示、符号/emoji 读法。
+    pub fn synthesize_input(
+        &mut self,
+        input: &SynthesisInput,
+        voice: Option<&str>,
+        options: &TextOptions,
+    ) -> Result<Synthesis> {
+        let mut diagnostics = SynthesisDiagnostics::default();
+        let audio = self.synthesize_input_with(input, voice, options, &mut diagnostics)?;
+
+        if !diagnostics.dropped.is_empty() {
+            warn!("⚠️ {} 个音素字符不在词汇表中, 已丢弃", diagnostics.dropped.len());
+        }
+        Ok(Synthesis { audio, diagnostics })
     }
 
-    /// 加载单个声音文件
-    fn load_voice_file<P: AsRef<Path>>(path: P) -> Result<Vec<Vec<f32>>> {
-        let mut file = File::open(path.as_ref())
-            .with_context(|| format!("无法打开声音文件: {:?}", path.as_ref()))?;
-
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)?;
-
-        // 解析为 f32 数组
-        let floats: Vec<f32> = buffer
-            .chunks_exact(4)
-            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
-            .collect();
-
-        // 重组为 [510, 256] 结构
-        const TOKEN_LIMIT: usize = 510;
-        const STYLE_DIM: usize = 256;
-
-        let mut vectors = Vec::with_capacity(TOKEN_LIMIT);
-        for i in 0..TOKEN_LIMIT {
-            let start = i * STYLE_DIM;
-            let end = start + STYLE_DIM;
-            if end <= floats.len() {
-                vectors.push(floats[start..end].to_vec());
+    fn synthesize_input_with(
+        &mut self,
+        input: &SynthesisInput,
+        voice: Option<&str>,
+        options: &TextOptions,
+        diagnostics: &mut SynthesisDiagnostics,
+    ) -> Result<Vec<f32>> {
+        let tokens = match input {
+            SynthesisInput::Text(text) => return self.synthesize_with_speed(text, voice, 1.0, options, diagnostics),
+            SynthesisInput::Ssml(ssml) => {
+                let default_voice = voice.unwrap_or(&self.default_voice).to_string();
+                let resolve = |name: &str| crate::voice_alias::resolve_installed(name).ok();
+                let items = crate::ssml::compile(ssml, &default_voice, &self.vocab, &resolve)?;
+                return self.synthesize_ssml(&items, &default_voice, options, diagnostics);
             }
+            SynthesisInput::Phonemes(phonemes) => {
+                info!("📝 原始音素输入: {}", phonemes.chars().take(50).collect::<String>());
+                self.vocab.tokenize_strict(phonemes)?
+            }
+            SynthesisInput::Tokens(tokens) => {
+                info!("🔢 原始 token 输入: {} 个", tokens.len());
+                self.vocab.validate_tokens(tokens)?;
+                tokens.clone()
+            }
+        };
+
+        if tokens.len() > MAX_TOKENS {
+            anyhow::bail!("原始输入过长 ({} tokens > {} 限制), 请分段提交", tokens.len(), MAX_TOKENS);
         }
 
-        Ok(vectors)
+        let voice_name = voice.unwrap_or(&self.default_voice).to_string();
+        self.synthesize_tokens(&tokens, &voice_name, 1.0, diagnostics)
     }
 
-    /// 文本转语音 - ONNX 推理
-    pub fn synthesize(&mut self, text: &str, voice: Option<&str>) -> Result<Vec<f32>> {
-        let voice_name = voice.unwrap_or(&self.default_voice);
-        // 安全截断：使用字符迭代器
-        let text_preview: String = text.chars().take(50).collect();
-        info!("🎵 合成文本: \"{}\" (声音: {})", text_preview, voice_name);
+    /// 合成编译后的 SSML: 逐段合成, 停顿插入静音
+    ///
+    /// 同一段内的文本和 `<phoneme>` 音素拼接后一次推理, 超出 token 上限时按片段分别合成。
+    fn synthesize_ssml(
+        &mut self,
+        items: &[SsmlItem],
+        default_voice: &str,
+        options: &TextOptions,
+        diagnostics: &mut SynthesisDiagnostics,
+    ) -> Result<Vec<f32>> {
+        info!("🧩 SSML: {} 段", items.len());
+        let mut combined_audio = Vec::new();
 
-        // 1. 检查文本长度，如果太长则分段处理
-        const MAX_TOKENS: usize = 400; // 安全限制
+        for item in items {
+            let chunk = match item {
+                SsmlItem::Break(millis) => {
+                    let samples = (*millis as u64 * self.sample_rate as u64 / 1000) as usize;
+                    combined_audio.extend(std::iter::repeat_n(0.0f32, samples));
+                    continue;
+                }
+                SsmlItem::Speech(chunk) => chunk,
+            };
+
+            // <voice name="..."> 也可以使用别名
+            let voice_name = match &chunk.voice {
+                Some(name) => voice_alias::aliases().resolve(name, &self.voices.names())?,
+                None => default_voice.to_string(),
+            };
+            if !self.voices.contains(&voice_name) {
+                anyhow::bail!("声音 '{}' 不存在", voice_name);
+            }
 
-        // 先进行音素化以获取实际 token 数
-        let phonemes = self.simple_phonemize(text);
-        // 安全截断：使用字符迭代器
-        let phonemes_preview: String = phonemes.chars().take(50).collect();
-        info!("📝 音素: {}", phonemes_preview);
+            let mut pieces = Vec::with_capacity(chunk.parts.len());
+            let mut fallback_used = false;
+            let mut unresolved = Vec::new();
+            for part in &chunk.parts {
+                match part {
+                    SsmlPart::Text(text) => {
+                        let phonemized = phonemize(text, &voice_name, options)?;
+                        fallback_used |= phonemized.fallback_used;
+                        unresolved.extend(phonemized.unresolved);
+                        pieces.push(phonemized.phonemes);
+                    }
+                    SsmlPart::Phonemes(phonemes) => pieces.push(phonemes.clone()),
+                }
+            }
+            let phonemes = pieces.join(" ");
+            let tokens = self.vocab.tokenize(&phonemes);
+            info!("🔢 SSML 段: {} tokens (声音: {}, 语速: {})", tokens.len(), voice_name, chunk.rate);
+
+            if tokens.len() <= MAX_TOKENS {
+                diagnostics.fallback_used |= fallback_used;
+                diagnostics.record_dropped(&phonemes, &self.vocab);
+                diagnostics.record_unresolved(&unresolved);
+                let audio = self.synthesize_tokens(&tokens, &voice_name, chunk.rate, diagnostics)?;
+                combined_audio.extend_from_slice(&audio);
+                continue;
+            }
 
-        let tokens = crate::vocab::tokenize(&phonemes);
-        info!("🔢 Tokens: {} 个", tokens.len());
+            for part in &chunk.parts {
+                let audio = match part {
+                    SsmlPart::Text(text) => {
+                        self.synthesize_with_speed(text, Some(&voice_name), chunk.rate, options, diagnostics)?
+                    }
+                    SsmlPart::Phonemes(phonemes) => {
+                        let tokens = self.vocab.tokenize_strict(phonemes)?;
+                        if tokens.len() > MAX_TOKENS {
+                            anyhow::bail!("<phoneme> 过长 ({} tokens > {} 限制)", tokens.len(), MAX_TOKENS);
+                        }
+                        self.synthesize_tokens(&tokens, &voice_name, chunk.rate, diagnostics)?
+                    }
+                };
+                combined_audio.extend_from_slice(&audio);
+            }
+        }
 
-        if tokens.is_empty() {
+        if combined_audio.is_empty() {
             return Ok(vec![0.0; 24000]); // 1秒静音
         }
+        Ok(combined_audio)
+    }
 
-        // 如果 tokens 数超过限制，按句子分割文本重新合成
-        if tokens.len() > MAX_TOKENS {
-            info!("⚠️ 文本过长 ({} tokens > {} 限制)，自动分段处理", tokens.len(), MAX_TOKENS);
-            return self.synthesize_long_text(text, voice);
+    /// token → 音频 (获取 style vector + ONNX 推理)
+    fn synthesize_tokens(
+        &mut self,
+        tokens: &[i64],
+        voice_name: &str,
+        speed: f32,
+        diagnostics: &mut SynthesisDiagnostics,
+    ) -> Result<Vec<f32>> {
+        if tokens.is_empty() {
+            return Ok(vec![0.0; 24000]); // 1秒静音
         }
+        diagnostics.chunks += 1;
 
-        // 3. 获取指定声音的 style vector
-        let style_vectors = self.voices.get(voice_name)
-            .ok_or_else(|| anyhow::anyhow!("声音 '{}' 不存在", voice_name))?;
+        // 获取指定声音的 style vector
+        let style_table = self.voices.get(voice_name).map_err(|e| match e {
+            VoiceError::NotFound(_) => anyhow::anyhow!("声音 '{}' 不存在", voice_name),
+            e => anyhow::anyhow!("{}", e),
+        })?;
 
-        let style_vector = if !style_vectors.is_empty() {
-            style_vectors[0].clone()  // 使用第一个 token 的 style
-        } else {
-            vec![0.0f32; 256]  // 降级: 零向量
-        };
+        // 加载时已校验为 510 行
+        let style_vector = style_table.row(0).to_vec();  // 使用第一个 token 的 style
 
         info!("🎨 使用声音 '{}' 的 style vector (dims={})", voice_name, style_vector.len());
 
-        // 4. ONNX 推理
-        let audio = self.run_inference(&tokens, &style_vector)?;
+        // ONNX 推理
+        let audio = self.run_inference(tokens, &style_vector, speed)?;
 
         info!("✅ ONNX 推理完成 ({} 样本)", audio.len());
         Ok(audio)
     }
 
     /// 分段合成长文本
-    fn synthesize_long_text(&mut self, text: &str, voice: Option<&str>) -> Result<Vec<f32>> {
-        // 按句子分割（支持 .!? 和中文标点）
-        let sentences: Vec<&str> = text
-            .split(|c: char| c == '.' || c == '!' || c == '?' || c == '。' || c == '!' || c == '?')
-            .filter(|s| !s.trim().is_empty())
-            .collect();
+    fn synthesize_long_text(
+        &mut self,
+        text: &str,
+        voice: Option<&str>,
+        speed: f32,
+        options: &TextOptions,
+        diagnostics: &mut SynthesisDiagnostics,
+    ) -> Result<Vec<f32>> {
+        let sentences = split_sentences(text);
 
         info!("✂️ 文本分割成 {} 个句子", sentences.len());
 
@@ -203,7 +428,7 @@ impl TTSEngine {
             info!("🎵 合成第 {}/{} 段: \"{}\"", i + 1, sentences.len(), sentence_preview);
 
             // 递归调用 synthesize (会再次检查长度，如果单句仍太长会继续分割)
-            match self.synthesize(sentence_text, voice) {
+            match self.synthesize_with_speed(sentence_text, voice, speed, options, diagnostics) {
                 Ok(audio) => {
                     combined_audio.extend_from_slice(&audio);
                     // 句子之间添加短暂静音
@@ -211,8 +436,11 @@ impl TTSEngine {
                         combined_audio.extend_from_slice(&silence);
                     }
                 }
+                // espeak-ng 超时/失败不能静默跳过
+                Err(e) if e.downcast_ref::<EspeakError>().is_some() => return Err(e),
                 Err(e) => {
-                    info!("⚠️ 第 {} 段合成失败: {}, 跳过", i + 1, e);
+                    warn!("⚠️ 第 {} 段合成失败: {}, 跳过", i + 1, e);
+                    diagnostics.record_skipped(i, sentence_text, &e);
                     continue;
                 }
             }
@@ -226,62 +454,8 @@ impl TTSEngine {
         Ok(combined_audio)
     }
 
-    /// espeak-ng 音素化
-    fn simple_phonemize(&self, text: &str) -> String {
-        match self.phonemize_with_espeak(text) {
-            Ok(phonemes) => {
-                info!("✅ espeak-ng 音素化成功");
-                phonemes
-            }
-            Err(e) => {
-                info!("⚠️ espeak-ng 失败: {}, 使用降级方案", e);
-                // 降级: 简单处理
-                text.chars()
-                    .filter(|c| c.is_ascii_alphanumeric() || c.is_whitespace())
-                    .collect::<String>()
-                    .to_lowercase()
-            }
-        }
-    }
-
-    /// 使用 espeak-ng 进行音素化
-    fn phonemize_with_espeak(&self, text: &str) -> Result<String> {
-        info!("🔊 调用 espeak-ng: {}", text);
-        let output = Command::new("espeak-ng")
-            .args(&["-v", "en-us", "-q", "--ipa", text])
-            .output()
-            .context("espeak-ng 未安装或无法执行")?;
-
-        info!("📋 espeak-ng 返回状态: {}", output.status);
-
-        if !output.status.success() {
-            return Err(anyhow::anyhow!("espeak-ng 执行失败"));
-        }
-
-        let mut phonemes = String::from_utf8(output.stdout)?
-            .trim()
-            .to_string();
-
-        // Kokoro-specific 替换
-        phonemes = phonemes
-            .replace("kəkˈoːɹoʊ", "kˈoʊkəɹoʊ")
-            .replace("kəkˈɔːɹəʊ", "kˈəʊkəɹəʊ")
-            .replace("ʲ", "j")
-            .replace("r", "ɹ")
-            .replace("x", "k")
-            .replace("ɬ", "l");
-
-        // 过滤词汇表外的字符
-        phonemes = phonemes
-            .chars()
-            .filter(|&c| crate::vocab::VOCAB.contains_key(&c))
-            .collect();
-
-        Ok(phonemes)
-    }
-
     /// ONNX 推理 (真实数据)
-    fn run_inference(&mut self, tokens: &[i64], style_vector: &[f32]) -> Result<Vec<f32>> {
+    fn run_inference(&mut self, tokens: &[i64], style_vector: &[f32], speed: f32) -> Result<Vec<f32>> {
         use ort::value::Tensor;
 
         // 添加 padding tokens (0 = pad token '$')
@@ -304,8 +478,8 @@ impl TTSEngine {
         let style_flat: Vec<f32> = style_2d.into_iter().flatten().collect();
         let style_tensor = Tensor::from_array((shape_style, style_flat))?;
 
-        // speed: 默认速度 1.0
-        let speed_tensor = Tensor::from_array(([1], vec![1.0f32]))?;
+        // speed: 语速 (1.0 = 正常)
+        let speed_tensor = Tensor::from_array(([1], vec![speed]))?;
 
         info!("🔧 ONNX 输入准备完成");
 
@@ -362,3 +536,250 @@ impl TTSEngine {
         self.sample_rate
     }
 }
+
+/// 长文本分句 (支持 .!? 和中文标点), 句末标点 (包括连续的 ?! 等) 保留在句子上, 去掉空句
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    const TERMINATORS: [char; 8] = ['.', '!', '?', '。', '！', '？', '；', '\n'];
+
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if !TERMINATORS.contains(&c) {
+            continue;
+        }
+        while let Some(&(_, next)) = chars.peek() {
+            if !TERMINATORS.contains(&next) {
+                break;
+            }
+            chars.next();
+        }
+        let end = chars.peek().map_or(text.len(), |&(i, _)| i);
+        sentences.push(&text[start..end]);
+        start = end;
+    }
+    sentences.push(&text[start..]);
+
+    sentences
+        .into_iter()
+        .filter(|s| s.chars().any(|c| !c.is_whitespace() && !TERMINATORS.contains(&c)))
+        .collect()
+}
+
+/// 音素化结果
+#[derive(Debug, Clone)]
+pub struct Phonemized {
+    pub phonemes: String,
+    /// espeak-ng 不可用, 使用了 ASCII 降级方案
+    pub fallback_used: bool,
+    /// 读音词典中没有、被丢弃的原文字符
+    pub unresolved: Vec<char>,
+}
+
+/// 音素化: 按语言选择前端 (中文 → zh_g2p, 日文 → ja_g2p, 其他 → espeak-ng)
+pub fn phonemize(text: &str, voice_name: &str, options: &TextOptions) -> Result<Phonemized, EspeakError> {
+    // G2P 前端的回调不能返回错误: 记下第一个 espeak-ng 错误, 结束后再返回
+    let mut espeak_error = None;
+    let mut fallback_used = false;
+    let mut fallback = |run: &str| {
+        espeak_or_fallback(run, &mut fallback_used).unwrap_or_else(|e| {
+            espeak_error.get_or_insert(e);
+            String::new()
+        })
+    };
+
+    let mut unresolved = Vec::new();
+    let language = Language::detect(text, voice_name);
+    let text = &verbalize::verbalize(text, language, &options.symbols);
+    let phonemes = match language {
+        Language::Mandarin => {
+            info!("🀄 使用普通话 G2P");
+            zh_g2p::phonemize(text, &mut fallback, &mut unresolved)
+        }
+        Language::Japanese => {
+            info!("🗾 使用日语 G2P");
+            ja_g2p::phonemize(text, &mut fallback, &mut unresolved)
+        }
+        Language::English => phonemize_preserving_punctuation(text, |run| {
+            homograph::phonemize_run(run, &options.senses, &mut fallback)
+        }),
+    };
+
+    match espeak_error {
+        Some(e) => Err(e),
+        None => Ok(Phonemized { phonemes, fallback_used, unresolved }),
+    }
+}
+
+/// 文本 → token, 并把被丢弃/未识别的字符记入诊断
+///
+/// 超过 `MAX_TOKENS` 时不记录: 长文本会按句子重新音素化, 由各句自己记录。
+fn text_tokens(
+    text: &str,
+    voice_name: &str,
+    options: &TextOptions,
+    vocab: &Vocab,
+    diagnostics: &mut SynthesisDiagnostics,
+) -> Result<Vec<i64>, EspeakError> {
+    let Phonemized { phonemes, fallback_used, unresolved } = phonemize(text, voice_name, options)?;
+    diagnostics.fallback_used |= fallback_used;
+    // 安全截断：使用字符迭代器
+    let phonemes_preview: String = phonemes.chars().take(50).collect();
+    info!("📝 音素: {}", phonemes_preview);
+
+    let tokens = vocab.tokenize(&phonemes);
+    info!("🔢 Tokens: {} 个", tokens.len());
+
+    if tokens.len() <= MAX_TOKENS {
+        diagnostics.record_dropped(&phonemes, vocab);
+        diagnostics.record_unresolved(&unresolved);
+    }
+    Ok(tokens)
+}
+
+/// 标点 → Kokoro 词汇表中的标点 (影响语调, 需要保留)
+///
+/// 西班牙语的 ¡ ¿ 不在词汇表中, 映射为空串: 只切分词串, 不输出 (语调由句末的 ! ? 决定)
+fn punctuation_symbol(c: char) -> Option<&'static str> {
+    match c {
+        '¡' | '¿' => Some(""),
+        '"' => Some("\""),
+        _ => map_cjk_punctuation(c),
+    }
+}
+
+/// 按标点切分文本, 词串交给 `phonemize_run` 音素化, 再把原标点插回
+///
+/// espeak-ng --ipa 会丢掉大部分标点, 而 Kokoro 依赖 `,` `?` 等决定停顿和语调。
+/// 夹在字母数字之间的标点 (3.14、10:30、e.g) 属于词的一部分, 不切分。
+pub fn phonemize_preserving_punctuation<F>(text: &str, mut phonemize_run: F) -> String
+where
+    F: FnMut(&str) -> String,
+{
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut run = String::new();
+    // 上一个输出是开引号/括号: 下一个词直接贴上
+    let mut after_opening = false;
+
+    let mut flush = |run: &mut String, out: &mut String, after_opening: &mut bool| {
+        if !run.trim().is_empty() {
+            let phonemes = phonemize_run(run.trim());
+            let phonemes = phonemes.trim();
+            if !phonemes.is_empty() {
+                if !out.is_empty() && !out.ends_with(' ') && !*after_opening {
+                    out.push(' ');
+                }
+                out.push_str(phonemes);
+                *after_opening = false;
+            }
+        }
+        run.clear();
+    };
+
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let inside_word = prev.is_some_and(char::is_alphanumeric)
+            && chars.get(i + 1).is_some_and(|n| n.is_alphanumeric());
+        match punctuation_symbol(c) {
+            Some("") if !inside_word => flush(&mut run, &mut out, &mut after_opening),
+            Some(symbol) if !inside_word => {
+                flush(&mut run, &mut out, &mut after_opening);
+                // 开引号/括号贴后一个词, 其他标点贴前一个词, 破折号两边留空
+                let opening = matches!(symbol, "“" | "(")
+                    || (symbol == "\"" && prev.is_none_or(char::is_whitespace));
+                let spaced = symbol == "—";
+                if (opening || spaced) && !out.is_empty() && !out.ends_with(' ') && !after_opening {
+                    out.push(' ');
+                }
+                if !opening && !spaced && out.ends_with(' ') {
+                    out.pop();
+                }
+                out.push_str(symbol);
+                if !opening {
+                    out.push(' ');
+                }
+                after_opening = opening;
+            }
+            _ => run.push(c),
+        }
+    }
+    flush(&mut run, &mut out, &mut after_opening);
+
+    out.trim().to_string()
+}
+
+/// espeak-ng 音素化; 只有 espeak-ng 未安装时降级为 ASCII (记入 `fallback_used`), 其他失败返回错误
+fn espeak_or_fallback(text: &str, fallback_used: &mut bool) -> Result<String, EspeakError> {
+    match espeak::phonemize(text) {
+        Ok(phonemes) => Ok(phonemes),
+        Err(EspeakError::NotInstalled) => {
+            warn!("⚠️ espeak-ng 未安装, 使用 ASCII 降级方案");
+            *fallback_used = true;
+            Ok(text
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || c.is_whitespace())
+                .collect::<String>()
+                .to_lowercase())
+        }
+        Err(e) => {
+            error!("❌ {}", e);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vocab::EMBEDDED;
+
+    #[test]
+    fn test_punctuation_survives() {
+        let phonemes = phonemize_preserving_punctuation("Well, are you there?", |run| run.to_lowercase());
+        assert_eq!(phonemes, "well, are you there?");
+
+        let tokens = EMBEDDED.tokenize(&phonemes);
+        assert!(tokens.contains(&EMBEDDED.get(',').unwrap()));
+        assert_eq!(tokens.last(), Some(&EMBEDDED.get('?').unwrap()));
+    }
+
+    #[test]
+    fn test_punctuation_inside_words() {
+        let mut runs = Vec::new();
+        let phonemes = phonemize_preserving_punctuation("Pi is 3.14… at 10:30!", |run| {
+            runs.push(run.to_string());
+            run.to_string()
+        });
+        assert_eq!(runs, vec!["Pi is 3.14", "at 10:30"]);
+        assert_eq!(phonemes, "Pi is 3.14… at 10:30!");
+
+        let phonemes = phonemize_preserving_punctuation("¿Qué? (yes) — no.", |run| run.to_string());
+        assert_eq!(phonemes, "Qué? (yes) — no.");
+        let phonemes = phonemize_preserving_punctuation("Sí. ¡Hola!", |run| run.to_string());
+        assert_eq!(phonemes, "Sí. Hola!");
+        assert!(!EMBEDDED.contains('¡') && !EMBEDDED.contains('¿'));
+    }
+
+    #[test]
+    fn test_all_chars_dropped_still_diagnosed() {
+        let vocab = Vocab::from_tokenizer_json(r#"{"model": {"vocab": {"$": 0, "q": 1}}}"#).unwrap();
+        let mut diagnostics = SynthesisDiagnostics::default();
+        let tokens = text_tokens("你好䶮", "zf_xiaobei", &TextOptions::default(), &vocab, &mut diagnostics).unwrap();
+
+        assert!(tokens.is_empty());
+        assert!(!diagnostics.dropped.is_empty());
+        assert!(diagnostics.dropped.iter().all(|d| d.chunk == 0));
+        let unresolved: Vec<char> = diagnostics.unresolved.iter().map(|u| u.char).collect();
+        assert_eq!(unresolved, vec!['䶮']);
+    }
+
+    #[test]
+    fn test_split_sentences_keeps_terminators() {
+        assert_eq!(
+            split_sentences("Hi there. Are you ok?! 好。\n\n"),
+            vec!["Hi there.", " Are you ok?!", " 好。\n\n"]
+        );
+        assert_eq!(split_sentences("no terminator"), vec!["no terminator"]);
+    }
+}
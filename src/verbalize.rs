@@ -0,0 +1,410 @@
+//! 符号文字化: emoji、箭头、©/%/± 等符号和简单算式 → 对应语言的读法
+//!
+//! 在音素化之前运行。读法表位于 data/symbols/<语言>.txt (CLDR 短名, 编译时嵌入),
+//! 每类符号可以选择朗读 (read)、跳过 (skip) 或替换成简短描述 (describe, 如 "emoji")。
+//!
+//! 规则:
+//! - 最长匹配: `°C` 优先于 `°`, `->` 优先于 `-`, ZWJ 组合 emoji 优先于单个 emoji
+//! - `operator` 类符号 (+ - * ^ < >) 只在两个操作数之间朗读, `-` 还要求两侧有空格 (5 - 3)
+//! - 带分数加连接词: 1½ → 1 and one half
+//! - 表外的 emoji 整个序列 (肤色、ZWJ、国旗) 按描述读出或跳过
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::language::Language;
+
+const EN_TABLE: &str = include_str!("../data/symbols/en.txt");
+const ZH_TABLE: &str = include_str!("../data/symbols/zh.txt");
+const JA_TABLE: &str = include_str!("../data/symbols/ja.txt");
+
+/// 零宽连接符 (ZWJ emoji 序列)
+const ZWJ: char = '\u{200D}';
+
+/// 符号类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Emoji,
+    Arrow,
+    Symbol,
+    Math,
+}
+
+impl Category {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "emoji" => Some(Category::Emoji),
+            "arrow" => Some(Category::Arrow),
+            "symbol" => Some(Category::Symbol),
+            "math" => Some(Category::Math),
+            _ => None,
+        }
+    }
+}
+
+/// 处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolMode {
+    /// 读出名称 (😂 → face with tears of joy)
+    #[default]
+    Read,
+    /// 删除
+    Skip,
+    /// 替换成类别描述 (😂 → emoji)
+    Describe,
+}
+
+/// 每类符号的处理方式, 默认全部朗读
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SymbolOptions {
+    pub emoji: SymbolMode,
+    pub arrow: SymbolMode,
+    pub symbol: SymbolMode,
+    pub math: SymbolMode,
+}
+
+impl SymbolOptions {
+    fn mode(&self, category: Category) -> SymbolMode {
+        match category {
+            Category::Emoji => self.emoji,
+            Category::Arrow => self.arrow,
+            Category::Symbol => self.symbol,
+            Category::Math => self.math,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 稳定字符串形式 (用于缓存键): "emoji=skip,arrow=read,symbol=read,math=read"
+    pub fn cache_key(&self) -> String {
+        let mode = |m: SymbolMode| match m {
+            SymbolMode::Read => "read",
+            SymbolMode::Skip => "skip",
+            SymbolMode::Describe => "describe",
+        };
+        format!(
+            "emoji={},arrow={},symbol={},math={}",
+            mode(self.emoji),
+            mode(self.arrow),
+            mode(self.symbol),
+            mode(self.math)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    category: Category,
+    /// 只在操作数之间朗读
+    operator: bool,
+    reading: String,
+}
+
+/// 单个语言的读法表
+#[derive(Debug, Default)]
+struct Table {
+    entries: HashMap<String, Entry>,
+    max_key_chars: usize,
+    descriptions: HashMap<Category, String>,
+    /// 带分数的连接词
+    and: String,
+}
+
+impl Table {
+    fn parse(src: &str) -> Self {
+        let mut table = Table::default();
+        for line in src.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(directive) = line.strip_prefix('@') {
+                let Some((key, value)) = directive.split_once(' ') else {
+                    warn!("⚠️ 无效符号表指令: {}", line);
+                    continue;
+                };
+                match (key, Category::parse(key)) {
+                    ("and", _) => table.and = value.trim().to_string(),
+                    (_, Some(category)) => {
+                        table.descriptions.insert(category, value.trim().to_string());
+                    }
+                    _ => warn!("⚠️ 未知符号表指令: {}", line),
+                }
+                continue;
+            }
+
+            let mut fields = line.splitn(3, ' ');
+            let (Some(symbol), Some(category), Some(reading)) = (fields.next(), fields.next(), fields.next()) else {
+                warn!("⚠️ 无效符号条目: {}", line);
+                continue;
+            };
+            let (category, operator) = match category {
+                "operator" => (Category::Math, true),
+                other => match Category::parse(other) {
+                    Some(category) => (category, false),
+                    None => {
+                        warn!("⚠️ 未知符号类别: {}", line);
+                        continue;
+                    }
+                },
+            };
+            table.max_key_chars = table.max_key_chars.max(symbol.chars().count());
+            table.entries.insert(
+                symbol.to_string(),
+                Entry { category, operator, reading: reading.trim().to_string() },
+            );
+        }
+        table
+    }
+
+    /// 从 `chars[i]` 开始的最长匹配, 返回条目和匹配长度
+    fn longest_match(&self, chars: &[char], i: usize) -> Option<(&Entry, usize)> {
+        let max = self.max_key_chars.min(chars.len() - i);
+        (1..=max).rev().find_map(|len| {
+            let key: String = chars[i..i + len].iter().collect();
+            self.entries.get(&key).map(|entry| (entry, len))
+        })
+    }
+
+    fn description(&self, category: Category) -> &str {
+        self.descriptions.get(&category).map(String::as_str).unwrap_or("")
+    }
+}
+
+lazy_static! {
+    static ref EN: Table = Table::parse(EN_TABLE);
+    static ref ZH: Table = Table::parse(ZH_TABLE);
+    static ref JA: Table = Table::parse(JA_TABLE);
+}
+
+fn table(language: Language) -> &'static Table {
+    match language {
+        Language::English => &EN,
+        Language::Mandarin => &ZH,
+        Language::Japanese => &JA,
+    }
+}
+
+/// 是否为 emoji 基础字符 (不含表内已收录的普通符号)
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1F2FF   // 麻将/扑克/带圈字母 + 区域指示符 (国旗)
+        | 0x1F300..=0x1FAFF // 各类图形 emoji
+        | 0x2600..=0x27BF   // 杂项符号、装饰符号
+    )
+}
+
+fn is_skin_tone(c: char) -> bool {
+    ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// 带分数中的分数字符 (½ ⅓ ¼ ...)
+fn is_vulgar_fraction(c: char) -> bool {
+    matches!(c, '¼'..='¾' | '\u{2150}'..='\u{215E}')
+}
+
+/// emoji 序列结束位置: 肤色修饰、ZWJ 连接的后续 emoji、国旗的第二个区域指示符
+fn emoji_sequence_end(chars: &[char], start: usize) -> usize {
+    let mut end = start + 1;
+    if is_regional_indicator(chars[start]) && chars.get(end).is_some_and(|&c| is_regional_indicator(c)) {
+        return end + 1;
+    }
+    loop {
+        while chars.get(end).is_some_and(|&c| is_skin_tone(c)) {
+            end += 1;
+        }
+        if chars.get(end) == Some(&ZWJ) && chars.get(end + 1).is_some_and(|&c| is_emoji(c)) {
+            end += 2;
+            continue;
+        }
+        return end;
+    }
+}
+
+/// `operator` 类符号是否处在两个操作数之间 (跳过空格)
+fn between_operands(chars: &[char], start: usize, end: usize) -> bool {
+    let before = chars[..start].iter().rev().position(|c| !c.is_whitespace());
+    let after = chars[end..].iter().position(|c| !c.is_whitespace());
+    let (Some(before), Some(after)) = (before, after) else {
+        return false;
+    };
+    let prev = chars[start - 1 - before];
+    let next = chars[end + after];
+    if !prev.is_alphanumeric() || !next.is_alphanumeric() {
+        return false;
+    }
+    // 连字符 (e-mail、2020-2021) 不读作减号
+    if chars[start] == '-' {
+        return before > 0 && after > 0 && prev.is_ascii_digit() && next.is_ascii_digit();
+    }
+    true
+}
+
+/// 写入一个读法: 空格分词的语言在前后补空格
+fn push_word(out: &mut String, word: &str, next: Option<char>, spaced: bool) {
+    if word.is_empty() {
+        return;
+    }
+    if spaced && out.chars().last().is_some_and(|c| !c.is_whitespace()) {
+        out.push(' ');
+    }
+    out.push_str(word);
+    if spaced && next.is_some_and(|c| !c.is_whitespace() && !matches!(c, '.' | ',' | '!' | '?' | ';' | ':' | ')' | ']' | '"' | '\'')) {
+        out.push(' ');
+    }
+}
+
+/// 把符号替换成 `language` 的读法
+pub fn verbalize(text: &str, language: Language, options: &SymbolOptions) -> String {
+    let table = table(language);
+    let spaced = language == Language::English;
+    // 变体选择符和组合键帽不影响读法 (❤️ = ❤, 1️⃣ = 1)
+    let chars: Vec<char> = text
+        .chars()
+        .filter(|c| !matches!(c, '\u{FE0E}' | '\u{FE0F}' | '\u{20E3}'))
+        .collect();
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let matched = table.longest_match(&chars, i).filter(|&(entry, len)| !entry.operator || between_operands(&chars, i, i + len));
+
+        let (category, reading, end) = match matched {
+            Some((entry, len)) => {
+                let mut end = i + len;
+                let mut reading = Some(entry.reading.as_str());
+                if entry.category == Category::Emoji {
+                    while chars.get(end).is_some_and(|&c| is_skin_tone(c)) {
+                        end += 1;
+                    }
+                    // 表中只有序列开头 (如 ❤ + ZWJ + 🩹): 整个序列按未知 emoji 处理
+                    let sequence_end = emoji_sequence_end(&chars, i);
+                    if sequence_end > end {
+                        end = sequence_end;
+                        reading = None;
+                    }
+                }
+                (entry.category, reading, end)
+            }
+            None if is_emoji(c) => (Category::Emoji, None, emoji_sequence_end(&chars, i)),
+            None => {
+                out.push(c);
+                i += 1;
+                continue;
+            }
+        };
+
+        let next = chars.get(end).copied();
+        match (options.mode(category), reading) {
+            (SymbolMode::Read, Some(reading)) => {
+                let word = match i > 0 && chars[i - 1].is_ascii_digit() && is_vulgar_fraction(c) {
+                    true if spaced => format!("{} {}", table.and, reading),
+                    true => format!("{}{}", table.and, reading),
+                    false => reading.to_string(),
+                };
+                push_word(&mut out, &word, next, spaced);
+            }
+            (SymbolMode::Skip, _) => {
+                // 夹在两个词之间时保留分隔
+                if spaced && out.chars().last().is_some_and(|c| !c.is_whitespace()) && next.is_some_and(char::is_alphanumeric) {
+                    out.push(' ');
+                }
+            }
+            _ => push_word(&mut out, table.description(category), next, spaced),
+        }
+        i = end;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn en(text: &str) -> String {
+        verbalize(text, Language::English, &SymbolOptions::default())
+    }
+
+    #[test]
+    fn test_tables_load() {
+        for (table, size) in [(&*EN, 150), (&*ZH, 80), (&*JA, 70)] {
+            assert!(table.entries.len() >= size);
+            assert!(!table.and.is_empty());
+            for category in [Category::Emoji, Category::Arrow, Category::Symbol, Category::Math] {
+                assert!(!table.description(category).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_symbols_and_math() {
+        assert_eq!(en("50% off"), "50 percent off");
+        assert_eq!(en("©2024 Acme™"), "copyright 2024 Acme trade mark");
+        assert_eq!(en("It's 25°C outside"), "It's 25 degrees Celsius outside");
+        assert_eq!(en("3 × 4 = 12"), "3 times 4 equals 12");
+        assert_eq!(en("±5"), "plus or minus 5");
+        assert_eq!(en("x² + 1"), "x squared plus 1");
+        assert_eq!(en("add 1½ cups"), "add 1 and one half cups");
+        assert_eq!(en("A → B"), "A rightwards arrow B");
+        assert_eq!(en("a -> b"), "a rightwards arrow b");
+    }
+
+    #[test]
+    fn test_operators_need_operands() {
+        assert_eq!(en("5 - 3"), "5 minus 3");
+        assert_eq!(en("2020-2021 e-mail"), "2020-2021 e-mail");
+        assert_eq!(en("2^10"), "2 to the power of 10");
+        assert_eq!(en("*really*"), "*really*");
+        assert_eq!(en("C++"), "C++");
+        assert_eq!(en("<b>"), "<b>");
+    }
+
+    #[test]
+    fn test_emoji() {
+        assert_eq!(en("Great job 👍!"), "Great job thumbs up!");
+        // 变体选择符、肤色
+        assert_eq!(en("I ❤️ it"), "I red heart it");
+        assert_eq!(en("👍🏽👍"), "thumbs up thumbs up");
+        // ZWJ 序列和国旗
+        assert_eq!(en("👩‍💻"), "woman technologist");
+        assert_eq!(en("🇯🇵"), "flag: Japan");
+        // 表外 emoji 整个序列读成描述
+        assert_eq!(en("🧑‍🚀 launch"), "emoji launch");
+        assert_eq!(en("❤‍🩹"), "emoji");
+    }
+
+    #[test]
+    fn test_modes() {
+        let skip = SymbolOptions { emoji: SymbolMode::Skip, ..Default::default() };
+        assert_eq!(verbalize("done ✅ ok", Language::English, &skip), "done  ok");
+        assert_eq!(verbalize("a😀b", Language::English, &skip), "a b");
+
+        let describe = SymbolOptions { emoji: SymbolMode::Describe, arrow: SymbolMode::Describe, ..Default::default() };
+        assert_eq!(verbalize("😂 → 50%", Language::English, &describe), "emoji arrow 50 percent");
+        assert_eq!(describe.cache_key(), "emoji=describe,arrow=describe,symbol=read,math=read");
+        assert!(SymbolOptions::default().is_default());
+    }
+
+    #[test]
+    fn test_cjk() {
+        let options = SymbolOptions::default();
+        assert_eq!(verbalize("太棒了👍", Language::Mandarin, &options), "太棒了拇指向上");
+        assert_eq!(verbalize("3×4=12", Language::Mandarin, &options), "3乘4等于12");
+        assert_eq!(verbalize("1½杯", Language::Mandarin, &options), "1又二分之一杯");
+        assert_eq!(verbalize("50%", Language::Mandarin, &options), "50%");
+        assert_eq!(verbalize("ありがとう🙏", Language::Japanese, &options), "ありがとうてをあわせる");
+        assert_eq!(verbalize("30%", Language::Japanese, &options), "30パーセント");
+    }
+}
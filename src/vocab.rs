@@ -1,36 +1,263 @@
-/// 词汇表模块 - Kokoro TTS
-///
-/// 字符 → Token ID 映射
+//! 词汇表模块 - Kokoro TTS
+//!
+//! 字符 → Token ID 映射
+//!
+//! 词汇表从模型目录下的 `tokenizer.json` 加载 (每个模型可以有自己的词汇表),
+//! 并与内置的 Kokoro v1.0 词汇表比对; 找不到文件时使用内置表。
 
+use anyhow::{Context, Result};
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use thiserror::Error;
+use tracing::{info, warn};
 
-fn build_vocab() -> HashMap<char, usize> {
-    let pad = "$";
-    let punctuation = r#";:,.!?¡¿—…"«»"" "#;
-    let letters = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
-    let letters_ipa = "ɑɐɒæɓʙβɔɕçɗɖðʤəɘɚɛɜɝɞɟʄɡɠɢʛɦɧħɥʜɨɪʝɭɬɫɮʟɱɯɰŋɳɲɴøɵɸθœɶʘɹɺɾɻʀʁɽʂʃʈʧʉʊʋⱱʌɣɤʍχʎʏʑʐʒʔʡʕʢǀǁǂǃˈˌːˑʼʴʰʱʲʷˠˤ˞↓↑→↗↘'̩'ᵻ";
+/// 内置词汇表 (Kokoro v1.0 `tokenizer.json` 的 `model.vocab`)
+const KOKORO_V1_VOCAB: &[(char, i64)] = &[
+    ('$', 0), (';', 1), (':', 2), (',', 3), ('.', 4), ('!', 5), ('?', 6), ('—', 9),
+    ('…', 10), ('"', 11), ('(', 12), (')', 13), ('“', 14), ('”', 15), (' ', 16), ('\u{303}', 17),
+    ('ʣ', 18), ('ʥ', 19), ('ʦ', 20), ('ʨ', 21), ('ᵝ', 22), ('ꭧ', 23), ('A', 24), ('I', 25),
+    ('O', 31), ('Q', 33), ('S', 35), ('T', 36), ('W', 39), ('Y', 41), ('ᵊ', 42), ('a', 43),
+    ('b', 44), ('c', 45), ('d', 46), ('e', 47), ('f', 48), ('h', 50), ('i', 51), ('j', 52),
+    ('k', 53), ('l', 54), ('m', 55), ('n', 56), ('o', 57), ('p', 58), ('q', 59), ('r', 60),
+    ('s', 61), ('t', 62), ('u', 63), ('v', 64), ('w', 65), ('x', 66), ('y', 67), ('z', 68),
+    ('ɑ', 69), ('ɐ', 70), ('ɒ', 71), ('æ', 72), ('β', 75), ('ɔ', 76), ('ɕ', 77), ('ç', 78),
+    ('ɖ', 80), ('ð', 81), ('ʤ', 82), ('ə', 83), ('ɚ', 85), ('ɛ', 86), ('ɜ', 87), ('ɟ', 90),
+    ('ɡ', 92), ('ɥ', 99), ('ɨ', 101), ('ɪ', 102), ('ʝ', 103), ('ɯ', 110), ('ɰ', 111), ('ŋ', 112),
+    ('ɳ', 113), ('ɲ', 114), ('ɴ', 115), ('ø', 116), ('ɸ', 118), ('θ', 119), ('œ', 120), ('ɹ', 123),
+    ('ɾ', 125), ('ɻ', 126), ('ʁ', 128), ('ɽ', 129), ('ʂ', 130), ('ʃ', 131), ('ʈ', 132), ('ʧ', 133),
+    ('ʊ', 135), ('ʋ', 136), ('ʌ', 138), ('ɣ', 139), ('ɤ', 140), ('χ', 142), ('ʎ', 143), ('ʒ', 147),
+    ('ʔ', 148), ('ˈ', 156), ('ˌ', 157), ('ː', 158), ('ʰ', 162), ('ʲ', 164), ('↓', 169), ('→', 171),
+    ('↗', 172), ('↘', 173), ('ᵻ', 177),
+];
 
-    let symbols: String = [pad, punctuation, letters, letters_ipa].concat();
+/// pad token ('$' = 0)
+const PAD: (char, i64) = ('$', 0);
 
-    symbols
-        .chars()
-        .enumerate()
-        .map(|(idx, c)| (c, idx))
-        .collect()
+lazy_static! {
+    /// 内置词汇表: 引擎加载前的输入校验、诊断等使用
+    pub static ref EMBEDDED: Vocab = Vocab::embedded();
 }
 
-lazy_static! {
-    pub static ref VOCAB: HashMap<char, usize> = build_vocab();
+/// 字符 → token ID 词汇表
+#[derive(Debug, Clone)]
+pub struct Vocab {
+    symbols: HashMap<char, i64>,
+    ids: HashSet<i64>,
+}
+
+/// 两个词汇表的差异
+#[derive(Debug, Default, PartialEq)]
+pub struct VocabDiff {
+    /// 只在对方表中出现的符号
+    pub added: Vec<(char, i64)>,
+    /// 对方表中缺少的符号
+    pub missing: Vec<(char, i64)>,
+    /// ID 不同的符号: (符号, 本表 ID, 对方 ID)
+    pub changed: Vec<(char, i64, i64)>,
+}
+
+impl VocabDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.missing.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Vocab {
+    /// 从 (符号, ID) 列表构建, 重复的符号或 ID 报错 (不能让后出现的静默覆盖前面的)
+    fn from_entries<I>(entries: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (char, i64)>,
+    {
+        let mut symbols = HashMap::new();
+        let mut ids: HashMap<i64, char> = HashMap::new();
+        for (c, id) in entries {
+            if id < 0 {
+                anyhow::bail!("符号 '{}' 的 ID 为负数: {}", c, id);
+            }
+            if let Some(prev) = symbols.insert(c, id) {
+                anyhow::bail!("符号 '{}' (U+{:04X}) 重复: ID {} 和 {}", c, c as u32, prev, id);
+            }
+            if let Some(prev) = ids.insert(id, c) {
+                anyhow::bail!("ID {} 重复: '{}' 和 '{}'", id, prev, c);
+            }
+        }
+        if symbols.get(&PAD.0) != Some(&PAD.1) {
+            anyhow::bail!("词汇表缺少 pad token '{}' = {}", PAD.0, PAD.1);
+        }
+        Ok(Self {
+            ids: ids.into_keys().collect(),
+            symbols,
+        })
+    }
+
+    /// 内置 Kokoro v1.0 词汇表
+    pub fn embedded() -> Self {
+        Self::from_entries(KOKORO_V1_VOCAB.iter().copied()).expect("内置词汇表无效")
+    }
+
+    /// 解析 tokenizer.json (`model.vocab` 为 符号 → ID 的对象)
+    pub fn from_tokenizer_json(json: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json).context("tokenizer.json 不是有效的 JSON")?;
+        let vocab = value
+            .pointer("/model/vocab")
+            .and_then(|v| v.as_object())
+            .context("tokenizer.json 缺少 model.vocab")?;
+
+        let entries = vocab
+            .iter()
+            .map(|(symbol, id)| {
+                let mut chars = symbol.chars();
+                let c = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => anyhow::bail!("词汇表符号必须是单个字符: {:?}", symbol),
+                };
+                let id = id
+                    .as_i64()
+                    .with_context(|| format!("符号 {:?} 的 ID 不是整数", symbol))?;
+                Ok((c, id))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::from_entries(entries)
+    }
+
+    /// 加载 tokenizer.json 文件
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).with_context(|| format!("无法读取 {:?}", path))?;
+        Self::from_tokenizer_json(&json).with_context(|| format!("无效的词汇表 {:?}", path))
+    }
+
+    /// 加载模型对应的词汇表: 模型同目录下的 tokenizer.json, 找不到时使用内置表
+    ///
+    /// 与内置表的差异只记录警告 (其他模型可以有不同的词汇表)。
+    pub fn for_model<P: AsRef<Path>>(model_path: P) -> Result<Self> {
+        let path = model_path
+            .as_ref()
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("tokenizer.json");
+
+        if !path.exists() {
+            warn!("⚠️ 未找到 {:?}, 使用内置 Kokoro v1.0 词汇表", path);
+            return Ok(Self::embedded());
+        }
+
+        let vocab = Self::from_file(&path)?;
+        let diff = EMBEDDED.diff(&vocab);
+        if diff.is_empty() {
+            info!("📖 词汇表: {:?} ({} 个符号, 与内置表一致)", path, vocab.len());
+        } else {
+            warn!(
+                "⚠️ 词汇表 {:?} 与内置表不同: 新增 {:?}, 缺少 {:?}, ID 变化 {:?}",
+                path, diff.added, diff.missing, diff.changed
+            );
+        }
+        Ok(vocab)
+    }
+
+    /// 与另一个词汇表比较
+    pub fn diff(&self, other: &Vocab) -> VocabDiff {
+        let mut diff = VocabDiff::default();
+        for (&c, &id) in &other.symbols {
+            match self.symbols.get(&c) {
+                None => diff.added.push((c, id)),
+                Some(&own) if own != id => diff.changed.push((c, own, id)),
+                Some(_) => {}
+            }
+        }
+        for (&c, &id) in &self.symbols {
+            if !other.symbols.contains_key(&c) {
+                diff.missing.push((c, id));
+            }
+        }
+        diff.added.sort_by_key(|&(_, id)| id);
+        diff.missing.sort_by_key(|&(_, id)| id);
+        diff.changed.sort_by_key(|&(_, id, _)| id);
+        diff
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn get(&self, c: char) -> Option<i64> {
+        self.symbols.get(&c).copied()
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.symbols.contains_key(&c)
+    }
+
+    /// 将音素字符串转换为 token IDs (词汇表外的字符被丢弃)
+    pub fn tokenize(&self, phonemes: &str) -> Vec<i64> {
+        phonemes.chars().filter_map(|c| self.get(c)).collect()
+    }
+
+    /// 严格 tokenize: 任何词汇表外的字符都报错 (用于原始音素输入)
+    pub fn tokenize_strict(&self, phonemes: &str) -> Result<Vec<i64>, VocabError> {
+        let unknown: Vec<(usize, char)> = phonemes
+            .chars()
+            .enumerate()
+            .filter(|&(_, c)| !self.contains(c))
+            .collect();
+
+        if !unknown.is_empty() {
+            return Err(VocabError::UnknownSymbols(unknown));
+        }
+        Ok(self.tokenize(phonemes))
+    }
+
+    /// 校验原始 token ID 都在词汇表内
+    pub fn validate_tokens(&self, tokens: &[i64]) -> Result<(), VocabError> {
+        let invalid: Vec<(usize, i64)> = tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| !self.ids.contains(id))
+            .map(|(idx, &id)| (idx, id))
+            .collect();
+
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(VocabError::InvalidTokens(invalid))
+        }
+    }
 }
 
-/// 将音素字符串转换为 token IDs
-pub fn tokenize(phonemes: &str) -> Vec<i64> {
-    phonemes
-        .chars()
-        .filter_map(|c| VOCAB.get(&c))
-        .map(|&idx| idx as i64)
-        .collect()
+/// 原始音素 / token 输入校验错误
+#[derive(Debug, Error)]
+pub enum VocabError {
+    #[error("音素包含词汇表外的字符: {}", describe_symbols(.0))]
+    UnknownSymbols(Vec<(usize, char)>),
+    #[error("无效的 token ID: {}", describe_tokens(.0))]
+    InvalidTokens(Vec<(usize, i64)>),
+}
+
+/// 'g' (U+0067, 位置 3, 7), 'x' (U+0078, 位置 5)
+fn describe_symbols(symbols: &[(usize, char)]) -> String {
+    let mut grouped: BTreeMap<char, Vec<usize>> = BTreeMap::new();
+    for &(pos, c) in symbols {
+        grouped.entry(c).or_default().push(pos);
+    }
+    grouped
+        .iter()
+        .map(|(c, positions)| {
+            let positions: Vec<String> = positions.iter().map(|p| p.to_string()).collect();
+            format!("'{}' (U+{:04X}, 位置 {})", c, *c as u32, positions.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 999 (索引 2), -1 (索引 4)
+fn describe_tokens(tokens: &[(usize, i64)]) -> String {
+    tokens
+        .iter()
+        .map(|(idx, id)| format!("{} (索引 {})", id, idx))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 #[cfg(test)]
@@ -39,17 +266,63 @@ mod tests {
 
     #[test]
     fn test_vocab_size() {
-        assert!(VOCAB.len() > 100);
-        assert!(VOCAB.contains_key(&'$')); // pad token
-        assert!(VOCAB.contains_key(&' ')); // space
-        assert!(VOCAB.contains_key(&'ə')); // IPA
+        assert_eq!(EMBEDDED.len(), 115);
+        assert_eq!(EMBEDDED.get('$'), Some(0)); // pad token
+        assert_eq!(EMBEDDED.get(' '), Some(16)); // space
+        assert_eq!(EMBEDDED.get('ə'), Some(83)); // IPA
+        assert_eq!(EMBEDDED.get('A'), Some(24));
+        assert_eq!(EMBEDDED.get('ꭧ'), Some(23));
+    }
+
+    #[test]
+    fn test_tokenizer_file_matches_embedded() {
+        let vocab = Vocab::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/checkpoints/tokenizer.json")).unwrap();
+        assert!(EMBEDDED.diff(&vocab).is_empty());
+    }
+
+    #[test]
+    fn test_from_tokenizer_json_rejects_bad_tables() {
+        let ok = Vocab::from_tokenizer_json(r#"{"model": {"vocab": {"$": 0, "a": 1}}}"#).unwrap();
+        assert_eq!(ok.tokenize("ab a"), vec![1, 1]);
+
+        let dup_id = Vocab::from_tokenizer_json(r#"{"model": {"vocab": {"$": 0, "a": 1, "b": 1}}}"#);
+        assert!(dup_id.unwrap_err().to_string().contains("重复"));
+
+        let multi = Vocab::from_tokenizer_json(r#"{"model": {"vocab": {"$": 0, "ab": 1}}}"#);
+        assert!(multi.is_err());
+
+        let no_pad = Vocab::from_tokenizer_json(r#"{"model": {"vocab": {"a": 1}}}"#);
+        assert!(no_pad.is_err());
+
+        let diff = EMBEDDED.diff(&ok);
+        assert_eq!(diff.changed, vec![('a', 43, 1)]);
+        assert_eq!(diff.missing.len(), 113);
     }
 
     #[test]
     fn test_tokenize() {
         let phonemes = "həlˈoʊ";
-        let tokens = tokenize(phonemes);
-        assert!(tokens.len() > 0);
-        assert!(tokens.iter().all(|&t| t >= 0));
+        let tokens = EMBEDDED.tokenize(phonemes);
+        assert_eq!(tokens, vec![50, 83, 54, 156, 57, 135]);
+    }
+
+    #[test]
+    fn test_tokenize_strict_lists_unknown_symbols() {
+        assert!(EMBEDDED.tokenize_strict("həlˈoʊ").is_ok());
+
+        let err = EMBEDDED.tokenize_strict("hə1lo1").unwrap_err();
+        match &err {
+            VocabError::UnknownSymbols(symbols) => assert_eq!(symbols, &vec![(2, '1'), (5, '1')]),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(err.to_string().contains("'1' (U+0031, 位置 2, 5)"));
+    }
+
+    #[test]
+    fn test_validate_tokens() {
+        assert!(EMBEDDED.validate_tokens(&[0, 16, 43]).is_ok());
+        let err = EMBEDDED.validate_tokens(&[16, 9999, -1]).unwrap_err();
+        assert!(err.to_string().contains("9999 (索引 1)"));
+        assert!(err.to_string().contains("-1 (索引 2)"));
     }
 }
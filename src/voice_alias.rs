@@ -0,0 +1,279 @@
+//! 声音别名与默认声音
+//!
+//! 配置文件 data/voices/aliases.json (缺失或格式错误时使用编译进来的内置版本):
+//!
+//! - `default`: 全局默认声音
+//! - `languages`: 语言 (或 语言-口音) → 默认声音, 未指定声音时按文本语言选择
+//! - `aliases`: 别名 → 声音名, 兼容其他 TTS API 的声音名 (alloy / nova / echo ...)
+//!
+//! 解析顺序: 声音名 → 忽略大小写 → 别名 → 唯一的短名 (bella → af_bella)。
+//! 都找不到时按编辑距离给出最接近的声音名。
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::language::Language;
+use crate::voices::{self, VOICES_DIR};
+
+const BUILTIN_ALIASES: &str = include_str!("../data/voices/aliases.json");
+
+/// 找不到声音时最多给出几个建议
+const MAX_SUGGESTIONS: usize = 3;
+
+lazy_static! {
+    static ref ALIASES: VoiceAliases = VoiceAliases::load(VOICES_DIR);
+}
+
+/// 全局别名配置 (首次使用时从 data/voices 读取)
+pub fn aliases() -> &'static VoiceAliases {
+    &ALIASES
+}
+
+/// 按全局别名配置把声音名解析为 data/voices 中已有的声音
+pub fn resolve_installed(name: &str) -> Result<String, UnknownVoice> {
+    let known = voices::load_index(VOICES_DIR)
+        .map(|index| index.into_keys().collect::<Vec<_>>())
+        .unwrap_or_default();
+    aliases().resolve(name, &known)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AliasFile {
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    languages: BTreeMap<String, String>,
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+}
+
+/// 声音不存在, 附带最接近的声音名
+#[derive(Debug, Error)]
+#[error("声音 '{name}' 不存在{}", describe_suggestions(.suggestions))]
+pub struct UnknownVoice {
+    pub name: String,
+    pub suggestions: Vec<String>,
+}
+
+fn describe_suggestions(suggestions: &[String]) -> String {
+    match suggestions.is_empty() {
+        true => String::new(),
+        false => format!(", 是否要找: {}", suggestions.join(", ")),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VoiceAliases {
+    default: String,
+    languages: BTreeMap<String, String>,
+    aliases: BTreeMap<String, String>,
+}
+
+impl VoiceAliases {
+    /// 合并配置: 同名项覆盖, 别名和语言代码不区分大小写
+    fn merge(&mut self, file: AliasFile) {
+        if let Some(default) = file.default {
+            self.default = default;
+        }
+        self.languages.extend(file.languages.into_iter().map(|(k, v)| (k.to_lowercase().replace('_', "-"), v)));
+        self.aliases.extend(file.aliases.into_iter().map(|(k, v)| (k.to_lowercase(), v)));
+    }
+
+    /// 内置配置
+    pub fn builtin() -> Self {
+        let mut config = Self { default: String::new(), languages: BTreeMap::new(), aliases: BTreeMap::new() };
+        config.merge(serde_json::from_str(BUILTIN_ALIASES).expect("内置 aliases.json 格式错误"));
+        config
+    }
+
+    /// 读取 aliases.json, 覆盖内置配置中的同名项
+    pub fn load<P: AsRef<Path>>(voices_dir: P) -> Self {
+        let mut config = Self::builtin();
+        let path = voices_dir.as_ref().join("aliases.json");
+        let Ok(content) = fs::read_to_string(&path) else {
+            return config;
+        };
+        match serde_json::from_str::<AliasFile>(&content) {
+            Ok(file) => {
+                config.merge(file);
+                info!("🏷️ 声音别名: {} 个, 默认声音: {}", config.aliases.len(), config.default);
+            }
+            Err(e) => warn!("⚠️ {:?} 格式错误, 使用内置别名: {}", path, e),
+        }
+        config
+    }
+
+    /// 全局默认声音
+    pub fn default_voice(&self) -> &str {
+        &self.default
+    }
+
+    /// 某个语言的默认声音: "en-gb" 找不到时退回 "en", 再退回全局默认
+    pub fn default_for(&self, language: &str) -> &str {
+        let language = language.to_lowercase().replace('_', "-");
+        let base = language.split_once('-').map_or(language.as_str(), |(base, _)| base);
+        self.languages
+            .get(&language)
+            .or_else(|| self.languages.get(base))
+            .unwrap_or(&self.default)
+    }
+
+    /// 未指定声音时按文本语言选择: 中文文本用中文默认声音, 日文用日文默认声音
+    pub fn default_for_text(&self, text: &str) -> &str {
+        let language = Language::detect(text, &self.default);
+        if language == Language::from_voice(&self.default) {
+            return &self.default;
+        }
+        self.default_for(match language {
+            Language::English => "en",
+            Language::Mandarin => "zh",
+            Language::Japanese => "ja",
+        })
+    }
+
+    /// 是否是某个语言 (或全局) 的默认声音
+    pub fn is_default(&self, name: &str) -> bool {
+        self.default == name || self.languages.values().any(|voice| voice == name)
+    }
+
+    /// 把请求中的声音名解析为已有的声音
+    pub fn resolve(&self, name: &str, known: &[String]) -> Result<String, UnknownVoice> {
+        let exists = |candidate: &str| known.iter().any(|k| k == candidate);
+        if exists(name) {
+            return Ok(name.to_string());
+        }
+
+        let lowered = name.trim().to_lowercase();
+        if exists(&lowered) {
+            return Ok(lowered);
+        }
+        if let Some(target) = self.aliases.get(&lowered).filter(|target| exists(target)) {
+            return Ok(target.clone());
+        }
+
+        // 短名: bella → af_bella (只有一个声音匹配时)
+        let mut short_matches = known.iter().filter(|k| base_name(k) == lowered);
+        if let (Some(only), None) = (short_matches.next(), short_matches.next()) {
+            return Ok(only.clone());
+        }
+
+        Err(UnknownVoice { name: name.to_string(), suggestions: self.suggest(&lowered, known) })
+    }
+
+    /// 按编辑距离 (声音名、短名、别名中最近的一个) 排序的候选声音
+    fn suggest(&self, name: &str, known: &[String]) -> Vec<String> {
+        let limit = (name.chars().count() / 2).max(2);
+        let mut scored: Vec<(usize, &String)> = known
+            .iter()
+            .map(|voice| {
+                let aliases = self.aliases.iter().filter(|(_, target)| *target == voice).map(|(alias, _)| alias.as_str());
+                let distance = [voice.as_str(), base_name(voice)]
+                    .into_iter()
+                    .chain(aliases)
+                    .map(|candidate| levenshtein(name, candidate))
+                    .min()
+                    .unwrap_or(usize::MAX);
+                (distance, voice)
+            })
+            .filter(|(distance, _)| *distance <= limit)
+            .collect();
+        scored.sort();
+        scored.into_iter().take(MAX_SUGGESTIONS).map(|(_, voice)| voice.clone()).collect()
+    }
+}
+
+/// af_bella → bella
+fn base_name(voice: &str) -> &str {
+    voice.split_once('_').map_or(voice, |(_, rest)| rest)
+}
+
+/// 编辑距离 (按字符)
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known() -> Vec<String> {
+        crate::voices::load_index(VOICES_DIR).unwrap().into_keys().collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let aliases = VoiceAliases::builtin();
+        let known = known();
+        assert_eq!(aliases.resolve("af_bella", &known).unwrap(), "af_bella");
+        assert_eq!(aliases.resolve("AF_Bella", &known).unwrap(), "af_bella");
+        assert_eq!(aliases.resolve("nova", &known).unwrap(), "af_nova");
+        assert_eq!(aliases.resolve("Shimmer", &known).unwrap(), "af_sky");
+        assert_eq!(aliases.resolve("xiaobei", &known).unwrap(), "zf_xiaobei");
+
+        // santa 有三个声音, 不能按短名解析
+        let err = aliases.resolve("santa", &known).unwrap_err();
+        assert_eq!(err.suggestions, vec!["am_santa", "em_santa", "pm_santa"]);
+
+        let err = aliases.resolve("af_bela", &known).unwrap_err();
+        assert_eq!(err.suggestions[0], "af_bella");
+        assert!(err.to_string().contains("af_bella"));
+        assert!(aliases.resolve("qqqqqqqqqqqq", &known).unwrap_err().suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_language_defaults() {
+        let aliases = VoiceAliases::builtin();
+        assert_eq!(aliases.default_voice(), "af_alloy");
+        assert_eq!(aliases.default_for("en-GB"), "bf_emma");
+        assert_eq!(aliases.default_for("en-au"), "af_alloy");
+        assert_eq!(aliases.default_for("xx"), "af_alloy");
+        assert_eq!(aliases.default_for_text("Hello world"), "af_alloy");
+        assert_eq!(aliases.default_for_text("你好世界"), "zf_xiaobei");
+        assert_eq!(aliases.default_for_text("こんにちは"), "jf_alpha");
+        assert!(aliases.is_default("zf_xiaobei") && !aliases.is_default("af_bella"));
+
+        // 内置配置引用的声音都存在
+        let known = known();
+        let targets = aliases.aliases.values().chain(aliases.languages.values());
+        assert!(targets.chain([&aliases.default]).all(|voice| known.contains(voice)));
+    }
+
+    #[test]
+    fn test_load_overrides_builtin() {
+        let dir = std::env::temp_dir().join(format!("tts_voice_alias_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("aliases.json"), r#"{"languages": {"zh": "zm_yunxi"}, "aliases": {"Narrator": "bm_george"}}"#).unwrap();
+        let aliases = VoiceAliases::load(&dir);
+        assert_eq!(aliases.default_voice(), "af_alloy");
+        assert_eq!(aliases.default_for("zh"), "zm_yunxi");
+        assert_eq!(aliases.resolve("narrator", &known()).unwrap(), "bm_george");
+        assert_eq!(aliases.resolve("nova", &known()).unwrap(), "af_nova");
+
+        fs::write(dir.join("aliases.json"), "{not json").unwrap();
+        assert_eq!(VoiceAliases::load(&dir).default_for("zh"), "zf_xiaobei");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("小贝", "小北"), 1);
+    }
+}
@@ -0,0 +1,818 @@
+//! 声音导入: 直接读取上游 Kokoro 发布的声音格式, 不需要 Python 脚本
+//!
+//! 支持的格式 (按文件内容识别, 不看扩展名):
+//! - NPZ (`voices-v1.0.bin`): zip 包, 每个 `<声音名>.npy` 是一个声音
+//! - NPY: 单个数组, 文件名即声音名
+//! - safetensors: 每个张量一个声音 (只有一个张量时用文件名)
+//! - PyTorch `.pt` (`torch.save`): zip 包, 只含一个连续张量 (不执行 pickle, 只解析张量布局)
+//! - 原始 `.bin`: 510×256 个小端 f32
+//!
+//! 数据类型支持 f16 / bf16 / f32 / f64, 形状必须是 [510, 256] 或 [510, 1, 256]。
+//! 命令行: `tts-server voices import <文件>...`, 导入的声音登记为用户声音。
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::rc::Rc;
+use thiserror::Error;
+use tracing::{error, info};
+
+use crate::style_table::StyleTable;
+use crate::voices::{self, VoiceError, VoiceMetadata, STYLE_DIM, STYLE_ROWS, VOICE_FILE_BYTES};
+
+/// zip 包内单个条目的大小上限 (一个 f64 声音约 1 MB)
+const MAX_ENTRY_BYTES: u64 = 16 * 1024 * 1024;
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("读取文件失败: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("zip 包损坏: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("无法识别的声音文件格式: {0}")]
+    UnknownFormat(String),
+    #[error("NPY 格式错误: {0}")]
+    Npy(String),
+    #[error("safetensors 格式错误: {0}")]
+    Safetensors(String),
+    #[error("PyTorch 文件格式错误: {0}")]
+    Pt(String),
+    #[error("不支持的数据类型: {0}")]
+    UnsupportedDtype(String),
+    #[error("声音 '{name}' 形状错误: {shape:?} (需要 [{STYLE_ROWS}, {STYLE_DIM}] 或 [{STYLE_ROWS}, 1, {STYLE_DIM}])")]
+    Shape { name: String, shape: Vec<usize> },
+    #[error("zip 条目 '{0}' 超过 {MAX_ENTRY_BYTES} 字节")]
+    EntryTooLarge(String),
+    #[error(transparent)]
+    Voice(#[from] VoiceError),
+}
+
+/// 导入得到的声音
+#[derive(Debug)]
+pub struct ImportedVoice {
+    pub name: String,
+    /// 510×256 style 表
+    pub table: StyleTable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dtype {
+    F16,
+    BF16,
+    F32,
+    F64,
+}
+
+impl Dtype {
+    fn size(self) -> usize {
+        match self {
+            Dtype::F16 | Dtype::BF16 => 2,
+            Dtype::F32 => 4,
+            Dtype::F64 => 8,
+        }
+    }
+
+    /// 小端字节 → f32
+    fn decode(self, b: &[u8]) -> f32 {
+        match self {
+            Dtype::F16 => f16_to_f32(u16::from_le_bytes([b[0], b[1]])),
+            Dtype::BF16 => f32::from_bits((u16::from_le_bytes([b[0], b[1]]) as u32) << 16),
+            Dtype::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            Dtype::F64 => f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32,
+        }
+    }
+}
+
+/// IEEE 754 半精度 → 单精度
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) as u32) << 31;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+    match exponent {
+        0 if mantissa == 0 => f32::from_bits(sign),
+        // 非规格化数
+        0 => {
+            let value = mantissa as f32 * 2f32.powi(-24);
+            if sign != 0 { -value } else { value }
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | (mantissa << 13)),
+        _ => f32::from_bits(sign | ((exponent + 112) << 23) | (mantissa << 13)),
+    }
+}
+
+/// 未解码的张量
+struct RawTensor<'a> {
+    dtype: Dtype,
+    shape: Vec<usize>,
+    data: &'a [u8],
+}
+
+impl RawTensor<'_> {
+    /// 校验形状, 转成 510×256 style 表 (同时检查 NaN/Inf)
+    fn into_voice(self, name: &str) -> Result<ImportedVoice, ImportError> {
+        let shape_ok = match self.shape.as_slice() {
+            [rows, dim] | [rows, 1, dim] => *rows == STYLE_ROWS && *dim == STYLE_DIM,
+            _ => false,
+        };
+        if !shape_ok || self.data.len() != STYLE_ROWS * STYLE_DIM * self.dtype.size() {
+            return Err(ImportError::Shape { name: name.to_string(), shape: self.shape });
+        }
+
+        let values = self.data.chunks_exact(self.dtype.size()).map(|chunk| self.dtype.decode(chunk)).collect();
+        voices::validate_name(name)?;
+        Ok(ImportedVoice { name: name.to_string(), table: StyleTable::from_values(values)? })
+    }
+}
+
+/// 解析 NPY 头部字典中的一个值: 'descr': '<f4' / 'shape': (510, 1, 256)
+fn npy_header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = match rest.chars().next()? {
+        '(' => rest.find(')')? + 1,
+        quote @ ('\'' | '"') => rest[1..].find(quote)? + 2,
+        _ => rest.find([',', '}'])?,
+    };
+    Some(rest[..end].trim())
+}
+
+fn parse_npy(bytes: &[u8]) -> Result<RawTensor<'_>, ImportError> {
+    let err = |message: &str| ImportError::Npy(message.to_string());
+    if !bytes.starts_with(NPY_MAGIC) || bytes.len() < 10 {
+        return Err(err("缺少 \\x93NUMPY 标记"));
+    }
+    // v1: 2 字节头长度; v2/v3: 4 字节
+    let (header_len, header_start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize, 12),
+        version => return Err(ImportError::Npy(format!("不支持的版本 {}", version))),
+    };
+    let data_start = header_start + header_len;
+    let header = bytes
+        .get(header_start..data_start)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| err("头部被截断"))?;
+
+    let descr = npy_header_value(header, "descr").ok_or_else(|| err("缺少 descr"))?;
+    let dtype = match descr.trim_matches(|c| c == '\'' || c == '"') {
+        "<f2" => Dtype::F16,
+        "<f4" => Dtype::F32,
+        "<f8" => Dtype::F64,
+        other => return Err(ImportError::UnsupportedDtype(other.to_string())),
+    };
+    if npy_header_value(header, "fortran_order") != Some("False") {
+        return Err(err("不支持 Fortran 顺序"));
+    }
+    let shape = npy_header_value(header, "shape")
+        .ok_or_else(|| err("缺少 shape"))?
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<usize>().map_err(|_| ImportError::Npy(format!("无效 shape: {}", s))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(RawTensor { dtype, shape, data: &bytes[data_start..] })
+}
+
+fn parse_safetensors<'a>(bytes: &'a [u8], file_stem: &str) -> Result<Vec<(String, RawTensor<'a>)>, ImportError> {
+    let err = |message: String| ImportError::Safetensors(message);
+    let header_len = bytes
+        .get(..8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize)
+        .ok_or_else(|| err("文件太短".to_string()))?;
+    let data_start = 8usize.checked_add(header_len).filter(|&end| end <= bytes.len()).ok_or_else(|| err("头部长度越界".to_string()))?;
+    let header: serde_json::Map<String, Value> =
+        serde_json::from_slice(&bytes[8..data_start]).map_err(|e| err(format!("头部不是 JSON 对象: {}", e)))?;
+    let data = &bytes[data_start..];
+
+    let tensors: Vec<(&String, &Value)> = header.iter().filter(|(name, _)| *name != "__metadata__").collect();
+    let single = tensors.len() == 1;
+    tensors
+        .into_iter()
+        .map(|(name, info)| {
+            let dtype = match info["dtype"].as_str() {
+                Some("F16") => Dtype::F16,
+                Some("BF16") => Dtype::BF16,
+                Some("F32") => Dtype::F32,
+                Some("F64") => Dtype::F64,
+                other => return Err(ImportError::UnsupportedDtype(format!("{}: {:?}", name, other))),
+            };
+            let shape = info["shape"]
+                .as_array()
+                .and_then(|dims| dims.iter().map(|d| d.as_u64().map(|d| d as usize)).collect::<Option<Vec<_>>>())
+                .ok_or_else(|| err(format!("{}: 缺少 shape", name)))?;
+            let range = match info["data_offsets"].as_array().map(|o| (o.first().and_then(Value::as_u64), o.get(1).and_then(Value::as_u64))) {
+                Some((Some(begin), Some(end))) if begin <= end && end as usize <= data.len() => begin as usize..end as usize,
+                _ => return Err(err(format!("{}: data_offsets 无效", name))),
+            };
+            // 单个张量通常叫 "voice" / "style" 之类, 用文件名更有意义
+            let voice_name = if single { file_stem.to_string() } else { name.clone() };
+            Ok((voice_name, RawTensor { dtype, shape, data: &data[range] }))
+        })
+        .collect()
+}
+
+/// data.pkl 中的值: 只覆盖 torch.save 用到的 pickle 操作码, GLOBAL/REDUCE 只记录不执行
+#[derive(Debug)]
+enum Pickled {
+    Int(i64),
+    Str(String),
+    Global(String, String),
+    Tuple(Vec<Rc<Pickled>>),
+    /// 用不到的值 (None、布尔、浮点、列表、字典), 只保留占位
+    Opaque,
+    PersistentId(Rc<Pickled>),
+    Reduce(Rc<Pickled>, Rc<Pickled>),
+}
+
+fn pickle_error(message: String) -> ImportError {
+    ImportError::Pt(format!("data.pkl: {}", message))
+}
+
+struct PickleReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PickleReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ImportError> {
+        let chunk = self
+            .pos
+            .checked_add(n)
+            .and_then(|end| self.bytes.get(self.pos..end))
+            .ok_or_else(|| pickle_error("数据被截断".to_string()))?;
+        self.pos += n;
+        Ok(chunk)
+    }
+
+    /// n 字节小端无符号整数
+    fn uint(&mut self, n: usize) -> Result<u64, ImportError> {
+        Ok(self.take(n)?.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+    }
+
+    /// GLOBAL 的参数: 以换行结尾的一行
+    fn line(&mut self) -> Result<String, ImportError> {
+        let rest = &self.bytes[self.pos.min(self.bytes.len())..];
+        let end = rest.iter().position(|&b| b == b'\n').ok_or_else(|| pickle_error("GLOBAL 缺少换行".to_string()))?;
+        let line = String::from_utf8_lossy(&self.take(end + 1)?[..end]).into_owned();
+        Ok(line)
+    }
+}
+
+/// 逐个操作码解释 pickle, 返回 STOP 时栈顶的值
+fn unpickle(bytes: &[u8]) -> Result<Rc<Pickled>, ImportError> {
+    let err = pickle_error;
+    let mut reader = PickleReader { bytes, pos: 0 };
+    let mut stack: Vec<Rc<Pickled>> = Vec::new();
+    let mut marks: Vec<usize> = Vec::new();
+    let mut memo: HashMap<u64, Rc<Pickled>> = HashMap::new();
+    macro_rules! pop {
+        () => {
+            stack.pop().ok_or_else(|| err("栈为空".to_string()))?
+        };
+    }
+    macro_rules! pop_mark {
+        () => {{
+            let mark = marks.pop().ok_or_else(|| err("缺少 MARK".to_string()))?;
+            stack.split_off(mark.min(stack.len()))
+        }};
+    }
+
+    loop {
+        let opcode = reader.take(1)?[0];
+        let value = match opcode {
+            // PROTO / FRAME
+            0x80 => {
+                reader.take(1)?;
+                continue;
+            }
+            0x95 => {
+                reader.take(8)?;
+                continue;
+            }
+            b'.' => return Ok(pop!()),
+            b'(' => {
+                marks.push(stack.len());
+                continue;
+            }
+            // BINPUT / LONG_BINPUT / MEMOIZE
+            b'q' | b'r' | 0x94 => {
+                let index = match opcode {
+                    b'q' => reader.uint(1)?,
+                    b'r' => reader.uint(4)?,
+                    _ => memo.len() as u64,
+                };
+                let top = stack.last().ok_or_else(|| err("栈为空".to_string()))?;
+                memo.insert(index, Rc::clone(top));
+                continue;
+            }
+            // BINGET / LONG_BINGET
+            b'h' | b'j' => {
+                let index = reader.uint(if opcode == b'h' { 1 } else { 4 })?;
+                Rc::clone(memo.get(&index).ok_or_else(|| err(format!("memo {} 不存在", index)))?)
+            }
+            b'N' | 0x88 | 0x89 | b'}' | b']' => Rc::new(Pickled::Opaque),
+            b'K' => Rc::new(Pickled::Int(reader.take(1)?[0] as i64)),
+            b'M' => Rc::new(Pickled::Int(reader.uint(2)? as i64)),
+            b'J' => Rc::new(Pickled::Int(i32::from_le_bytes(reader.take(4)?.try_into().unwrap()) as i64)),
+            // LONG1: 小端补码, 最多 8 字节
+            0x8a => {
+                let n = reader.take(1)?[0] as usize;
+                if n > 8 {
+                    return Err(err(format!("整数过长 ({} 字节)", n)));
+                }
+                let b = reader.uint(n)?;
+                let shift = 64 - 8 * n as u32;
+                let value = if n == 0 { 0 } else { ((b << shift) as i64) >> shift };
+                Rc::new(Pickled::Int(value))
+            }
+            b'G' => {
+                reader.take(8)?;
+                Rc::new(Pickled::Opaque)
+            }
+            // SHORT_BINUNICODE / BINUNICODE / SHORT_BINSTRING / BINSTRING
+            0x8c | b'X' | b'U' | b'T' => {
+                let len = reader.uint(if matches!(opcode, 0x8c | b'U') { 1 } else { 4 })? as usize;
+                Rc::new(Pickled::Str(String::from_utf8_lossy(reader.take(len)?).into_owned()))
+            }
+            b'c' => {
+                let module = reader.line()?;
+                let name = reader.line()?;
+                Rc::new(Pickled::Global(module, name))
+            }
+            0x93 => {
+                let (name, module) = (pop!(), pop!());
+                match (&*module, &*name) {
+                    (Pickled::Str(module), Pickled::Str(name)) => Rc::new(Pickled::Global(module.clone(), name.clone())),
+                    _ => return Err(err("STACK_GLOBAL 参数不是字符串".to_string())),
+                }
+            }
+            b')' => Rc::new(Pickled::Tuple(Vec::new())),
+            0x85..=0x87 => {
+                let n = (opcode - 0x84) as usize;
+                if stack.len() < n {
+                    return Err(err("栈为空".to_string()));
+                }
+                Rc::new(Pickled::Tuple(stack.split_off(stack.len() - n)))
+            }
+            b't' => Rc::new(Pickled::Tuple(pop_mark!())),
+            // SETITEM / SETITEMS / APPEND / APPENDS: 丢弃内容, 容器留在栈上
+            b's' => {
+                pop!();
+                pop!();
+                continue;
+            }
+            b'a' => {
+                pop!();
+                continue;
+            }
+            b'u' | b'e' => {
+                let _ = pop_mark!();
+                continue;
+            }
+            b'Q' => Rc::new(Pickled::PersistentId(pop!())),
+            b'R' => {
+                let args = pop!();
+                let callable = pop!();
+                Rc::new(Pickled::Reduce(callable, args))
+            }
+            // BUILD: 状态用不到
+            b'b' => {
+                pop!();
+                continue;
+            }
+            other => return Err(err(format!("不支持的操作码 0x{:02x} (位置 {})", other, reader.pos - 1))),
+        };
+        stack.push(value);
+    }
+}
+
+/// torch.save 保存的单个张量: `_rebuild_tensor_v2(存储, 偏移, 形状, 步长, ...)`
+#[derive(Debug)]
+struct TorchTensor {
+    dtype: Dtype,
+    /// 存储在 zip 包中的文件名 (`data/<key>`)
+    key: String,
+    storage_numel: usize,
+    offset: usize,
+    size: Vec<usize>,
+    stride: Vec<usize>,
+}
+
+fn parse_torch_tensor(pickle: &[u8]) -> Result<TorchTensor, ImportError> {
+    let err = |message: &str| ImportError::Pt(message.to_string());
+    let root = unpickle(pickle)?;
+    let Pickled::Reduce(callable, args) = &*root else {
+        return Err(err("data.pkl 的顶层对象不是张量"));
+    };
+    match &**callable {
+        Pickled::Global(module, name) if module == "torch._utils" && name == "_rebuild_tensor_v2" => {}
+        Pickled::Global(module, name) => return Err(ImportError::Pt(format!("不支持的重建函数 {}.{}", module, name))),
+        _ => return Err(err("data.pkl 的顶层对象不是张量")),
+    }
+    let Pickled::Tuple(args) = &**args else {
+        return Err(err("_rebuild_tensor_v2 参数不是元组"));
+    };
+    let [storage, offset, size, stride, ..] = args.as_slice() else {
+        return Err(err("_rebuild_tensor_v2 参数不足"));
+    };
+
+    let as_usize = |value: &Pickled| match value {
+        Pickled::Int(n) => usize::try_from(*n).ok(),
+        _ => None,
+    };
+    let as_dims = |value: &Pickled| match value {
+        Pickled::Tuple(items) => items.iter().map(|item| as_usize(item)).collect::<Option<Vec<_>>>(),
+        _ => None,
+    };
+
+    // 持久化 ID: ('storage', torch.FloatStorage, '<key>', 'cpu', 元素数)
+    let Pickled::PersistentId(pid) = &**storage else {
+        return Err(err("张量存储不是持久化引用"));
+    };
+    let (storage_type, key, storage_numel) = match &**pid {
+        Pickled::Tuple(items) => match items.as_slice() {
+            [kind, storage_type, key, _location, numel] if matches!(&**kind, Pickled::Str(k) if k == "storage") => {
+                match (&**storage_type, &**key, as_usize(numel)) {
+                    (Pickled::Global(module, name), Pickled::Str(key), Some(numel)) if module == "torch" => (name, key, numel),
+                    _ => return Err(err("存储引用格式错误")),
+                }
+            }
+            _ => return Err(err("存储引用格式错误")),
+        },
+        _ => return Err(err("存储引用格式错误")),
+    };
+    let dtype = match storage_type.as_str() {
+        "BFloat16Storage" => Dtype::BF16,
+        "HalfStorage" => Dtype::F16,
+        "FloatStorage" => Dtype::F32,
+        "DoubleStorage" => Dtype::F64,
+        other => return Err(ImportError::UnsupportedDtype(other.to_string())),
+    };
+
+    Ok(TorchTensor {
+        dtype,
+        key: key.clone(),
+        storage_numel,
+        offset: as_usize(offset).ok_or_else(|| err("存储偏移无效"))?,
+        size: as_dims(size).ok_or_else(|| err("张量形状无效"))?,
+        stride: as_dims(stride).ok_or_else(|| err("张量步长无效"))?,
+    })
+}
+
+impl TorchTensor {
+    /// 只接受独占整个存储、从头开始的连续张量 (转置、切片视图都按步长读会错位)
+    fn check_layout(&self) -> Result<(), ImportError> {
+        if self.offset != 0 {
+            return Err(ImportError::Pt(format!("张量是偏移 {} 的视图, 请先 .clone() 再保存", self.offset)));
+        }
+        let numel: usize = self.size.iter().product();
+        if self.storage_numel != numel {
+            return Err(ImportError::Pt(format!(
+                "存储有 {} 个元素, 张量只用到 {} 个 (与其他张量共享存储), 请先 .clone() 再保存",
+                self.storage_numel, numel
+            )));
+        }
+        // 与 torch 的 is_contiguous 一致: 长度为 1 的维度不看步长
+        if self.stride.len() != self.size.len() {
+            return Err(ImportError::Pt(format!("形状 {:?} 与步长 {:?} 维数不符", self.size, self.stride)));
+        }
+        let mut expected = 1;
+        for (&dim, &stride) in self.size.iter().zip(&self.stride).rev() {
+            if dim != 1 && stride != expected {
+                return Err(ImportError::Pt(format!(
+                    "张量不连续 (形状 {:?}, 步长 {:?}), 请先 .contiguous() 再保存",
+                    self.size, self.stride
+                )));
+            }
+            expected *= dim;
+        }
+        Ok(())
+    }
+}
+
+fn read_entry<R: Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Result<Vec<u8>, ImportError> {
+    let entry = archive.by_name(name)?;
+    if entry.size() > MAX_ENTRY_BYTES {
+        return Err(ImportError::EntryTooLarge(name.to_string()));
+    }
+    let mut data = Vec::with_capacity(entry.size() as usize);
+    entry.take(MAX_ENTRY_BYTES + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > MAX_ENTRY_BYTES {
+        return Err(ImportError::EntryTooLarge(name.to_string()));
+    }
+    Ok(data)
+}
+
+/// 读取 zip 包: 含 .npy 条目按 NPZ 处理, 含 data.pkl 按 PyTorch 处理
+fn import_zip(bytes: &[u8], file_stem: &str) -> Result<Vec<ImportedVoice>, ImportError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    // 按包内顺序 (file_names() 的顺序不固定)
+    let names = (0..archive.len())
+        .map(|i| archive.by_index_raw(i).map(|entry| entry.name().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if names.iter().any(|n| n.ends_with(".npy")) {
+        let mut voices = Vec::new();
+        for entry_name in &names {
+            let Some(stem) = entry_name.strip_suffix(".npy") else {
+                continue;
+            };
+            let data = read_entry(&mut archive, entry_name)?;
+            let voice_name = stem.rsplit('/').next().unwrap_or(stem);
+            voices.push(parse_npy(&data)?.into_voice(voice_name)?);
+        }
+        return Ok(voices);
+    }
+
+    // torch.save: <前缀>/data.pkl 描述张量, <前缀>/data/<key> 是原始存储
+    let pkl_name = names.iter().find(|n| n.ends_with("data.pkl")).ok_or_else(|| {
+        ImportError::UnknownFormat("zip 包中既没有 .npy 也没有 data.pkl".to_string())
+    })?;
+    let prefix = pkl_name.trim_end_matches("data.pkl");
+    let pickle = read_entry(&mut archive, pkl_name)?;
+
+    let storages: Vec<&String> = names.iter().filter(|n| n.starts_with(&format!("{}data/", prefix))).collect();
+    let [storage] = storages.as_slice() else {
+        return Err(ImportError::Pt(format!("需要正好一个张量存储, 实际 {} 个", storages.len())));
+    };
+
+    // 不执行 pickle, 只解析 _rebuild_tensor_v2 的参数
+    let tensor = parse_torch_tensor(&pickle)?;
+    if **storage != format!("{}data/{}", prefix, tensor.key) {
+        return Err(ImportError::Pt(format!("data.pkl 引用的存储 '{}' 不存在", tensor.key)));
+    }
+    tensor.check_layout()?;
+
+    let data = read_entry(&mut archive, storage)?;
+    if data.len() != tensor.storage_numel * tensor.dtype.size() {
+        return Err(ImportError::Pt(format!("存储长度 {} 字节与元素数 {} 不符", data.len(), tensor.storage_numel)));
+    }
+    Ok(vec![RawTensor { dtype: tensor.dtype, shape: tensor.size, data: &data }.into_voice(file_stem)?])
+}
+
+/// 从内存中的文件内容导入声音, `file_stem` 用于单声音文件的命名
+pub fn import_bytes(bytes: &[u8], file_stem: &str) -> Result<Vec<ImportedVoice>, ImportError> {
+    if bytes.starts_with(ZIP_MAGIC) {
+        return import_zip(bytes, file_stem);
+    }
+    if bytes.starts_with(NPY_MAGIC) {
+        return Ok(vec![parse_npy(bytes)?.into_voice(file_stem)?]);
+    }
+    if bytes.len() == VOICE_FILE_BYTES {
+        voices::validate_name(file_stem)?;
+        return Ok(vec![ImportedVoice { name: file_stem.to_string(), table: voices::parse_voice_bytes(bytes)? }]);
+    }
+    // safetensors: 8 字节头长度 + JSON 头
+    if bytes.get(8) == Some(&b'{') {
+        return parse_safetensors(bytes, file_stem)?
+            .into_iter()
+            .map(|(name, tensor)| tensor.into_voice(&name))
+            .collect();
+    }
+    Err(ImportError::UnknownFormat(file_stem.to_string()))
+}
+
+/// 导入声音文件
+pub fn import_file<P: AsRef<Path>>(path: P) -> Result<Vec<ImportedVoice>, ImportError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("voice").to_lowercase();
+    import_bytes(&bytes, &stem)
+}
+
+/// `tts-server voices import <文件>...`: 导入并登记到 data/voices
+pub fn run_cli(args: &[String]) -> anyhow::Result<()> {
+    const USAGE: &str = "用法: tts-server voices import <文件>...";
+    let [command, paths @ ..] = args else {
+        anyhow::bail!(USAGE);
+    };
+    if command != "import" || paths.is_empty() {
+        anyhow::bail!(USAGE);
+    }
+
+    let mut imported = 0;
+    let mut failed = 0;
+    for path in paths {
+        info!("📥 导入声音文件: {}", path);
+        let voices = match import_file(path) {
+            Ok(voices) => voices,
+            Err(e) => {
+                error!("❌ {}: {}", path, e);
+                failed += 1;
+                continue;
+            }
+        };
+        for voice in voices {
+            let bytes = voice.table.to_le_bytes();
+            match voices::add_custom_voice(voices::VOICES_DIR, &voice.name, VoiceMetadata::default(), &bytes) {
+                Ok(_) => {
+                    info!("✅ 已导入: {}", voice.name);
+                    imported += 1;
+                }
+                Err(e) => {
+                    error!("❌ {}: {}", voice.name, e);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    info!("📊 导入完成: 成功 {}, 失败 {}", imported, failed);
+    if failed > 0 {
+        anyhow::bail!("{} 个声音导入失败", failed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn values() -> Vec<f32> {
+        (0..STYLE_ROWS * STYLE_DIM).map(|i| (i % 97) as f32 / 97.0 - 0.5).collect()
+    }
+
+    fn npy(shape: &str, descr: &str, data: &[u8]) -> Vec<u8> {
+        let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+        // 头部按 64 字节对齐, 以换行结尾
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        let mut out = NPY_MAGIC.to_vec();
+        out.extend_from_slice(&[1, 0]);
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        out.extend_from_slice(header.as_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn f32_bytes(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// 与 torch.save 写出的 data.pkl 逐字节相同 (pickle 协议 2, [510, 1, 256] float32 张量)
+    const TORCH_SAVE_PKL: &[u8] = b"\x80\x02ctorch._utils\n_rebuild_tensor_v2\nq\x00((X\x07\x00\x00\x00storageq\x01ctorch\nFloatStorage\nq\x02X\x01\x00\x00\x000q\x03X\x03\x00\x00\x00cpuq\x04J\x00\xfe\x01\x00tq\x05QK\x00M\xfe\x01K\x01M\x00\x01\x87q\x06M\x00\x01M\x00\x01K\x01\x87q\x07\x89ccollections\nOrderedDict\nq\x08)Rq\ttq\nRq\x0b.";
+
+    /// 按 torch.save 的字节布局生成 data.pkl, 用于构造各种张量视图
+    fn torch_pickle(size: &[usize], stride: &[usize], offset: usize, numel: usize) -> Vec<u8> {
+        fn int(out: &mut Vec<u8>, n: usize) {
+            match n {
+                0..=0xff => out.extend_from_slice(&[b'K', n as u8]),
+                0x100..=0xffff => {
+                    out.push(b'M');
+                    out.extend_from_slice(&(n as u16).to_le_bytes());
+                }
+                _ => {
+                    out.push(b'J');
+                    out.extend_from_slice(&(n as i32).to_le_bytes());
+                }
+            }
+        }
+        fn tuple(out: &mut Vec<u8>, items: &[usize], memo: u8) {
+            if items.len() > 3 {
+                out.push(b'(');
+            }
+            items.iter().for_each(|&n| int(out, n));
+            out.push(if items.len() > 3 { b't' } else { 0x84 + items.len() as u8 });
+            out.extend_from_slice(&[b'q', memo]);
+        }
+
+        let mut out = b"\x80\x02ctorch._utils\n_rebuild_tensor_v2\nq\x00((X\x07\x00\x00\x00storageq\x01ctorch\nFloatStorage\nq\x02X\x01\x00\x00\x000q\x03X\x03\x00\x00\x00cpuq\x04".to_vec();
+        int(&mut out, numel);
+        out.extend_from_slice(b"tq\x05Q");
+        int(&mut out, offset);
+        tuple(&mut out, size, 6);
+        tuple(&mut out, stride, 7);
+        out.extend_from_slice(b"\x89ccollections\nOrderedDict\nq\x08)Rq\ttq\nRq\x0b.");
+        out
+    }
+
+    fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in entries {
+            writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_f16() {
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert!(f16_to_f32(0x7c00).is_infinite());
+    }
+
+    #[test]
+    fn test_npy() {
+        let values = values();
+        let voice = &import_bytes(&npy("(510, 1, 256)", "<f4", &f32_bytes(&values)), "af_test").unwrap()[0];
+        assert_eq!(voice.name, "af_test");
+        assert_eq!(voice.table.row(3)[5], values[3 * STYLE_DIM + 5]);
+
+        let f64_data: Vec<u8> = values.iter().flat_map(|&v| (v as f64).to_le_bytes()).collect();
+        assert!(import_bytes(&npy("(510, 256)", "<f8", &f64_data), "af_test").is_ok());
+
+        let err = import_bytes(&npy("(256, 510)", "<f4", &f32_bytes(&values)), "af_test").unwrap_err();
+        assert!(matches!(err, ImportError::Shape { .. }));
+        let err = import_bytes(&npy("(510, 256)", ">f4", &f32_bytes(&values)), "af_test").unwrap_err();
+        assert!(matches!(err, ImportError::UnsupportedDtype(_)));
+    }
+
+    #[test]
+    fn test_npz() {
+        let data = f32_bytes(&values());
+        let archive = zip(&[
+            ("af_one.npy", &npy("(510, 1, 256)", "<f4", &data)),
+            ("bm_two.npy", &npy("(510, 1, 256)", "<f4", &data)),
+        ]);
+        let voices = import_bytes(&archive, "voices-v1.0").unwrap();
+        let names: Vec<&str> = voices.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["af_one", "bm_two"]);
+    }
+
+    #[test]
+    fn test_safetensors() {
+        let values = values();
+        let half: Vec<u8> = values
+            .iter()
+            .flat_map(|&v| ((v.to_bits() >> 16) as u16).to_le_bytes())
+            .collect();
+        let header = format!(
+            r#"{{"__metadata__": {{"format": "pt"}}, "af_a": {{"dtype": "BF16", "shape": [510, 1, 256], "data_offsets": [0, {}]}}, "af_b": {{"dtype": "F32", "shape": [510, 256], "data_offsets": [{}, {}]}}}}"#,
+            half.len(),
+            half.len(),
+            half.len() + values.len() * 4
+        );
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(&half);
+        bytes.extend_from_slice(&f32_bytes(&values));
+
+        let voices = import_bytes(&bytes, "pack").unwrap();
+        assert_eq!(voices.len(), 2);
+        assert_eq!(voices[0].name, "af_a");
+        assert!((voices[0].table.row(1)[2] - values[STYLE_DIM + 2]).abs() < 0.01);
+        assert_eq!(voices[1].table.row(1)[2], values[STYLE_DIM + 2]);
+    }
+
+    #[test]
+    fn test_pytorch() {
+        let data = f32_bytes(&values());
+        assert_eq!(torch_pickle(&[510, 1, 256], &[256, 256, 1], 0, STYLE_ROWS * STYLE_DIM), TORCH_SAVE_PKL);
+        let archive = zip(&[("am_pt/data.pkl", TORCH_SAVE_PKL), ("am_pt/data/0", &data), ("am_pt/version", b"3\n")]);
+        let voices = import_bytes(&archive, "am_pt").unwrap();
+        assert_eq!(voices[0].name, "am_pt");
+        assert_eq!(voices[0].table.to_le_bytes(), data);
+
+        let two = zip(&[("x/data.pkl", TORCH_SAVE_PKL), ("x/data/0", &data), ("x/data/1", &data)]);
+        assert!(matches!(import_bytes(&two, "x"), Err(ImportError::Pt(_))));
+    }
+
+    #[test]
+    fn test_pytorch_layout() {
+        let numel = STYLE_ROWS * STYLE_DIM;
+        let import = |pickle: &[u8], data: &[u8]| import_bytes(&zip(&[("x/data.pkl", pickle), ("x/data/0", data)]), "x");
+        let data = f32_bytes(&values());
+        let larger = f32_bytes(&vec![0.0; numel + STYLE_DIM]);
+
+        assert!(import(&torch_pickle(&[510, 256], &[256, 1], 0, numel), &data).is_ok());
+        // [256, 510] 张量转置成 [510, 256]
+        let err = import(&torch_pickle(&[510, 256], &[1, 510], 0, numel), &data).unwrap_err();
+        assert!(matches!(err, ImportError::Pt(_)), "{}", err);
+        // 偏移视图 big[1:]
+        let err = import(&torch_pickle(&[510, 256], &[256, 1], STYLE_DIM, numel + STYLE_DIM), &larger).unwrap_err();
+        assert!(matches!(err, ImportError::Pt(_)), "{}", err);
+        // 与更大的张量共享存储 big[:510]
+        let err = import(&torch_pickle(&[510, 256], &[256, 1], 0, numel + STYLE_DIM), &larger).unwrap_err();
+        assert!(matches!(err, ImportError::Pt(_)), "{}", err);
+        // 连续但形状错误
+        let err = import(&torch_pickle(&[256, 510], &[510, 1], 0, numel), &data).unwrap_err();
+        assert!(matches!(err, ImportError::Shape { .. }), "{}", err);
+        // 元素数与存储长度不符
+        let err = import(&torch_pickle(&[510, 256], &[256, 1], 0, numel), &larger).unwrap_err();
+        assert!(matches!(err, ImportError::Pt(_)), "{}", err);
+        // 其他 GLOBAL 只被识别, 不会被调用
+        let err = import(b"\x80\x02cos\nsystem\nq\x00X\x04\x00\x00\x00trueq\x01\x85q\x02Rq\x03.", &data).unwrap_err();
+        assert!(matches!(err, ImportError::Pt(_)), "{}", err);
+        assert!(matches!(import(b"\x80\x02(", &data), Err(ImportError::Pt(_))));
+    }
+
+    #[test]
+    fn test_raw_and_unknown() {
+        let data = f32_bytes(&values());
+        assert_eq!(import_bytes(&data, "af_raw").unwrap()[0].table.as_slice().len(), STYLE_ROWS * STYLE_DIM);
+        assert_eq!(import_bytes(&data, "af_raw").unwrap()[0].table.to_le_bytes(), data);
+        assert!(matches!(import_bytes(b"hello", "x"), Err(ImportError::UnknownFormat(_))));
+    }
+}
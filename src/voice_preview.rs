@@ -0,0 +1,171 @@
+//! 声音试听片段
+//!
+//! 每个声音一段固定的试听句子 (按声音的语言), 保存在缓存目录下的 previews/ 子目录:
+//!
+//! - `<name>.wav`: 试听音频
+//! - `<name>.fingerprint`: 生成时的指纹 (模型文件大小/修改时间 + 声音文件内容 + 试听文本)
+//!
+//! `AudioCache` 的 TTL 清理只处理缓存目录顶层的 .wav, 不会删除这里的文件。
+//! 指纹不一致 (换了模型或声音文件) 时视为过期, 重新生成。
+//!
+//! 同一指纹也用于音频缓存键 (`cache_key`): 删除后重新创建的同名声音不会命中旧缓存。
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info};
+
+use crate::voices;
+
+/// 试听片段格式版本, 生成方式变化时递增
+const PREVIEW_VERSION: &str = "2";
+
+/// 试听片段目录
+pub struct PreviewStore {
+    dir: PathBuf,
+}
+
+/// 模型文件标识: 大小 + 修改时间
+pub fn model_fingerprint(model_path: &Path) -> Result<String> {
+    let model = fs::metadata(model_path).with_context(|| format!("无法读取模型文件: {:?}", model_path))?;
+    let modified = model.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).unwrap_or_default();
+    Ok(format!("{}:{}", model.len(), modified.as_nanos()))
+}
+
+/// 计算试听片段指纹
+pub fn fingerprint(model_path: &Path, voice_path: &Path, text: &str) -> Result<String> {
+    let model = model_fingerprint(model_path)?;
+    let voice = fs::read(voice_path).with_context(|| format!("无法读取声音文件: {:?}", voice_path))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(PREVIEW_VERSION.as_bytes());
+    hasher.update(format!("|model:{}", model).as_bytes());
+    hasher.update(b"|voice:");
+    hasher.update(Sha256::digest(&voice));
+    hasher.update(b"|text:");
+    hasher.update(text.as_bytes());
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 声音文件路径 (按 index.json 登记的文件名)
+pub fn voice_path(voices_dir: &Path, name: &str) -> Result<PathBuf> {
+    let index = voices::load_index(voices_dir)?;
+    let entry = index.get(name).ok_or_else(|| anyhow::anyhow!("声音不存在: {}", name))?;
+    Ok(voices_dir.join(&entry.file))
+}
+
+/// 音频缓存键中的声音标识: 声音名 + 模型和声音内容的指纹
+///
+/// `voice_fingerprint` 来自 `VoiceStore::fingerprint` (加载/注册声音时计算一次)。
+pub fn cache_key(model_fingerprint: &str, name: &str, voice_fingerprint: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("model:{}|voice:{}", model_fingerprint, voice_fingerprint).as_bytes());
+    let fingerprint = format!("{:x}", hasher.finalize());
+    format!("{}#{}", name, &fingerprint[..16])
+}
+
+impl PreviewStore {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).with_context(|| format!("无法创建试听目录: {:?}", dir))?;
+        Ok(Self { dir })
+    }
+
+    fn wav_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.wav", name))
+    }
+
+    fn fingerprint_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.fingerprint", name))
+    }
+
+    /// 指纹一致时返回试听音频
+    pub fn get(&self, name: &str, fingerprint: &str) -> Option<Vec<u8>> {
+        let stored = fs::read_to_string(self.fingerprint_path(name)).ok()?;
+        if stored.trim() != fingerprint {
+            debug!("⏰ 试听已过期: {}", name);
+            return None;
+        }
+        fs::read(self.wav_path(name)).ok()
+    }
+
+    /// 保存试听音频 (先写音频再写指纹, 中途失败只会导致重新生成)
+    pub fn save(&self, name: &str, fingerprint: &str, wav: &[u8]) -> Result<()> {
+        let _ = fs::remove_file(self.fingerprint_path(name));
+        voices::write_atomic(&self.wav_path(name), wav).context("写入试听音频失败")?;
+        voices::write_atomic(&self.fingerprint_path(name), fingerprint.as_bytes()).context("写入试听指纹失败")?;
+        info!("💾 保存试听: {} ({} 字节)", name, wav.len());
+        Ok(())
+    }
+
+    /// 删除声音时一并删除试听
+    pub fn remove(&self, name: &str) {
+        let _ = fs::remove_file(self.fingerprint_path(name));
+        let _ = fs::remove_file(self.wav_path(name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_and_store() {
+        let dir = std::env::temp_dir().join(format!("tts_voice_preview_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let (model, voice) = (dir.join("model.onnx"), dir.join("af_test.bin"));
+        fs::write(&model, b"model").unwrap();
+        fs::write(&voice, [1u8; 64]).unwrap();
+
+        let original = fingerprint(&model, &voice, "Hello").unwrap();
+        assert_eq!(original, fingerprint(&model, &voice, "Hello").unwrap());
+        assert_ne!(original, fingerprint(&model, &voice, "Hi").unwrap());
+
+        let store = PreviewStore::new(dir.join("previews")).unwrap();
+        assert!(store.get("af_test", &original).is_none());
+        store.save("af_test", &original, b"RIFF").unwrap();
+        assert_eq!(store.get("af_test", &original).unwrap(), b"RIFF");
+
+        // 声音文件变化 → 指纹变化 → 旧片段失效
+        fs::write(&voice, [2u8; 64]).unwrap();
+        let changed = fingerprint(&model, &voice, "Hello").unwrap();
+        assert_ne!(changed, original);
+        assert!(store.get("af_test", &changed).is_none());
+
+        store.remove("af_test");
+        assert!(store.get("af_test", &original).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_cache_key_after_recreate() {
+        let dir = std::env::temp_dir().join(format!("tts_voice_cache_key_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let model = dir.join("model.onnx");
+        fs::write(&model, b"model").unwrap();
+        let model = model_fingerprint(&model).unwrap();
+        let store = crate::voice_store::VoiceStore::open(voices::VOICES_DIR).unwrap();
+        let values = |value: f32| vec![value; voices::STYLE_ROWS * voices::STYLE_DIM];
+
+        store.insert("my_voice", crate::style_table::StyleTable::from_values(values(0.1)).unwrap());
+        let original = cache_key(&model, "my_voice", &store.fingerprint("my_voice").unwrap());
+        assert!(original.starts_with("my_voice#"));
+        assert_eq!(original, cache_key(&model, "my_voice", &store.fingerprint("my_voice").unwrap()));
+
+        let cache = crate::cache::AudioCache::new(dir.join("audio"), 3600).unwrap();
+        let format = crate::audio_format::AudioFormat::Wav;
+        cache.save_in(crate::cache::TEXT_NAMESPACE, "Hello", Some(&original), format, b"RIFF").unwrap();
+
+        // 删除后用不同内容重新上传同名声音: 缓存键变化, 旧音频不再命中
+        store.remove("my_voice");
+        store.insert("my_voice", crate::style_table::StyleTable::from_values(values(0.2)).unwrap());
+        let recreated = cache_key(&model, "my_voice", &store.fingerprint("my_voice").unwrap());
+        assert_ne!(recreated, original);
+        assert!(cache.exists_in(crate::cache::TEXT_NAMESPACE, "Hello", Some(&recreated), format).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
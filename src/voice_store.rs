@@ -0,0 +1,317 @@
+//! 声音表存储: 按需加载, 后台并行预加载
+//!
+//! 启动时只读取 index.json, 声音文件在第一次使用时才映射和校验;
+//! 引擎初始化后在后台线程里并行预加载其余声音, 以便 /health 及早发现损坏的文件。
+//! 预加载完成后计算 style 空间 (声音相似度矩阵), 声音增删后重新计算。
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::thread;
+use std::time::Instant;
+use tracing::{error, info};
+
+use crate::style_space::{self, StyleSpace};
+use crate::style_table::StyleTable;
+use crate::voices::{self, VoiceError};
+
+/// 单个声音: 文件路径 + 加载结果 (只加载一次) + 内容指纹
+struct VoiceSlot {
+    path: PathBuf,
+    table: OnceLock<Result<Arc<StyleTable>, String>>,
+    /// 声音内容的 SHA-256, 加载/注册时计算; 声音增删时整个 slot 被替换
+    fingerprint: OnceLock<String>,
+}
+
+/// style 数据的 SHA-256 (十六进制)
+fn content_fingerprint(table: &StyleTable) -> String {
+    let mut hasher = Sha256::new();
+    for value in table.as_slice() {
+        hasher.update(value.to_le_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+impl VoiceSlot {
+    fn new(path: PathBuf) -> Self {
+        Self { path, table: OnceLock::new(), fingerprint: OnceLock::new() }
+    }
+
+    fn load(&self, name: &str) -> Result<Arc<StyleTable>, VoiceError> {
+        self.table
+            .get_or_init(|| match voices::load_voice_file(&self.path) {
+                Ok(table) => {
+                    voices::clear_load_error(name);
+                    let _ = self.fingerprint.set(content_fingerprint(&table));
+                    Ok(Arc::new(table))
+                }
+                Err(e) => {
+                    error!("❌ 声音 {} 加载失败: {}", name, e);
+                    voices::record_load_error(name, &e);
+                    Err(e.to_string())
+                }
+            })
+            .clone()
+            .map_err(|reason| VoiceError::LoadFailed { name: name.to_string(), reason })
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.table.get().is_some_and(Result::is_ok)
+    }
+}
+
+/// 所有声音
+pub struct VoiceStore {
+    dir: PathBuf,
+    slots: RwLock<HashMap<String, Arc<VoiceSlot>>>,
+    /// 缓存的 style 空间, 声音增删时清空
+    space: RwLock<Option<Arc<StyleSpace>>>,
+}
+
+/// 当前进程的常驻内存 (MB), 只在 Linux 上可用
+pub fn resident_memory_mb() -> Option<f64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: f64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024.0)
+}
+
+/// 日志用的常驻内存描述
+pub fn describe_memory() -> String {
+    resident_memory_mb().map_or_else(|| "未知".to_string(), |mb| format!("{:.1} MB", mb))
+}
+
+impl VoiceStore {
+    /// 读取 index.json 建立索引 (不读声音文件)
+    pub fn open<P: AsRef<Path>>(voices_dir: P) -> Result<Self> {
+        let voices_dir = voices_dir.as_ref();
+        let slots = voices::load_index(voices_dir)?
+            .into_iter()
+            .map(|(name, entry)| {
+                (name, Arc::new(VoiceSlot::new(voices_dir.join(&entry.file))))
+            })
+            .collect();
+        Ok(Self { dir: voices_dir.to_path_buf(), slots: RwLock::new(slots), space: RwLock::new(None) })
+    }
+
+    fn slot(&self, name: &str) -> Option<Arc<VoiceSlot>> {
+        self.slots.read().unwrap_or_else(|e| e.into_inner()).get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.slot(name).is_some()
+    }
+
+    /// 所有声音名 (排序)
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.slots.read().unwrap_or_else(|e| e.into_inner()).keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// 已成功加载的声音数
+    pub fn loaded(&self) -> usize {
+        self.slots.read().unwrap_or_else(|e| e.into_inner()).values().filter(|s| s.is_loaded()).count()
+    }
+
+    /// 获取声音表, 第一次访问时加载
+    pub fn get(&self, name: &str) -> Result<Arc<StyleTable>, VoiceError> {
+        self.slot(name)
+            .ok_or_else(|| VoiceError::NotFound(name.to_string()))?
+            .load(name)
+    }
+
+    /// 声音内容指纹 (用于音频缓存键), 第一次访问时加载声音
+    pub fn fingerprint(&self, name: &str) -> Result<String, VoiceError> {
+        let slot = self.slot(name).ok_or_else(|| VoiceError::NotFound(name.to_string()))?;
+        let table = slot.load(name)?;
+        Ok(slot.fingerprint.get_or_init(|| content_fingerprint(&table)).clone())
+    }
+
+    /// 注册已加载的声音 (上传/导入)
+    pub fn insert(&self, name: &str, table: StyleTable) {
+        let slot = VoiceSlot {
+            path: PathBuf::new(),
+            fingerprint: OnceLock::from(content_fingerprint(&table)),
+            table: OnceLock::from(Ok(Arc::new(table))),
+        };
+        voices::clear_load_error(name);
+        self.slots.write().unwrap_or_else(|e| e.into_inner()).insert(name.to_string(), Arc::new(slot));
+        self.invalidate_space();
+    }
+
+    pub fn remove(&self, name: &str) -> bool {
+        let removed = self.slots.write().unwrap_or_else(|e| e.into_inner()).remove(name).is_some();
+        self.invalidate_space();
+        removed
+    }
+
+    fn invalidate_space(&self) {
+        *self.space.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// 所有可用声音的 style 空间 (按需加载声音并计算, 结果缓存)
+    pub fn style_space(&self) -> Arc<StyleSpace> {
+        if let Some(space) = self.space.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            return Arc::clone(space);
+        }
+        let means = self
+            .names()
+            .into_iter()
+            .filter_map(|name| self.get(&name).ok().map(|table| (name, style_space::mean_style(table.as_slice()))))
+            .collect();
+        let genders = voices::load_index(&self.dir)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|(name, entry)| Some((name.clone(), voices::VoiceInfo::from_entry(name, entry).gender?)))
+            .collect();
+        let space = Arc::new(StyleSpace::new(means, &genders));
+        *self.space.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::clone(&space));
+        space
+    }
+
+    /// 并行加载全部声音
+    pub fn preload_all(&self) {
+        let slots: Vec<(String, Arc<VoiceSlot>)> = self
+            .slots
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(name, slot)| (name.clone(), Arc::clone(slot)))
+            .collect();
+        if slots.is_empty() {
+            return;
+        }
+
+        let threads = thread::available_parallelism().map_or(4, |n| n.get()).min(slots.len());
+        let chunk_size = slots.len().div_ceil(threads);
+        thread::scope(|scope| {
+            for chunk in slots.chunks(chunk_size) {
+                scope.spawn(move || {
+                    for (name, slot) in chunk {
+                        let _ = slot.load(name);
+                    }
+                });
+            }
+        });
+    }
+
+    /// 后台线程预加载并计算 style 空间, 完成后记录耗时和常驻内存
+    pub fn preload_in_background(store: Arc<Self>) {
+        thread::spawn(move || {
+            let started = Instant::now();
+            store.preload_all();
+            info!(
+                "✅ 预加载 {}/{} 个声音 ({:.0?}, 常驻内存 {})",
+                store.loaded(),
+                store.len(),
+                started.elapsed(),
+                describe_memory()
+            );
+            let started = Instant::now();
+            let space = store.style_space();
+            info!("✅ 声音相似度矩阵: {}×{} ({:.0?})", space.len(), space.len(), started.elapsed());
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_store(name: &str) -> (PathBuf, VoiceStore) {
+        let dir = std::env::temp_dir().join(format!("tts_voice_store_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let bundled = Path::new(voices::VOICES_DIR);
+        fs::copy(bundled.join("af_alloy.bin"), dir.join("af_alloy.bin")).unwrap();
+        fs::copy(bundled.join("am_adam.bin"), dir.join("am_adam.bin")).unwrap();
+        fs::write(dir.join("zz_store_broken.bin"), [0u8; 100]).unwrap();
+        fs::write(
+            dir.join("index.json"),
+            r#"{"af_alloy": {"file": "af_alloy.bin"}, "am_adam": {"file": "am_adam.bin"}, "zz_store_broken": {"file": "zz_store_broken.bin"}}"#,
+        )
+        .unwrap();
+        let store = VoiceStore::open(&dir).unwrap();
+        (dir, store)
+    }
+
+    #[test]
+    fn test_lazy_loading() {
+        let (dir, store) = temp_store("lazy");
+        assert_eq!((store.len(), store.loaded()), (3, 0));
+
+        let table = store.get("af_alloy").unwrap();
+        assert_eq!(store.loaded(), 1);
+        assert!(Arc::ptr_eq(&table, &store.get("af_alloy").unwrap()));
+
+        assert!(matches!(store.get("nope"), Err(VoiceError::NotFound(_))));
+        assert!(matches!(store.get("zz_store_broken"), Err(VoiceError::LoadFailed { .. })));
+        assert!(voices::load_errors().contains_key("zz_store_broken"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_bundled_style_space() {
+        let store = VoiceStore::open(voices::VOICES_DIR).unwrap();
+        let space = store.style_space();
+        assert_eq!(space.len(), 54);
+
+        let names: Vec<&str> = space.axes().iter().map(|axis| axis.name.as_str()).collect();
+        assert_eq!(names.len(), 9);
+        assert_eq!(names.last(), Some(&"gender"));
+        let gender = space.axes().last().unwrap();
+        assert!(gender.positive.iter().all(|name| name.chars().nth(1) == Some('f')));
+        assert!(gender.negative.iter().all(|name| name.chars().nth(1) == Some('m')));
+
+        let nearest = space.nearest("bm_george", 3, |name| name.starts_with("bf_")).unwrap();
+        assert_eq!(nearest.len(), 3);
+        assert!(nearest[0].similarity >= nearest[2].similarity);
+    }
+
+    #[test]
+    fn test_preload_and_insert() {
+        let (dir, store) = temp_store("preload");
+        store.preload_all();
+        assert_eq!(store.loaded(), 2);
+
+        let values = vec![0.5f32; crate::voices::STYLE_ROWS * crate::voices::STYLE_DIM];
+        store.insert("af_new", StyleTable::from_values(values).unwrap());
+        assert_eq!(store.get("af_new").unwrap().row(0)[0], 0.5);
+        assert_eq!(store.style_space().len(), 3);
+        assert!(store.remove("af_new"));
+        assert_eq!(store.style_space().len(), 2);
+        assert!(!store.contains("af_new"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fingerprint_after_recreate() {
+        let (dir, store) = temp_store("fingerprint");
+        let alloy = store.fingerprint("af_alloy").unwrap();
+        assert_eq!(alloy, store.fingerprint("af_alloy").unwrap());
+        assert_ne!(alloy, store.fingerprint("am_adam").unwrap());
+        assert!(matches!(store.fingerprint("nope"), Err(VoiceError::NotFound(_))));
+
+        // 同内容 → 同指纹 (无论从文件加载还是注册)
+        store.insert("af_copy", StyleTable::from_values(store.get("af_alloy").unwrap().as_slice().to_vec()).unwrap());
+        assert_eq!(store.fingerprint("af_copy").unwrap(), alloy);
+
+        // 删除后用不同内容重新注册同名声音: 指纹变化
+        let values = |value: f32| vec![value; voices::STYLE_ROWS * voices::STYLE_DIM];
+        store.insert("my_voice", StyleTable::from_values(values(0.1)).unwrap());
+        let original = store.fingerprint("my_voice").unwrap();
+        assert!(store.remove("my_voice"));
+        assert!(store.fingerprint("my_voice").is_err());
+        store.insert("my_voice", StyleTable::from_values(values(0.2)).unwrap());
+        assert_ne!(store.fingerprint("my_voice").unwrap(), original);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
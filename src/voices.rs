@@ -0,0 +1,484 @@
+//! 声音目录
+//!
+//! data/voices/index.json 记录每个声音的文件和元数据:
+//!
+//! ```json
+//! "af_alloy": {
+//!   "file": "af_alloy.bin", "shape": [510, 256],
+//!   "language": "en", "accent": "us", "gender": "female",
+//!   "display_name": "Alloy"
+//! }
+//! ```
+//!
+//! 元数据缺失时按 Kokoro 命名规则推断 (首字母 = 语言/口音, 第二个字母 = 性别)。
+//! 默认声音和别名在 aliases.json 中配置 (见 `voice_alias`)。
+//!
+//! 用户上传的声音标记 `"custom": true`, 只有这些声音可以删除。
+//! 声音文件和 index.json 都先写临时文件再 rename, 中途失败不会留下半个文件。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use thiserror::Error;
+
+use crate::language::Language;
+use crate::style_table::StyleTable;
+use crate::voice_alias;
+
+/// 声音目录
+pub const VOICES_DIR: &str = "data/voices";
+
+/// style 表行数 (按 token 数索引)
+pub const STYLE_ROWS: usize = 510;
+
+/// style 向量维度
+pub const STYLE_DIM: usize = 256;
+
+/// .bin 声音文件大小: STYLE_ROWS × STYLE_DIM 个小端 f32
+pub const VOICE_FILE_BYTES: usize = STYLE_ROWS * STYLE_DIM * 4;
+
+/// 声音名最大长度
+const MAX_NAME_LEN: usize = 64;
+
+/// 串行化 index.json 的读-改-写
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// 加载失败的声音: 名称 → 错误信息 (/health 展示)
+static LOAD_ERRORS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Error)]
+pub enum VoiceError {
+    #[error("声音名无效: '{0}' (只允许小写字母、数字和下划线, 最多 64 个字符)")]
+    InvalidName(String),
+    #[error("声音已存在: {0}")]
+    AlreadyExists(String),
+    #[error("声音不存在: {0}")]
+    NotFound(String),
+    #[error("内置声音不能删除: {0}")]
+    BuiltIn(String),
+    #[error("声音文件大小错误: 需要 {expected} 字节 ({STYLE_ROWS}×{STYLE_DIM} 个 f32), 实际 {actual} 字节")]
+    InvalidSize { expected: usize, actual: usize },
+    #[error("声音数据包含非有限值: 第 {row} 行第 {column} 维 = {value}")]
+    NonFinite { row: usize, column: usize, value: f32 },
+    #[error("声音 {name} 加载失败: {reason}")]
+    LoadFailed { name: String, reason: String },
+    #[error("声音索引错误: {0}")]
+    Index(String),
+    #[error("声音文件读写失败: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// 各语言的试听文本
+const PREVIEW_EN: &str = "Hello! This is a short preview of my voice.";
+const PREVIEW_ZH: &str = "你好，这是我的声音试听。";
+const PREVIEW_JA: &str = "こんにちは、これはわたしのこえのサンプルです。";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    Female,
+    Male,
+}
+
+/// index.json 中的一条记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shape: Option<Vec<usize>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gender: Option<Gender>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// 用户上传的声音 (可以删除)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub custom: bool,
+    /// 其他字段原样保留
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// 上传声音时提供的元数据 (缺省按声音名推断)
+#[derive(Debug, Clone, Default)]
+pub struct VoiceMetadata {
+    pub language: Option<String>,
+    pub accent: Option<String>,
+    pub gender: Option<Gender>,
+    pub display_name: Option<String>,
+}
+
+/// GET /voices 返回的声音信息
+#[derive(Debug, Clone, Serialize)]
+pub struct VoiceInfo {
+    pub name: String,
+    /// ISO 639-1 语言代码: en / zh / ja ...
+    pub language: String,
+    /// 口音 (地区): us / gb / br
+    pub accent: Option<String>,
+    pub gender: Option<Gender>,
+    pub display_name: String,
+    /// 全局或某个语言的默认声音
+    pub default: bool,
+    pub custom: bool,
+    pub preview_url: String,
+}
+
+/// 按 Kokoro 命名推断语言和口音: af_ → (en, us), zf_ → (zh, -)
+fn infer_language(name: &str) -> (&'static str, Option<&'static str>) {
+    match name.chars().next() {
+        Some('a') => ("en", Some("us")),
+        Some('b') => ("en", Some("gb")),
+        Some('e') => ("es", None),
+        Some('f') => ("fr", None),
+        Some('h') => ("hi", None),
+        Some('i') => ("it", None),
+        Some('j') => ("ja", None),
+        Some('p') => ("pt", Some("br")),
+        Some('z') => ("zh", None),
+        _ => ("en", None),
+    }
+}
+
+/// 按 Kokoro 命名推断性别: af_ → female, am_ → male
+fn infer_gender(name: &str) -> Option<Gender> {
+    match name.chars().nth(1) {
+        Some('f') => Some(Gender::Female),
+        Some('m') => Some(Gender::Male),
+        _ => None,
+    }
+}
+
+/// 显示名: af_alloy → Alloy
+fn infer_display_name(name: &str) -> String {
+    let base = name.split_once('_').map(|(_, rest)| rest).unwrap_or(name);
+    let mut chars = base.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name.to_string(),
+    }
+}
+
+impl VoiceInfo {
+    pub fn from_entry(name: &str, entry: &IndexEntry) -> Self {
+        let (language, accent) = infer_language(name);
+        Self {
+            name: name.to_string(),
+            language: entry.language.clone().unwrap_or_else(|| language.to_string()),
+            accent: entry.accent.clone().or_else(|| accent.map(str::to_string)),
+            gender: entry.gender.or_else(|| infer_gender(name)),
+            display_name: entry.display_name.clone().unwrap_or_else(|| infer_display_name(name)),
+            default: voice_alias::aliases().is_default(name),
+            custom: entry.custom,
+            preview_url: format!("http://localhost:9527/voices/{}/preview", name),
+        }
+    }
+
+    /// 语言过滤: "en" 匹配所有英语声音, "en-gb" / "en_GB" 只匹配英式
+    pub fn matches_language(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase().replace('_', "-");
+        match filter.split_once('-') {
+            Some((language, accent)) => self.language == language && self.accent.as_deref() == Some(accent),
+            None => self.language == filter,
+        }
+    }
+}
+
+/// 读取 index.json (按声音名排序)
+pub fn load_index<P: AsRef<Path>>(voices_dir: P) -> Result<BTreeMap<String, IndexEntry>> {
+    let index_path = voices_dir.as_ref().join("index.json");
+    let content = fs::read_to_string(&index_path)
+        .with_context(|| format!("无法读取 index.json: {:?}", index_path))?;
+    serde_json::from_str(&content).with_context(|| format!("index.json 格式错误: {:?}", index_path))
+}
+
+/// 先写同目录下的临时文件再 rename, 读者不会看到写了一半的文件
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("voice");
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name));
+    let result = File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+    match result.and_then(|_| fs::rename(&tmp_path, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            Err(e)
+        }
+    }
+}
+
+fn write_index(voices_dir: &Path, index: &BTreeMap<String, IndexEntry>) -> Result<(), VoiceError> {
+    let json = serde_json::to_string_pretty(index).map_err(|e| VoiceError::Index(e.to_string()))?;
+    write_atomic(&voices_dir.join("index.json"), json.as_bytes())?;
+    Ok(())
+}
+
+/// 声音名: 小写字母、数字、下划线 (同时是文件名, 不能含路径分隔符)
+pub fn validate_name(name: &str) -> Result<(), VoiceError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    match valid {
+        true => Ok(()),
+        false => Err(VoiceError::InvalidName(name.to_string())),
+    }
+}
+
+/// 解析并严格校验 .bin 声音数据: 大小必须正好是 510×256 个 f32, 不允许 NaN/Inf
+pub fn parse_voice_bytes(bytes: &[u8]) -> Result<StyleTable, VoiceError> {
+    StyleTable::from_bytes(bytes)
+}
+
+/// 映射并严格校验 .bin 声音文件 (截断、多余字节、NaN/Inf 都视为损坏)
+pub fn load_voice_file<P: AsRef<Path>>(path: P) -> Result<StyleTable, VoiceError> {
+    StyleTable::open(path)
+}
+
+/// 记录声音加载失败
+pub fn record_load_error(name: &str, error: &VoiceError) {
+    LOAD_ERRORS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), error.to_string());
+}
+
+/// 声音重新可用 (上传/删除) 时清除之前的错误
+pub fn clear_load_error(name: &str) {
+    LOAD_ERRORS.lock().unwrap_or_else(|e| e.into_inner()).remove(name);
+}
+
+/// 所有加载失败的声音
+pub fn load_errors() -> BTreeMap<String, String> {
+    LOAD_ERRORS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 添加用户声音: 校验数据, 写入 <name>.bin 并登记到 index.json, 返回 style 表
+pub fn add_custom_voice<P: AsRef<Path>>(
+    voices_dir: P,
+    name: &str,
+    metadata: VoiceMetadata,
+    bytes: &[u8],
+) -> Result<StyleTable, VoiceError> {
+    validate_name(name)?;
+    let table = parse_voice_bytes(bytes)?;
+
+    let voices_dir = voices_dir.as_ref();
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut index = load_index(voices_dir).map_err(|e| VoiceError::Index(format!("{:#}", e)))?;
+    if index.contains_key(name) {
+        return Err(VoiceError::AlreadyExists(name.to_string()));
+    }
+
+    let file = format!("{}.bin", name);
+    write_atomic(&voices_dir.join(&file), bytes)?;
+
+    let entry = IndexEntry {
+        file,
+        shape: Some(vec![STYLE_ROWS, STYLE_DIM]),
+        language: metadata.language,
+        accent: metadata.accent,
+        gender: metadata.gender,
+        display_name: metadata.display_name,
+        custom: true,
+        ..Default::default()
+    };
+    index.insert(name.to_string(), entry);
+    if let Err(e) = write_index(voices_dir, &index) {
+        let _ = fs::remove_file(voices_dir.join(format!("{}.bin", name)));
+        return Err(e);
+    }
+    Ok(table)
+}
+
+/// 删除用户声音 (内置声音不能删除)
+pub fn remove_custom_voice<P: AsRef<Path>>(voices_dir: P, name: &str) -> Result<(), VoiceError> {
+    let voices_dir = voices_dir.as_ref();
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut index = load_index(voices_dir).map_err(|e| VoiceError::Index(format!("{:#}", e)))?;
+    match index.get(name) {
+        None => return Err(VoiceError::NotFound(name.to_string())),
+        Some(entry) if !entry.custom => return Err(VoiceError::BuiltIn(name.to_string())),
+        Some(_) => {}
+    }
+
+    // 先更新索引再删文件: 中途失败最多留下一个没人引用的文件
+    let entry = index.remove(name).expect("上面已检查存在");
+    write_index(voices_dir, &index)?;
+    clear_load_error(name);
+    match fs::remove_file(voices_dir.join(&entry.file)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// 列出声音, 可按语言和性别过滤
+pub fn list<P: AsRef<Path>>(voices_dir: P, language: Option<&str>, gender: Option<Gender>) -> Result<Vec<VoiceInfo>> {
+    Ok(load_index(voices_dir)?
+        .iter()
+        .map(|(name, entry)| VoiceInfo::from_entry(name, entry))
+        .filter(|voice| language.is_none_or(|language| voice.matches_language(language)))
+        .filter(|voice| gender.is_none_or(|gender| voice.gender == Some(gender)))
+        .collect())
+}
+
+/// 声音的试听文本 (按该声音使用的 G2P 语言)
+///
+/// 西/法/印地/意/葡语声音没有对应的前端 (espeak-ng 固定按 en-us 音素化),
+/// 用本族语句子会读成英语发音规则的乱码, 所以这些声音试听的是英文句子。
+pub fn preview_text(name: &str) -> &'static str {
+    match Language::from_voice(name) {
+        Language::English => PREVIEW_EN,
+        Language::Mandarin => PREVIEW_ZH,
+        Language::Japanese => PREVIEW_JA,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_index_metadata() {
+        let voices = list(VOICES_DIR, None, None).unwrap();
+        assert_eq!(voices.len(), 54);
+        // 全局默认 + 每个语言的默认声音
+        assert_eq!(voices.iter().filter(|v| v.default).count(), 9);
+
+        let alloy = voices.iter().find(|v| v.name == "af_alloy").unwrap();
+        assert_eq!((alloy.language.as_str(), alloy.accent.as_deref()), ("en", Some("us")));
+        assert_eq!((alloy.gender, alloy.display_name.as_str(), alloy.default), (Some(Gender::Female), "Alloy", true));
+        assert_eq!(alloy.name, voice_alias::aliases().default_voice());
+        assert!(voices.iter().any(|v| v.name == "zf_xiaobei" && v.default));
+        assert!(alloy.preview_url.ends_with("/voices/af_alloy/preview"));
+    }
+
+    #[test]
+    fn test_filters() {
+        let british_men = list(VOICES_DIR, Some("en-GB"), Some(Gender::Male)).unwrap();
+        let names: Vec<&str> = british_men.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["bm_daniel", "bm_fable", "bm_george", "bm_lewis"]);
+
+        let chinese = list(VOICES_DIR, Some("zh"), None).unwrap();
+        assert!(!chinese.is_empty() && chinese.iter().all(|v| v.name.starts_with('z')));
+    }
+
+    /// 复制内置 index.json 到临时目录
+    fn temp_voices_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("tts_voices_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(Path::new(VOICES_DIR).join("index.json"), dir.join("index.json")).unwrap();
+        dir
+    }
+
+    fn voice_bytes(value: f32) -> Vec<u8> {
+        std::iter::repeat_n(value.to_le_bytes(), STYLE_ROWS * STYLE_DIM).flatten().collect()
+    }
+
+    #[test]
+    fn test_bundled_index_round_trips() {
+        let path = Path::new(VOICES_DIR).join("index.json");
+        let original = fs::read_to_string(&path).unwrap();
+        let index = load_index(VOICES_DIR).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&index).unwrap(), original);
+    }
+
+    #[test]
+    fn test_parse_voice_bytes() {
+        assert_eq!(parse_voice_bytes(&voice_bytes(0.5)).unwrap().as_slice().len(), STYLE_ROWS * STYLE_DIM);
+
+        let err = parse_voice_bytes(&voice_bytes(0.5)[4..]).unwrap_err();
+        assert!(matches!(err, VoiceError::InvalidSize { actual, .. } if actual == VOICE_FILE_BYTES - 4));
+
+        let mut bytes = voice_bytes(0.5);
+        let offset = (3 * STYLE_DIM + 7) * 4;
+        bytes[offset..offset + 4].copy_from_slice(&f32::NAN.to_le_bytes());
+        assert!(matches!(parse_voice_bytes(&bytes), Err(VoiceError::NonFinite { row: 3, column: 7, .. })));
+
+        // 多出的字节同样视为损坏
+        let mut oversized = voice_bytes(0.5);
+        oversized.extend_from_slice(&[0, 0]);
+        assert!(matches!(parse_voice_bytes(&oversized), Err(VoiceError::InvalidSize { .. })));
+    }
+
+    #[test]
+    fn test_bundled_voices_are_valid() {
+        for (name, entry) in load_index(VOICES_DIR).unwrap() {
+            let vectors = load_voice_file(Path::new(VOICES_DIR).join(&entry.file))
+                .unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(vectors.as_slice().len(), STYLE_ROWS * STYLE_DIM);
+        }
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let dir = temp_voices_dir("errors");
+        fs::write(dir.join("broken.bin"), &voice_bytes(0.5)[..1000]).unwrap();
+        let err = load_voice_file(dir.join("broken.bin")).unwrap_err();
+        assert!(matches!(err, VoiceError::InvalidSize { actual: 1000, .. }));
+
+        record_load_error("zz_broken", &err);
+        assert!(load_errors()["zz_broken"].contains("1000"));
+        assert!(load_errors().contains_key("zz_broken"));
+        clear_load_error("zz_broken");
+        assert!(!load_errors().contains_key("zz_broken"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_add_and_remove_custom_voice() {
+        let dir = temp_voices_dir("custom");
+        let metadata = VoiceMetadata { display_name: Some("Mine".to_string()), ..Default::default() };
+
+        assert!(matches!(
+            add_custom_voice(&dir, "../evil", metadata.clone(), &voice_bytes(0.1)),
+            Err(VoiceError::InvalidName(_))
+        ));
+        assert!(matches!(
+            add_custom_voice(&dir, "af_alloy", metadata.clone(), &voice_bytes(0.1)),
+            Err(VoiceError::AlreadyExists(_))
+        ));
+
+        add_custom_voice(&dir, "af_mine", metadata, &voice_bytes(0.1)).unwrap();
+        assert_eq!(fs::read(dir.join("af_mine.bin")).unwrap().len(), VOICE_FILE_BYTES);
+        let voices = list(&dir, None, None).unwrap();
+        let mine = voices.iter().find(|v| v.name == "af_mine").unwrap();
+        assert!(mine.custom);
+        assert_eq!((mine.display_name.as_str(), mine.gender), ("Mine", Some(Gender::Female)));
+
+        assert!(matches!(remove_custom_voice(&dir, "af_alloy"), Err(VoiceError::BuiltIn(_))));
+        remove_custom_voice(&dir, "af_mine").unwrap();
+        assert!(!dir.join("af_mine.bin").exists());
+        assert_eq!(list(&dir, None, None).unwrap().len(), 54);
+        assert!(matches!(remove_custom_voice(&dir, "af_mine"), Err(VoiceError::NotFound(_))));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_inferred_metadata() {
+        let entry: IndexEntry = serde_json::from_str(r#"{"file": "pm_alex.bin"}"#).unwrap();
+        let voice = VoiceInfo::from_entry("pm_alex", &entry);
+        assert_eq!((voice.language.as_str(), voice.accent.as_deref()), ("pt", Some("br")));
+        assert_eq!((voice.gender, voice.display_name.as_str(), voice.default), (Some(Gender::Male), "Alex", false));
+    }
+
+    #[test]
+    fn test_preview_text() {
+        assert_eq!(preview_text("zf_xiaobei"), PREVIEW_ZH);
+        assert_eq!(preview_text("jf_alpha"), PREVIEW_JA);
+        // 没有专用前端的语言试听英文句子
+        for name in ["af_alloy", "ef_dora", "ff_siwis", "hf_alpha", "if_sara", "pm_alex"] {
+            assert_eq!(preview_text(name), PREVIEW_EN, "{}", name);
+        }
+    }
+}
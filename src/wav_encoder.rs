@@ -1,14 +1,107 @@
-/// WAV Audio Encoder
-///
-/// 将 f32 音频样本编码为 WAV 格式
+//! WAV Audio Encoder
+//!
+//! 将 f32 音频样本编码为 WAV 格式, 样本格式可选:
+//!
+//! - `s16` (默认): 16-bit PCM, 加 TPDF 抖动后四舍五入, [-1.0, 1.0] → [-32768, 32767]
+//! - `s24`: 24-bit PCM, 四舍五入 (量化误差远低于模型输出的噪声, 不加抖动)
+//! - `f32`: 32-bit 浮点, 原样保存
 
-use anyhow::Result;
-use hound::{WavWriter, WavSpec, SampleFormat};
+use anyhow::{ensure, Result};
+use hound::{WavReader, WavWriter, WavSpec, SampleFormat};
+use serde::{Deserialize, Serialize};
 use std::io::Cursor;
 
+/// WAV 样本格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PcmFormat {
+    #[default]
+    S16,
+    S24,
+    F32,
+}
+
+impl PcmFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            PcmFormat::S16 => "s16",
+            PcmFormat::S24 => "s24",
+            PcmFormat::F32 => "f32",
+        }
+    }
+}
+
+/// 抖动噪声的伪随机数 (xorshift32, 固定种子: 相同输入得到相同输出, 缓存结果可复现)
+struct Dither(u32);
+
+impl Dither {
+    fn new() -> Self {
+        Self(0x9E37_79B9)
+    }
+
+    /// [0, 1) 均匀分布
+    fn uniform(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// 三角分布 (TPDF), 范围 (-1, 1) LSB
+    fn next(&mut self) -> f32 {
+        self.uniform() - self.uniform()
+    }
+}
+
+/// 量化到 `bits` 位整数: 满量程 2^(bits-1), 四舍五入并限幅
+fn quantize(sample: f32, bits: u32, dither: Option<&mut Dither>) -> i32 {
+    let full_scale = (1u32 << (bits - 1)) as f64;
+    let scaled = sample.clamp(-1.0, 1.0) as f64 * full_scale;
+    // 正好落在整数上的样本 (如数字静音) 没有量化误差, 不加抖动
+    let noise = match dither {
+        Some(dither) if scaled.fract() != 0.0 => dither.next() as f64,
+        _ => 0.0,
+    };
+    (scaled + noise).round().clamp(-full_scale, full_scale - 1.0) as i32
+}
+
 /// 编码 WAV 音频到内存
 /// 使用 16-bit PCM 格式 (浏览器兼容)
 pub fn encode_wav(audio: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+    encode_wav_pcm16(&to_pcm16(audio), sample_rate)
+}
+
+/// 按指定的样本格式编码 WAV
+pub fn encode_wav_as(audio: &[f32], sample_rate: u32, format: PcmFormat) -> Result<Vec<u8>> {
+    let (bits_per_sample, sample_format) = match format {
+        PcmFormat::S16 => return encode_wav(audio, sample_rate),
+        PcmFormat::S24 => (24, SampleFormat::Int),
+        PcmFormat::F32 => (32, SampleFormat::Float),
+    };
+    let spec = WavSpec { channels: 1, sample_rate, bits_per_sample, sample_format };
+
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut writer = WavWriter::new(&mut cursor, spec)?;
+        for &sample in audio {
+            match format {
+                PcmFormat::F32 => writer.write_sample(sample)?,
+                _ => writer.write_sample(quantize(sample, 24, None))?,
+            }
+        }
+        writer.finalize()?;
+    }
+    Ok(cursor.into_inner())
+}
+
+/// f32 样本转换为 16-bit PCM (TPDF 抖动 + 四舍五入)
+pub fn to_pcm16(audio: &[f32]) -> Vec<i16> {
+    let mut dither = Dither::new();
+    audio.iter().map(|&sample| quantize(sample, 16, Some(&mut dither)) as i16).collect()
+}
+
+/// 16-bit PCM 样本编码为 WAV
+pub fn encode_wav_pcm16(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>> {
     // WAV 规格 - 使用 16-bit PCM (浏览器标准格式)
     let spec = WavSpec {
         channels: 1,                    // 单声道
@@ -22,13 +115,8 @@ pub fn encode_wav(audio: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
     {
         let mut writer = WavWriter::new(&mut cursor, spec)?;
 
-        // 写入所有样本 (f32 → i16 转换)
-        for &sample in audio {
-            // 限制范围到 [-1.0, 1.0]
-            let clamped = sample.max(-1.0).min(1.0);
-            // 转换为 16-bit PCM: [-1.0, 1.0] → [-32768, 32767]
-            let pcm_sample = (clamped * 32767.0) as i16;
-            writer.write_sample(pcm_sample)?;
+        for &sample in samples {
+            writer.write_sample(sample)?;
         }
 
         writer.finalize()?;
@@ -37,24 +125,23 @@ pub fn encode_wav(audio: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
     Ok(cursor.into_inner())
 }
 
-/// 编码 WAV 音频到文件 (保留用于未来直接文件输出)
-#[allow(dead_code)]
-pub fn encode_wav_file(audio: &[f32], sample_rate: u32, path: &str) -> Result<()> {
-    let spec = WavSpec {
-        channels: 1,
-        sample_rate,
-        bits_per_sample: 32,
-        sample_format: SampleFormat::Float,
-    };
-
-    let mut writer = WavWriter::create(path, spec)?;
-
-    for &sample in audio {
-        writer.write_sample(sample)?;
-    }
+/// 是否是 16-bit 单声道整数 WAV (可以无损转为 FLAC)
+pub fn is_pcm16(data: &[u8]) -> bool {
+    WavReader::new(Cursor::new(data))
+        .map(|reader| {
+            let spec = reader.spec();
+            spec.channels == 1 && spec.bits_per_sample == 16 && spec.sample_format == SampleFormat::Int
+        })
+        .unwrap_or(false)
+}
 
-    writer.finalize()?;
-    Ok(())
+/// 读取 16-bit 单声道 WAV, 返回 (样本, 采样率)
+pub fn decode_wav_pcm16(data: &[u8]) -> Result<(Vec<i16>, u32)> {
+    let reader = WavReader::new(Cursor::new(data))?;
+    let spec = reader.spec();
+    ensure!(is_pcm16(data), "只支持 16-bit 单声道 WAV");
+    let samples = reader.into_samples::<i16>().collect::<Result<Vec<_>, _>>()?;
+    Ok((samples, spec.sample_rate))
 }
 
 #[cfg(test)]
@@ -102,5 +189,80 @@ mod tests {
 
         // 验证非空
         assert!(wav_bytes.len() > 44);
+
+        // 读回的 PCM 与编码前一致
+        let (samples, rate) = decode_wav_pcm16(&wav_bytes).unwrap();
+        assert_eq!(rate, sample_rate);
+        assert_eq!(samples, to_pcm16(&tone));
+        assert_eq!(encode_wav_pcm16(&samples, rate).unwrap(), wav_bytes);
+    }
+
+    /// 解码 WAV 为 [-1.0, 1.0] 的浮点样本 (按满量程 2^(bits-1) 缩放)
+    fn decode(wav: &[u8]) -> (WavSpec, Vec<f32>) {
+        let reader = WavReader::new(Cursor::new(wav)).unwrap();
+        let spec = reader.spec();
+        let samples = match spec.sample_format {
+            SampleFormat::Float => reader.into_samples::<f32>().map(Result::unwrap).collect(),
+            SampleFormat::Int => {
+                let scale = (1u32 << (spec.bits_per_sample - 1)) as f32;
+                reader.into_samples::<i32>().map(|s| s.unwrap() as f32 / scale).collect()
+            }
+        };
+        (spec, samples)
+    }
+
+    /// 覆盖整个范围的测试信号 (含满量程和非整数 LSB 的值)
+    fn ramp() -> Vec<f32> {
+        (0..=20000).map(|i| i as f32 / 10000.0 - 1.0).chain([1.0, -1.0, 0.0, 1e-6, -1e-6]).collect()
+    }
+
+    #[test]
+    fn test_full_scale_mapping() {
+        assert_eq!(to_pcm16(&[-1.0, 1.0, 0.0, -2.0, 2.0]), vec![-32768, 32767, 0, -32768, 32767]);
+        assert_eq!(quantize(-1.0, 24, None), -8_388_608);
+        assert_eq!(quantize(1.0, 24, None), 8_388_607);
+        // 四舍五入而不是向零截断
+        assert_eq!(quantize(-0.7 / 32768.0, 16, None), -1);
+        assert_eq!(quantize(0.7 / 32768.0, 16, None), 1);
+    }
+
+    #[test]
+    fn test_round_trip_error_bounds() {
+        let audio = ramp();
+        let lsb16 = 1.0 / 32768.0;
+        let lsb24 = 1.0 / 8_388_608.0;
+
+        // 16-bit: 四舍五入 0.5 LSB + 抖动最多 1 LSB (满量程正端限幅多 1 LSB)
+        let (spec, decoded) = decode(&encode_wav(&audio, 24000).unwrap());
+        assert_eq!(spec.bits_per_sample, 16);
+        for (&x, &y) in audio.iter().zip(&decoded) {
+            assert!((x - y).abs() <= 1.5 * lsb16 + f32::EPSILON, "{} → {}", x, y);
+        }
+
+        // 24-bit: 只有四舍五入误差
+        let (spec, decoded) = decode(&encode_wav_as(&audio, 24000, PcmFormat::S24).unwrap());
+        assert_eq!(spec.bits_per_sample, 24);
+        for (&x, &y) in audio.iter().zip(&decoded) {
+            assert!((x - y).abs() <= 1.0 * lsb24, "{} → {}", x, y);
+        }
+
+        // 32-bit 浮点: 无损
+        let (spec, decoded) = decode(&encode_wav_as(&audio, 24000, PcmFormat::F32).unwrap());
+        assert_eq!(spec.sample_format, SampleFormat::Float);
+        assert_eq!(decoded, audio);
+    }
+
+    #[test]
+    fn test_dither_is_unbiased() {
+        // 截断会让 DC 偏向零; 抖动 + 四舍五入的平均误差接近 0, 且不会卡在同一个值上
+        let level = 0.3 + 0.37 / 32768.0;
+        let (_, decoded) = decode(&encode_wav(&vec![level; 48000], 24000).unwrap());
+        let mean = decoded.iter().map(|&y| y as f64).sum::<f64>() / decoded.len() as f64;
+        assert!(((mean - level as f64) * 32768.0).abs() < 0.02, "偏差 {} LSB", (mean - level as f64) * 32768.0);
+        assert!(decoded.iter().any(|&y| y != decoded[0]));
+
+        // 数字静音保持静音, 相同输入输出相同
+        assert!(to_pcm16(&[0.0; 100]).iter().all(|&s| s == 0));
+        assert_eq!(to_pcm16(&[level; 100]), to_pcm16(&[level; 100]));
     }
 }
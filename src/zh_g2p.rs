@@ -0,0 +1,784 @@
+//! 普通话 G2P 前端 - zf_/zm_ 声音
+//!
+//! 流程:
+//! 1. 文本规范化: 数字、日期、时间、百分比 → 汉字读法 (2025年 → 二零二五年)
+//! 2. jieba 分词 (内置离线词典)
+//! 3. 拼音: 词语表 (多音字/轻声) 优先, 其次单字默认读音
+//! 4. 变调: 三声连读、"一"、"不"
+//! 5. 拼音 → Kokoro 音素 (声调用 → ↗ ↓ ↘ 标记)
+//!
+//! 拼音词典位于 data/zh/, 编译时嵌入二进制。
+
+use jieba_rs::Jieba;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::language::map_cjk_punctuation;
+
+const CHAR_TABLE: &str = include_str!("../data/zh/pinyin_chars.txt");
+const PHRASE_TABLE: &str = include_str!("../data/zh/pinyin_phrases.txt");
+
+const DIGITS: [char; 10] = ['零', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+/// 带声调的拼音音节 (tone: 1-4, 5 = 轻声)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Syllable {
+    base: String,
+    tone: u8,
+}
+
+impl Syllable {
+    fn parse(s: &str) -> Option<Self> {
+        let tone = s.chars().last()?.to_digit(10)? as u8;
+        if !(1..=5).contains(&tone) {
+            return None;
+        }
+        let base = s[..s.len() - 1].replace('v', "ü");
+        if base.is_empty() {
+            return None;
+        }
+        Some(Self { base, tone })
+    }
+}
+
+fn load_char_table() -> HashMap<char, Syllable> {
+    let mut table = HashMap::new();
+    for line in CHAR_TABLE.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((pinyin, chars)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some(syllable) = Syllable::parse(pinyin) else {
+            warn!("⚠️ 无效拼音: {}", pinyin);
+            continue;
+        };
+        for c in chars.trim().chars() {
+            table.entry(c).or_insert_with(|| syllable.clone());
+        }
+    }
+    table
+}
+
+fn load_phrase_table() -> HashMap<String, Vec<Syllable>> {
+    let mut table = HashMap::new();
+    for line in PHRASE_TABLE.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let Some(word) = parts.next() else {
+            continue;
+        };
+        let syllables: Option<Vec<Syllable>> = parts.map(Syllable::parse).collect();
+        match syllables {
+            Some(s) if s.len() == word.chars().count() => {
+                table.insert(word.to_string(), s);
+            }
+            _ => warn!("⚠️ 词语读音与字数不符: {}", line),
+        }
+    }
+    table
+}
+
+fn build_jieba(phrases: &HashMap<String, Vec<Syllable>>) -> Jieba {
+    let mut jieba = Jieba::new();
+    // 确保多音词不会被切开
+    for word in phrases.keys() {
+        jieba.add_word(word, None, None);
+    }
+    jieba
+}
+
+lazy_static! {
+    static ref CHAR_PINYIN: HashMap<char, Syllable> = load_char_table();
+    static ref PHRASE_PINYIN: HashMap<String, Vec<Syllable>> = load_phrase_table();
+    static ref JIEBA: Jieba = build_jieba(&PHRASE_PINYIN);
+}
+
+/// 是否为 CJK 统一表意文字
+pub fn is_han(c: char) -> bool {
+    matches!(c as u32, 0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF)
+}
+
+// ============================================================
+// 文本规范化: 数字读法
+// ============================================================
+
+/// 0-9999 的读法 (不含前导零)
+fn read_section(n: u64) -> String {
+    let mut out = String::new();
+    let mut pending_zero = false;
+    for (unit_value, unit) in [(1000, "千"), (100, "百"), (10, "十"), (1, "")] {
+        let d = (n / unit_value % 10) as usize;
+        if d == 0 {
+            if !out.is_empty() {
+                pending_zero = true;
+            }
+            continue;
+        }
+        if pending_zero {
+            out.push('零');
+            pending_zero = false;
+        }
+        out.push(DIGITS[d]);
+        out.push_str(unit);
+    }
+    out
+}
+
+/// 整数基数读法: 2025 → 二千零二十五, 15 → 十五
+pub fn read_cardinal(n: u64) -> String {
+    if n == 0 {
+        return "零".to_string();
+    }
+
+    const UNITS: [&str; 5] = ["", "万", "亿", "万亿", "亿亿"];
+    let mut sections = Vec::new();
+    let mut rest = n;
+    while rest > 0 {
+        sections.push(rest % 10000);
+        rest /= 10000;
+    }
+
+    let mut out = String::new();
+    let mut need_zero = false;
+    for (i, &section) in sections.iter().enumerate().rev() {
+        if section == 0 {
+            need_zero = !out.is_empty();
+            continue;
+        }
+        if !out.is_empty() && (need_zero || section < 1000) {
+            out.push('零');
+        }
+        out.push_str(&read_section(section));
+        out.push_str(UNITS[i]);
+        need_zero = false;
+    }
+
+    // 一十五 → 十五
+    if out.starts_with("一十") {
+        out.remove(0);
+    }
+    out
+}
+
+/// 逐位读法: 2025 → 二零二五
+pub fn read_digits(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| DIGITS[d as usize])
+        .collect()
+}
+
+/// 数字串的读法: 过长或以 0 开头 (编号、电话) 逐位读, 否则按基数读
+fn read_number(digits: &str) -> String {
+    if (digits.len() > 1 && digits.starts_with('0')) || digits.len() > 16 {
+        return read_digits(digits);
+    }
+    match digits.parse::<u64>() {
+        Ok(n) => read_cardinal(n),
+        Err(_) => read_digits(digits),
+    }
+}
+
+fn take_digits(chars: &[char], start: usize) -> usize {
+    let mut end = start;
+    while end < chars.len() && chars[end].is_ascii_digit() {
+        end += 1;
+    }
+    end
+}
+
+/// 尝试匹配日期 YYYY-MM-DD / YYYY/MM/DD, 返回 (读法, 结束位置)
+fn match_date(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let y_end = take_digits(chars, start);
+    if y_end - start != 4 {
+        return None;
+    }
+    let sep = *chars.get(y_end)?;
+    if sep != '-' && sep != '/' {
+        return None;
+    }
+    let m_end = take_digits(chars, y_end + 1);
+    if !(1..=2).contains(&(m_end - y_end - 1)) || chars.get(m_end) != Some(&sep) {
+        return None;
+    }
+    let d_end = take_digits(chars, m_end + 1);
+    if !(1..=2).contains(&(d_end - m_end - 1)) {
+        return None;
+    }
+
+    let year: String = chars[start..y_end].iter().collect();
+    let month: u64 = chars[y_end + 1..m_end].iter().collect::<String>().parse().ok()?;
+    let day: u64 = chars[m_end + 1..d_end].iter().collect::<String>().parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some((
+        format!("{}年{}月{}日", read_digits(&year), read_cardinal(month), read_cardinal(day)),
+        d_end,
+    ))
+}
+
+/// 尝试匹配时间 HH:MM(:SS), 返回 (读法, 结束位置)
+fn match_time(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let h_end = take_digits(chars, start);
+    if !(1..=2).contains(&(h_end - start)) || chars.get(h_end) != Some(&':') {
+        return None;
+    }
+    let m_end = take_digits(chars, h_end + 1);
+    if m_end - h_end - 1 != 2 {
+        return None;
+    }
+
+    let hour: u64 = chars[start..h_end].iter().collect::<String>().parse().ok()?;
+    let minute: u64 = chars[h_end + 1..m_end].iter().collect::<String>().parse().ok()?;
+    if hour > 24 || minute > 59 {
+        return None;
+    }
+
+    let mut out = format!("{}点", read_cardinal(hour));
+    if minute > 0 {
+        if minute < 10 {
+            out.push('零');
+        }
+        out.push_str(&read_cardinal(minute));
+        out.push('分');
+    }
+
+    let mut end = m_end;
+    if chars.get(m_end) == Some(&':') {
+        let s_end = take_digits(chars, m_end + 1);
+        if s_end - m_end - 1 == 2 {
+            let second: u64 = chars[m_end + 1..s_end].iter().collect::<String>().parse().ok()?;
+            if second > 0 {
+                out.push_str(&read_cardinal(second));
+                out.push('秒');
+            }
+            end = s_end;
+        }
+    }
+
+    Some((out, end))
+}
+
+/// 中文文本规范化: 把阿拉伯数字、日期、时间、百分比、负号转成汉字读法
+pub fn normalize(text: &str) -> String {
+    // 全角数字/符号 → 半角
+    let chars: Vec<char> = text
+        .chars()
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+            '％' => '%',
+            _ => c,
+        })
+        .collect();
+
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if !c.is_ascii_digit() {
+            // 负号: 前面不是字母数字, 后面紧跟数字
+            let prev_is_alnum = i > 0 && chars[i - 1].is_alphanumeric();
+            if c == '-' && !prev_is_alnum && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()) {
+                out.push('负');
+            } else {
+                out.push(c);
+            }
+            i += 1;
+            continue;
+        }
+
+        if let Some((reading, end)) = match_date(&chars, i).or_else(|| match_time(&chars, i)) {
+            out.push_str(&reading);
+            i = end;
+            continue;
+        }
+
+        let int_end = take_digits(&chars, i);
+        let int_part: String = chars[i..int_end].iter().collect();
+
+        // 小数部分
+        let mut end = int_end;
+        let mut frac_part = String::new();
+        if chars.get(int_end) == Some(&'.')
+            && chars.get(int_end + 1).is_some_and(|n| n.is_ascii_digit())
+        {
+            end = take_digits(&chars, int_end + 1);
+            frac_part = chars[int_end + 1..end].iter().collect();
+        }
+
+        let is_percent = chars.get(end) == Some(&'%');
+        let is_year = frac_part.is_empty()
+            && (int_part.len() == 4 || int_part.len() == 2)
+            && chars.get(end) == Some(&'年');
+
+        let mut reading = if is_year {
+            read_digits(&int_part)
+        } else {
+            read_number(&int_part)
+        };
+        if !frac_part.is_empty() {
+            reading.push('点');
+            reading.push_str(&read_digits(&frac_part));
+        }
+
+        if is_percent {
+            out.push_str("百分之");
+            out.push_str(&reading);
+            end += 1;
+        } else {
+            out.push_str(&reading);
+        }
+        i = end;
+    }
+
+    out
+}
+
+// ============================================================
+// 拼音与变调
+// ============================================================
+
+/// 一个汉字及其读音
+#[derive(Debug, Clone)]
+struct HanSyllable {
+    ch: char,
+    syllable: Syllable,
+}
+
+/// 查词语的拼音: 词语表优先, 其次逐字查表; 未登录字被丢弃并记入 `unresolved`
+fn lookup_word(word: &str, unresolved: &mut Vec<char>) -> Vec<HanSyllable> {
+    if let Some(syllables) = PHRASE_PINYIN.get(word) {
+        return word
+            .chars()
+            .zip(syllables.iter().cloned())
+            .map(|(ch, syllable)| HanSyllable { ch, syllable })
+            .collect();
+    }
+
+    let char_count = word.chars().count();
+    let mut out = Vec::with_capacity(char_count);
+    for (i, ch) in word.chars().enumerate() {
+        match CHAR_PINYIN.get(&ch) {
+            Some(syllable) => {
+                let mut syllable = syllable.clone();
+                // 双字词末尾的 "子"/"头" 读轻声 (桌子、馒头)
+                if char_count == 2 && i == 1 && (ch == '子' || ch == '头') {
+                    syllable.tone = 5;
+                }
+                out.push(HanSyllable { ch, syllable });
+            }
+            None => {
+                warn!("⚠️ 拼音词典缺少汉字: {}", ch);
+                unresolved.push(ch);
+            }
+        }
+    }
+    out
+}
+
+fn is_numeral(c: char) -> bool {
+    DIGITS.contains(&c) || matches!(c, '十' | '百' | '千' | '万' | '亿' | '第')
+}
+
+/// 变调处理 (作用于一个连续汉字片段, 以词为单位)
+fn apply_tone_sandhi(words: &mut [Vec<HanSyllable>]) {
+    // "一" / "不" 变调需要看后一个音节, 先展平成索引
+    let positions: Vec<(usize, usize)> = words
+        .iter()
+        .enumerate()
+        .flat_map(|(w, syl)| (0..syl.len()).map(move |s| (w, s)))
+        .collect();
+
+    let get = |words: &[Vec<HanSyllable>], idx: usize| -> Option<HanSyllable> {
+        positions.get(idx).map(|&(w, s)| words[w][s].clone())
+    };
+
+    for (idx, &(w, s)) in positions.iter().enumerate() {
+        let current = words[w][s].clone();
+        let prev = if idx > 0 { get(words, idx - 1) } else { None };
+        let next = get(words, idx + 1);
+
+        match current.ch {
+            '一' => {
+                // 序数和逐位读的数字 (第一、十一、一九) 保持原调
+                let in_number = prev.as_ref().is_some_and(|p| is_numeral(p.ch))
+                    || next.as_ref().is_some_and(|n| DIGITS.contains(&n.ch));
+                let reduplicated = matches!((&prev, &next), (Some(p), Some(n)) if p.ch == n.ch);
+                let new_tone = if reduplicated {
+                    5 // 看一看
+                } else if in_number {
+                    1
+                } else {
+                    match next.as_ref().map(|n| n.syllable.tone) {
+                        Some(4) | Some(5) => 2,
+                        Some(_) => 4,
+                        None => 1,
+                    }
+                };
+                words[w][s].syllable.tone = new_tone;
+            }
+            '不' if next.as_ref().is_some_and(|n| n.syllable.tone == 4) => {
+                words[w][s].syllable.tone = 2;
+            }
+            _ => {}
+        }
+    }
+
+    // 三声连读: 词内前一个三声变二声
+    for word in words.iter_mut() {
+        for s in 0..word.len().saturating_sub(1) {
+            if word[s].syllable.tone == 3 && word[s + 1].syllable.tone == 3 {
+                word[s].syllable.tone = 2;
+            }
+        }
+    }
+
+    // 跨词: 单音节词与相邻词之间的三声连读 (很好 → hen2 hao3)
+    for w in 0..words.len().saturating_sub(1) {
+        let short = words[w].len() == 1 || words[w + 1].len() == 1;
+        let (left, right) = words.split_at_mut(w + 1);
+        let (Some(last), Some(first)) = (left[w].last_mut(), right[0].first()) else {
+            continue;
+        };
+        if short && last.syllable.tone == 3 && first.syllable.tone == 3 {
+            last.syllable.tone = 2;
+        }
+    }
+}
+
+// ============================================================
+// 拼音 → Kokoro 音素
+// ============================================================
+
+const INITIALS: [(&str, &str); 21] = [
+    ("zh", "ꭧ"),
+    ("ch", "ꭧʰ"),
+    ("sh", "ʂ"),
+    ("b", "p"),
+    ("p", "pʰ"),
+    ("m", "m"),
+    ("f", "f"),
+    ("d", "t"),
+    ("t", "tʰ"),
+    ("n", "n"),
+    ("l", "l"),
+    ("g", "k"),
+    ("k", "kʰ"),
+    ("h", "x"),
+    ("j", "ʨ"),
+    ("q", "ʨʰ"),
+    ("x", "ɕ"),
+    ("r", "ɻ"),
+    ("z", "ʦ"),
+    ("c", "ʦʰ"),
+    ("s", "s"),
+];
+
+fn final_to_ipa(final_: &str) -> Option<&'static str> {
+    Some(match final_ {
+        "a" => "a",
+        "o" => "wo",
+        "e" => "ɤ",
+        "ê" => "ɛ",
+        "ai" => "ai",
+        "ei" => "ei",
+        "ao" => "au",
+        "ou" => "ou",
+        "an" => "an",
+        "en" => "ən",
+        "ang" => "aŋ",
+        "eng" => "əŋ",
+        "ong" => "ʊŋ",
+        "er" => "ɚ",
+        "i" => "i",
+        "ia" => "ja",
+        "ie" => "jɛ",
+        "iao" => "jau",
+        "iou" => "jou",
+        "ian" => "jɛn",
+        "in" => "in",
+        "iang" => "jaŋ",
+        "ing" => "iŋ",
+        "iong" => "jʊŋ",
+        "io" => "jo",
+        "u" => "u",
+        "ua" => "wa",
+        "uo" => "wo",
+        "uai" => "wai",
+        "uei" => "wei",
+        "uan" => "wan",
+        "uen" => "wən",
+        "uang" => "waŋ",
+        "ueng" => "wəŋ",
+        "ü" => "y",
+        "üe" => "ɥɛ",
+        "üan" => "ɥɛn",
+        "ün" => "yn",
+        _ => return None,
+    })
+}
+
+/// 还原零声母音节与缩写韵母: yi → i, wu → u, yue → üe, gui → guei ...
+fn expand_final(initial: &str, rest: &str) -> String {
+    if initial.is_empty() {
+        if let Some(r) = rest.strip_prefix('y') {
+            return match r {
+                "i" | "in" | "ing" => r.to_string(),
+                "u" | "ue" | "uan" | "un" => format!("ü{}", &r[1..]),
+                _ => format!("i{}", r),
+            };
+        }
+        if let Some(r) = rest.strip_prefix('w') {
+            return match r {
+                "u" => "u".to_string(),
+                _ => format!("u{}", r),
+            };
+        }
+        return rest.to_string();
+    }
+
+    // j/q/x 后的 u 实为 ü
+    if matches!(initial, "j" | "q" | "x") {
+        if let Some(r) = rest.strip_prefix('u') {
+            return format!("ü{}", r);
+        }
+    }
+
+    match rest {
+        "iu" => "iou".to_string(),
+        "ui" => "uei".to_string(),
+        "un" => "uen".to_string(),
+        _ => rest.to_string(),
+    }
+}
+
+fn tone_mark(tone: u8) -> &'static str {
+    match tone {
+        1 => "→",
+        2 => "↗",
+        3 => "↓",
+        4 => "↘",
+        _ => "",
+    }
+}
+
+/// 单个拼音音节 → Kokoro 音素
+fn syllable_to_ipa(syllable: &Syllable) -> String {
+    let base = syllable.base.as_str();
+    let (initial, initial_ipa) = INITIALS
+        .iter()
+        .find(|(py, _)| base.starts_with(py) && base.len() > py.len())
+        .map(|&(py, ipa)| (py, ipa))
+        .unwrap_or(("", ""));
+
+    let rest = &base[initial.len()..];
+    let final_ = expand_final(initial, rest);
+
+    // zh/ch/sh/r/z/c/s 后的 -i 是舌尖元音
+    let final_ipa = if final_ == "i" && matches!(initial, "zh" | "ch" | "sh" | "r" | "z" | "c" | "s") {
+        "ɨ"
+    } else {
+        match final_to_ipa(&final_) {
+            Some(ipa) => ipa,
+            None => {
+                warn!("⚠️ 无法识别的拼音: {}", base);
+                return String::new();
+            }
+        }
+    };
+
+    format!("{}{}{}", initial_ipa, final_ipa, tone_mark(syllable.tone))
+}
+
+fn han_words_to_ipa(words: &mut Vec<Vec<HanSyllable>>, out: &mut Vec<String>) {
+    apply_tone_sandhi(words);
+    for word in words.drain(..) {
+        let ipa: String = word.iter().map(|h| syllable_to_ipa(&h.syllable)).collect();
+        if !ipa.is_empty() {
+            out.push(ipa);
+        }
+    }
+}
+
+/// 中文文本 → Kokoro 音素串
+///
+/// 非汉字片段 (英文单词等) 交给 `fallback` 处理; 词典中没有读音的汉字被丢弃, 记入 `unresolved`。
+pub fn phonemize<F>(text: &str, mut fallback: F, unresolved: &mut Vec<char>) -> String
+where
+    F: FnMut(&str) -> String,
+{
+    let normalized = normalize(text);
+
+    let mut pieces: Vec<String> = Vec::new();
+    let mut han_run: Vec<Vec<HanSyllable>> = Vec::new();
+
+    for word in JIEBA.cut(&normalized, true) {
+        if word.chars().all(is_han) {
+            let syllables = lookup_word(word, unresolved);
+            if !syllables.is_empty() {
+                han_run.push(syllables);
+            }
+            continue;
+        }
+
+        han_words_to_ipa(&mut han_run, &mut pieces);
+
+        if word.trim().is_empty() {
+            continue;
+        }
+
+        let mut other = String::new();
+        for c in word.chars() {
+            match map_cjk_punctuation(c) {
+                Some(p) => {
+                    if !other.is_empty() {
+                        pieces.push(fallback(&other));
+                        other.clear();
+                    }
+                    // 标点贴在前一个词后面
+                    match pieces.last_mut() {
+                        Some(last) => last.push_str(p),
+                        None => pieces.push(p.to_string()),
+                    }
+                }
+                None => other.push(c),
+            }
+        }
+        if !other.trim().is_empty() {
+            pieces.push(fallback(other.trim()));
+        }
+    }
+    han_words_to_ipa(&mut han_run, &mut pieces);
+
+    pieces
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phonemize_zh(text: &str) -> String {
+        phonemize(text, |other| format!("<{}>", other), &mut Vec::new())
+    }
+
+    #[test]
+    fn test_read_cardinal() {
+        assert_eq!(read_cardinal(0), "零");
+        assert_eq!(read_cardinal(15), "十五");
+        assert_eq!(read_cardinal(105), "一百零五");
+        assert_eq!(read_cardinal(2025), "二千零二十五");
+        assert_eq!(read_cardinal(100_000), "十万");
+        assert_eq!(read_cardinal(100_500), "十万零五百");
+        assert_eq!(read_cardinal(120_000_003), "一亿二千万零三");
+    }
+
+    #[test]
+    fn test_normalize_numbers_and_dates() {
+        assert_eq!(normalize("2025年"), "二零二五年");
+        assert_eq!(normalize("2025年10月18日"), "二零二五年十月十八日");
+        assert_eq!(normalize("2025-10-18"), "二零二五年十月十八日");
+        assert_eq!(normalize("10:05"), "十点零五分");
+        assert_eq!(normalize("50%"), "百分之五十");
+        assert_eq!(normalize("3.14"), "三点一四");
+        assert_eq!(normalize("-5度"), "负五度");
+        assert_eq!(normalize("０１０"), "零一零");
+    }
+
+    #[test]
+    fn test_tables_load() {
+        assert!(CHAR_PINYIN.len() > 1000);
+        assert_eq!(CHAR_PINYIN.get(&'中'), Syllable::parse("zhong1").as_ref());
+        assert!(PHRASE_PINYIN.contains_key("银行"));
+    }
+
+    #[test]
+    fn test_common_char_coverage() {
+        let common = include_str!("../data/zh/common_chars.txt");
+        let chars: Vec<char> = common.lines().filter(|l| !l.starts_with('#')).flat_map(str::chars).collect();
+        assert_eq!(chars.len(), 3500);
+        let missing: String = chars.iter().filter(|c| !CHAR_PINYIN.contains_key(c)).collect();
+        assert!(missing.is_empty(), "拼音词典缺少常用字: {}", missing);
+
+        // 每个默认读音都能转成音素
+        for (ch, syllable) in CHAR_PINYIN.iter() {
+            assert!(!syllable_to_ipa(syllable).is_empty(), "{} {:?}", ch, syllable);
+        }
+    }
+
+    #[test]
+    fn test_default_readings() {
+        let pinyin = |c: char| CHAR_PINYIN.get(&c).map(|s| format!("{}{}", s.base, s.tone));
+        for (c, expected) in [('按', "an4"), ('每', "mei3"), ('已', "yi3"), ('只', "zhi3"), ('更', "geng4"), ('园', "yuan2"), ('散', "san4")] {
+            assert_eq!(pinyin(c).as_deref(), Some(expected), "{}", c);
+        }
+    }
+
+    #[test]
+    fn test_unresolved_chars() {
+        let mut unresolved = Vec::new();
+        let out = phonemize("你好䶮", |other| other.to_string(), &mut unresolved);
+        assert_eq!(out, "ni↗xau↓");
+        assert_eq!(unresolved, vec!['䶮']);
+    }
+
+    #[test]
+    fn test_syllable_to_ipa() {
+        let ipa = |s: &str| syllable_to_ipa(&Syllable::parse(s).unwrap());
+        assert_eq!(ipa("ma1"), "ma→");
+        assert_eq!(ipa("zhong1"), "ꭧʊŋ→");
+        assert_eq!(ipa("shi4"), "ʂɨ↘");
+        assert_eq!(ipa("ju3"), "ʨy↓");
+        assert_eq!(ipa("yue4"), "ɥɛ↘");
+        assert_eq!(ipa("gui4"), "kwei↘");
+        assert_eq!(ipa("wo3"), "wo↓");
+        assert_eq!(ipa("lv4"), "ly↘");
+        assert_eq!(ipa("de5"), "tɤ");
+    }
+
+    #[test]
+    fn test_polyphone_phrase() {
+        // 银行 的 "行" 读 hang2
+        assert_eq!(phonemize_zh("银行"), "in↗xaŋ↗");
+        assert_eq!(phonemize_zh("行"), "ɕiŋ↗");
+    }
+
+    #[test]
+    fn test_third_tone_sandhi() {
+        // 你好: ni3 hao3 → ni2 hao3
+        assert_eq!(phonemize_zh("你好"), "ni↗xau↓");
+    }
+
+    #[test]
+    fn test_yi_bu_sandhi() {
+        // 一个 → yi2 ge4, 一天 → yi4 tian1, 不是 → bu2 shi4
+        assert!(phonemize_zh("一个").starts_with("i↗"));
+        assert!(phonemize_zh("一天").starts_with("i↘"));
+        assert!(phonemize_zh("不是").starts_with("pu↗"));
+        // 数字里的 "一" 不变调
+        assert!(phonemize_zh("第一").ends_with("i→"));
+    }
+
+    #[test]
+    fn test_punctuation_and_fallback() {
+        let out = phonemize_zh("你好，Kokoro！");
+        assert!(out.contains(','));
+        assert!(out.contains("<Kokoro>"));
+        assert!(out.ends_with('!'));
+    }
+}